@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 
 // was vibing while 'coding' this one too, iykwim.
-use reqwest;
+use chrono::NaiveDate;
 use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
 use std::error::Error;
@@ -20,11 +20,13 @@ where
 }
 
 // Your Ohlcv related structs (assuming they work, not directly related to this options issue)
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Clone)]
 pub struct Ohlcv {
-    #[serde(rename = "4. close", deserialize_with = "deserialize_string_to_f64")]
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
     pub close: f64,
-    pub date: String,
+    pub date: NaiveDate,
 }
 
 #[derive(Debug, Deserialize)]
@@ -53,22 +55,23 @@ pub fn transform_raw_data_to_ohlcv_vec(
     raw_time_series_map: HashMap<String, RawDailyData>,
 ) -> Result<Vec<Ohlcv>, Box<dyn Error>> {
     let mut ohlcv_points: Vec<Ohlcv> = Vec::new();
-    let mut sorted_dates: Vec<&String> = raw_time_series_map.keys().collect();
-    sorted_dates.sort_unstable();
-    for date_str in sorted_dates {
-        if let Some(raw_daily_data) = raw_time_series_map.get(date_str) {
-            let ohlcv_point = Ohlcv {
-                date: date_str.clone(),
-                close: raw_daily_data.close,
-            };
-            ohlcv_points.push(ohlcv_point);
-        }
+    for (date_str, raw_daily_data) in raw_time_series_map {
+        let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid OHLCV date '{}': {}", date_str, e))?;
+        ohlcv_points.push(Ohlcv {
+            open: raw_daily_data.open,
+            high: raw_daily_data.high,
+            low: raw_daily_data.low,
+            close: raw_daily_data.close,
+            date,
+        });
     }
+    ohlcv_points.sort_unstable_by_key(|ohlcv| ohlcv.date);
     Ok(ohlcv_points)
 }
 
 // --- Options related structs ---
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum OptionType {
     Put,
     Call,
@@ -137,11 +140,22 @@ pub struct OptionsData {
     pub symbol: String,
     pub contract: String,
     pub contract_type: OptionType,
-    pub expiration: String,
-    pub date: String,
+    pub expiration: NaiveDate,
+    pub date: NaiveDate,
     pub strike: f64,
     pub last: f64,
+    pub mark: f64,
+    pub bid: f64,
+    pub ask: f64,
+    pub volume: f64,
+    pub open_interest: f64,
     pub implied_volatility: f64,
+    // Vendor greeks, kept so they can be checked against our own pricing.
+    pub delta: f64,
+    pub gamma: f64,
+    pub theta: f64,
+    pub vega: f64,
+    pub rho: f64,
 }
 
 // Transform `AlphaVantageOptionsRawResponse` into `Vec<OptionsData>`
@@ -166,17 +180,41 @@ pub fn transform_raw_options_to_options_data(
             }
         };
 
-        // Filter out bad data points (e.g., zero implied volatility or last price)
-        if entry.implied_volatility > 0.0 && entry.last > 0.0 {
+        let (Ok(expiration), Ok(date)) = (
+            NaiveDate::parse_from_str(&entry.expiration, "%Y-%m-%d"),
+            NaiveDate::parse_from_str(&entry.date, "%Y-%m-%d"),
+        ) else {
+            eprintln!(
+                "Warning: Invalid date '{}' or expiration '{}' for contract {}",
+                entry.date, entry.expiration, entry.contract_id
+            );
+            continue;
+        };
+
+        // Filter out contracts with no usable price. A zero vendor IV is kept, since
+        // we can invert the price ourselves (see implied_vol.rs).
+        let has_price =
+            entry.last > 0.0 || entry.mark > 0.0 || (entry.bid > 0.0 && entry.ask > 0.0);
+        if has_price {
             options_data_vec.push(OptionsData {
                 symbol: entry.symbol.clone(), // Use entry.symbol here
                 contract: entry.contract_id,  // Use contract_id directly
-                contract_type: contract_type,
-                expiration: entry.expiration,
-                date: entry.date,
+                contract_type,
+                expiration,
+                date,
                 strike: entry.strike,
                 last: entry.last,
+                mark: entry.mark,
+                bid: entry.bid,
+                ask: entry.ask,
+                volume: entry.volume,
+                open_interest: entry.open_interest,
                 implied_volatility: entry.implied_volatility,
+                delta: entry.delta,
+                gamma: entry.gamma,
+                theta: entry.theta,
+                vega: entry.vega,
+                rho: entry.rho,
             });
         }
     }
@@ -186,7 +224,7 @@ pub fn transform_raw_options_to_options_data(
 pub async fn options_data(
     key: String,
     symbol: String, // Note: The actual response includes symbol per entry, not necessarily top-level.
-    date: String,
+    date: NaiveDate,
 ) -> Result<Vec<OptionsData>, Box<dyn Error>> {
     let url = format!("https://www.alphavantage.co/query?function=HISTORICAL_OPTIONS&symbol={symbol}&date={date}&apikey={key}");
     let response_text = reqwest::get(&url).await?.text().await?;
@@ -0,0 +1,601 @@
+// Data-quality audit of the price history and option chains before anything
+// is computed from them. A zero close becomes ln(0) in the vol estimators and
+// a duplicated or missing day silently shifts every window, so these are
+// reported up front. Issues are errors when they break the calculations and
+// warnings when they only make the results less trustworthy.
+
+use crate::api::{Ohlcv, OptionType, OptionsData};
+use crate::calendar::trading_days;
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PriceIssue {
+    // A trading day on the exchange calendar with no bar.
+    MissingTradingDay {
+        date: NaiveDate,
+    },
+    // A bar on a weekend or exchange holiday.
+    NonTradingDay {
+        date: NaiveDate,
+    },
+    DuplicateDate {
+        date: NaiveDate,
+        count: usize,
+    },
+    OutOfOrder {
+        date: NaiveDate,
+        previous: NaiveDate,
+    },
+    NonPositivePrice {
+        date: NaiveDate,
+        close: f64,
+    },
+    // The same close on `days` consecutive bars.
+    StalePrice {
+        from: NaiveDate,
+        to: NaiveDate,
+        days: usize,
+        close: f64,
+    },
+    // A daily log return more than the threshold number of robust standard
+    // deviations (1.4826 * MAD) from the median.
+    ReturnOutlier {
+        date: NaiveDate,
+        log_return: f64,
+        robust_z: f64,
+    },
+}
+
+impl PriceIssue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            PriceIssue::DuplicateDate { .. }
+            | PriceIssue::OutOfOrder { .. }
+            | PriceIssue::NonPositivePrice { .. } => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PriceIssue::MissingTradingDay { .. } => "missing trading day",
+            PriceIssue::NonTradingDay { .. } => "non-trading day",
+            PriceIssue::DuplicateDate { .. } => "duplicate date",
+            PriceIssue::OutOfOrder { .. } => "out of order",
+            PriceIssue::NonPositivePrice { .. } => "non-positive price",
+            PriceIssue::StalePrice { .. } => "stale price",
+            PriceIssue::ReturnOutlier { .. } => "return outlier",
+        }
+    }
+}
+
+impl fmt::Display for PriceIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceIssue::MissingTradingDay { date } => {
+                write!(f, "{}: no bar on a trading day", date)
+            }
+            PriceIssue::NonTradingDay { date } => write!(f, "{}: bar on a non-trading day", date),
+            PriceIssue::DuplicateDate { date, count } => {
+                write!(f, "{}: {} bars for the same date", date, count)
+            }
+            PriceIssue::OutOfOrder { date, previous } => {
+                write!(f, "{}: follows the later date {}", date, previous)
+            }
+            PriceIssue::NonPositivePrice { date, close } => {
+                write!(f, "{}: non-positive close {}", date, close)
+            }
+            PriceIssue::StalePrice {
+                from,
+                to,
+                days,
+                close,
+            } => write!(
+                f,
+                "{} to {}: close unchanged at {} for {} bars",
+                from, to, close, days
+            ),
+            PriceIssue::ReturnOutlier {
+                date,
+                log_return,
+                robust_z,
+            } => write!(
+                f,
+                "{}: log return {:.4} is {:.1} robust SDs from the median",
+                date, log_return, robust_z
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainIssueKind {
+    // Bid above ask.
+    CrossedQuote { bid: f64, ask: f64 },
+    // No bid and no ask.
+    EmptyQuote,
+    // IV far from the median of the nearest four strikes of the same type and
+    // expiry. The median keeps one spike from flagging its neighbours.
+    IvSpike { iv: f64, neighbour_iv: f64 },
+    // A quoted IV but no vendor greeks, or non-finite ones.
+    MissingGreeks,
+    // `count` contracts with the same expiry, strike and type. Indexing the
+    // chain keeps only the last of them.
+    DuplicateContract { count: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainIssue {
+    pub date: NaiveDate,
+    pub contract: String,
+    pub kind: ChainIssueKind,
+}
+
+impl ChainIssue {
+    pub fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self.kind {
+            ChainIssueKind::CrossedQuote { .. } => "crossed quote",
+            ChainIssueKind::EmptyQuote => "empty quote",
+            ChainIssueKind::IvSpike { .. } => "IV spike",
+            ChainIssueKind::MissingGreeks => "missing greeks",
+            ChainIssueKind::DuplicateContract { .. } => "duplicate contract",
+        }
+    }
+}
+
+impl fmt::Display for ChainIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: ", self.date, self.contract)?;
+        match &self.kind {
+            ChainIssueKind::CrossedQuote { bid, ask } => write!(f, "bid {} above ask {}", bid, ask),
+            ChainIssueKind::EmptyQuote => write!(f, "no bid or ask"),
+            ChainIssueKind::IvSpike { iv, neighbour_iv } => write!(
+                f,
+                "IV {:.4} against {:.4} at the neighbouring strikes",
+                iv, neighbour_iv
+            ),
+            ChainIssueKind::MissingGreeks => write!(f, "IV quoted without greeks"),
+            ChainIssueKind::DuplicateContract { count } => {
+                write!(f, "{} quotes for the same expiry, strike and type", count)
+            }
+        }
+    }
+}
+
+// What to do when the audit finds problems.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditPolicy {
+    // Print the report and carry on.
+    Report,
+    // The stricter policies are chosen through the audit_policy config in main.
+    #[allow(dead_code)]
+    FailOnErrors,
+    #[allow(dead_code)]
+    FailOnWarnings,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DataAudit {
+    // Consecutive identical closes before a run is reported as stale.
+    pub stale_run_days: usize,
+    // Robust z-score above which a daily return is an outlier.
+    pub outlier_threshold: f64,
+    // Absolute IV difference from the neighbouring strikes that counts as a spike.
+    pub iv_spike_threshold: f64,
+}
+
+impl Default for DataAudit {
+    fn default() -> Self {
+        DataAudit {
+            stale_run_days: 3,
+            outlier_threshold: 8.0,
+            iv_spike_threshold: 0.15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditReport {
+    pub prices: Vec<PriceIssue>,
+    pub chains: Vec<ChainIssue>,
+    pub chains_audited: usize,
+    pub contracts_audited: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditFailure {
+    pub policy: AuditPolicy,
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+impl fmt::Display for AuditFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "data audit failed under {:?}: {} errors, {} warnings",
+            self.policy, self.errors, self.warnings
+        )
+    }
+}
+
+impl std::error::Error for AuditFailure {}
+
+impl AuditReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.prices
+            .iter()
+            .filter(|issue| issue.severity() == severity)
+            .count()
+            + self
+                .chains
+                .iter()
+                .filter(|issue| issue.severity() == severity)
+                .count()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.prices.is_empty() && self.chains.is_empty()
+    }
+
+    pub fn enforce(&self, policy: AuditPolicy) -> Result<(), AuditFailure> {
+        let errors = self.count(Severity::Error);
+        let warnings = self.count(Severity::Warning);
+        let failed = match policy {
+            AuditPolicy::Report => false,
+            AuditPolicy::FailOnErrors => errors > 0,
+            AuditPolicy::FailOnWarnings => errors + warnings > 0,
+        };
+        if failed {
+            Err(AuditFailure {
+                policy,
+                errors,
+                warnings,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// Counts per issue kind, then every price issue. Chain issues can run into
+// the thousands, so only their counts are shown.
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for issue in &self.prices {
+            *counts.entry(issue.kind()).or_default() += 1;
+        }
+        let price_counts: Vec<String> = counts
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect();
+        writeln!(
+            f,
+            "Price data: {} issues ({} errors){}",
+            self.prices.len(),
+            self.prices
+                .iter()
+                .filter(|issue| issue.severity() == Severity::Error)
+                .count(),
+            if price_counts.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", price_counts.join(", "))
+            }
+        )?;
+        for issue in &self.prices {
+            writeln!(f, "  {:?} {}", issue.severity(), issue)?;
+        }
+
+        counts.clear();
+        for issue in &self.chains {
+            *counts.entry(issue.kind()).or_default() += 1;
+        }
+        let chain_counts: Vec<String> = counts
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect();
+        write!(
+            f,
+            "Option chains: {} issues over {} contracts on {} dates{}",
+            self.chains.len(),
+            self.contracts_audited,
+            self.chains_audited,
+            if chain_counts.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", chain_counts.join(", "))
+            }
+        )
+    }
+}
+
+fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let middle = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        0.5 * (values[middle - 1] + values[middle])
+    } else {
+        values[middle]
+    })
+}
+
+impl DataAudit {
+    // Price issues in the order found: calendar, duplicates and ordering,
+    // then prices, stale runs and return outliers.
+    pub fn audit_prices(&self, data: &[Ohlcv]) -> Vec<PriceIssue> {
+        let mut issues = Vec::new();
+        if data.is_empty() {
+            return issues;
+        }
+
+        let mut counts: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        for bar in data {
+            *counts.entry(bar.date).or_default() += 1;
+        }
+        let (Some(&first), Some(&last)) = (counts.keys().next(), counts.keys().next_back()) else {
+            return issues;
+        };
+        let calendar_days = trading_days(first, last);
+        let calendar: HashSet<NaiveDate> = calendar_days.iter().copied().collect();
+        for &date in &calendar_days {
+            if !counts.contains_key(&date) {
+                issues.push(PriceIssue::MissingTradingDay { date });
+            }
+        }
+        for (&date, &count) in &counts {
+            if !calendar.contains(&date) {
+                issues.push(PriceIssue::NonTradingDay { date });
+            }
+            if count > 1 {
+                issues.push(PriceIssue::DuplicateDate { date, count });
+            }
+        }
+
+        for pair in data.windows(2) {
+            if pair[1].date < pair[0].date {
+                issues.push(PriceIssue::OutOfOrder {
+                    date: pair[1].date,
+                    previous: pair[0].date,
+                });
+            }
+        }
+
+        for bar in data {
+            if !(bar.close.is_finite() && bar.close > 0.0) {
+                issues.push(PriceIssue::NonPositivePrice {
+                    date: bar.date,
+                    close: bar.close,
+                });
+            }
+        }
+
+        let mut run_start = 0;
+        for i in 1..=data.len() {
+            if i < data.len() && data[i].close == data[run_start].close {
+                continue;
+            }
+            let days = i - run_start;
+            if days >= self.stale_run_days.max(2) && data[run_start].close > 0.0 {
+                issues.push(PriceIssue::StalePrice {
+                    from: data[run_start].date,
+                    to: data[i - 1].date,
+                    days,
+                    close: data[run_start].close,
+                });
+            }
+            run_start = i;
+        }
+
+        let returns: Vec<(NaiveDate, f64)> = data
+            .windows(2)
+            .filter(|pair| pair[0].close > 0.0 && pair[1].close > 0.0)
+            .map(|pair| (pair[1].date, (pair[1].close / pair[0].close).ln()))
+            .filter(|(_, r)| r.is_finite())
+            .collect();
+        let mut values: Vec<f64> = returns.iter().map(|(_, r)| *r).collect();
+        if let Some(center) = median(&mut values) {
+            let mut deviations: Vec<f64> = values.iter().map(|r| (r - center).abs()).collect();
+            let scale = 1.4826 * median(&mut deviations).unwrap_or(0.0);
+            if scale > 0.0 {
+                for (date, log_return) in returns {
+                    let robust_z = (log_return - center) / scale;
+                    if robust_z.abs() > self.outlier_threshold {
+                        issues.push(PriceIssue::ReturnOutlier {
+                            date,
+                            log_return,
+                            robust_z,
+                        });
+                    }
+                }
+            }
+        }
+        issues
+    }
+
+    // Audits a day's contracts as fetched, before they are indexed into an
+    // `OptionChain`, so duplicates are still visible.
+    pub fn audit_chain(&self, options: &[OptionsData]) -> Vec<ChainIssue> {
+        let mut issues = Vec::new();
+        let issue = |option: &OptionsData, kind| ChainIssue {
+            date: option.date,
+            contract: option.contract.clone(),
+            kind,
+        };
+
+        let mut counts: HashMap<(NaiveDate, u64, OptionType), (usize, &OptionsData)> =
+            HashMap::new();
+        for option in options {
+            counts
+                .entry((
+                    option.expiration,
+                    option.strike.to_bits(),
+                    option.contract_type,
+                ))
+                .or_insert((0, option))
+                .0 += 1;
+        }
+        let mut duplicates: Vec<_> = counts
+            .into_values()
+            .filter(|(count, _)| *count > 1)
+            .collect();
+        duplicates.sort_by(|a, b| a.1.contract.cmp(&b.1.contract));
+        for (count, option) in duplicates {
+            issues.push(issue(option, ChainIssueKind::DuplicateContract { count }));
+        }
+
+        for option in options {
+            if option.bid <= 0.0 && option.ask <= 0.0 {
+                issues.push(issue(option, ChainIssueKind::EmptyQuote));
+            } else if option.ask > 0.0 && option.bid > option.ask {
+                issues.push(issue(
+                    option,
+                    ChainIssueKind::CrossedQuote {
+                        bid: option.bid,
+                        ask: option.ask,
+                    },
+                ));
+            }
+            let greeks = [
+                option.delta,
+                option.gamma,
+                option.theta,
+                option.vega,
+                option.rho,
+            ];
+            if option.implied_volatility > 0.0
+                && (greeks.iter().all(|g| *g == 0.0) || greeks.iter().any(|g| !g.is_finite()))
+            {
+                issues.push(issue(option, ChainIssueKind::MissingGreeks));
+            }
+        }
+
+        // Quoted contracts grouped by expiry and type, each group by strike.
+        let mut quoted: Vec<&OptionsData> = options
+            .iter()
+            .filter(|option| option.implied_volatility > 0.0)
+            .collect();
+        quoted.sort_by(|a, b| {
+            (a.expiration, a.contract_type == OptionType::Call)
+                .cmp(&(b.expiration, b.contract_type == OptionType::Call))
+                .then(a.strike.total_cmp(&b.strike))
+        });
+        for group in quoted
+            .chunk_by(|a, b| a.expiration == b.expiration && a.contract_type == b.contract_type)
+        {
+            for (i, option) in group.iter().enumerate() {
+                // Five strikes around this one, shifted inwards at the ends.
+                let start = i.saturating_sub(2).min(group.len().saturating_sub(5));
+                let mut neighbours: Vec<f64> = (start..group.len().min(start + 5))
+                    .filter(|&j| j != i)
+                    .map(|j| group[j].implied_volatility)
+                    .collect();
+                let Some(neighbour_iv) = median(&mut neighbours) else {
+                    continue;
+                };
+                if (option.implied_volatility - neighbour_iv).abs() > self.iv_spike_threshold {
+                    issues.push(issue(
+                        option,
+                        ChainIssueKind::IvSpike {
+                            iv: option.implied_volatility,
+                            neighbour_iv,
+                        },
+                    ));
+                }
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract(strike: f64, contract_type: OptionType, iv: f64) -> OptionsData {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        OptionsData {
+            symbol: "SPY".to_string(),
+            contract: format!("SPY{:?}{}", contract_type, strike),
+            contract_type,
+            expiration: NaiveDate::from_ymd_opt(2024, 3, 15).unwrap(),
+            date,
+            strike,
+            last: 1.0,
+            mark: 1.0,
+            bid: 0.9,
+            ask: 1.1,
+            volume: 10.0,
+            open_interest: 100.0,
+            implied_volatility: iv,
+            delta: 0.5,
+            gamma: 0.01,
+            theta: -0.02,
+            vega: 0.1,
+            rho: 0.01,
+        }
+    }
+
+    #[test]
+    fn duplicate_contracts_are_reported() {
+        let options = vec![
+            contract(100.0, OptionType::Call, 0.2),
+            contract(100.0, OptionType::Put, 0.2),
+            contract(100.0, OptionType::Call, 0.2),
+        ];
+        let issues = DataAudit::default().audit_chain(&options);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].kind,
+            ChainIssueKind::DuplicateContract { count: 2 }
+        );
+    }
+
+    #[test]
+    fn iv_spike_is_flagged_against_its_own_type() {
+        let mut options: Vec<OptionsData> = (0..7)
+            .map(|i| contract(90.0 + 5.0 * i as f64, OptionType::Call, 0.2))
+            .collect();
+        options[3].implied_volatility = 0.5;
+        // Puts at very different IVs must not count as neighbours.
+        options.extend((0..7).map(|i| contract(90.0 + 5.0 * i as f64, OptionType::Put, 0.6)));
+        let issues = DataAudit::default().audit_chain(&options);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].contract, options[3].contract);
+    }
+
+    #[test]
+    fn unordered_and_non_positive_prices_are_errors() {
+        let bar = |day: u32, close: f64| Ohlcv {
+            open: close,
+            high: close,
+            low: close,
+            close,
+            date: NaiveDate::from_ymd_opt(2024, 3, day).unwrap(),
+        };
+        let data = [bar(4, 100.0), bar(6, 101.0), bar(5, 0.0)];
+        let report = AuditReport {
+            prices: DataAudit::default().audit_prices(&data),
+            ..AuditReport::default()
+        };
+        assert_eq!(report.count(Severity::Error), 2);
+        assert!(report.enforce(AuditPolicy::FailOnErrors).is_err());
+        assert!(report.enforce(AuditPolicy::Report).is_ok());
+    }
+}
@@ -0,0 +1,343 @@
+// Black-Scholes-Merton pricing with a continuous dividend yield, plus first-
+// and second-order greeks. Greeks are in raw units: vega and rho per 1.0
+// change (not per 1%), theta per year. Vendor theta is usually per calendar
+// day and vendor vega per vol point, so scale before comparing.
+
+use crate::api::{OptionType, OptionsData};
+use crate::stats::{normal_cdf, normal_pdf};
+
+#[derive(Debug, Clone, Copy)]
+pub struct PricingInputs {
+    pub option_type: OptionType,
+    pub spot: f64,
+    pub strike: f64,
+    // Year fraction, ACT/365.
+    pub time_to_expiry: f64,
+    // Continuously compounded risk-free rate.
+    pub rate: f64,
+    // Continuously compounded dividend (or borrow) yield.
+    pub dividend_yield: f64,
+    pub volatility: f64,
+}
+
+impl PricingInputs {
+    pub fn with_volatility(&self, volatility: f64) -> Self {
+        PricingInputs {
+            volatility,
+            ..*self
+        }
+    }
+
+    pub fn forward(&self) -> f64 {
+        self.spot * ((self.rate - self.dividend_yield) * self.time_to_expiry).exp()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Greeks {
+    // dV/dS
+    pub delta: f64,
+    // dV/dsigma
+    pub vega: f64,
+    // dV/dt, calendar time, per year.
+    pub theta: f64,
+    // dV/dr
+    pub rho: f64,
+    // dV/dq
+    pub dividend_rho: f64,
+    // d2V/dS2
+    pub gamma: f64,
+    // d2V/dS dsigma
+    pub vanna: f64,
+    // d2V/dsigma2
+    pub volga: f64,
+    // d(delta)/dt, per year.
+    pub charm: f64,
+    // d(vega)/dt, per year.
+    pub veta: f64,
+}
+
+// Common interface for option pricers, so European and American models can be
+// swapped in the IV solver and chain analytics.
+pub trait OptionPricer {
+    fn price(&self, inputs: &PricingInputs) -> f64;
+    fn greeks(&self, inputs: &PricingInputs) -> Greeks;
+
+    // Vega alone, for solvers. Override when the full greeks are expensive.
+    fn vega(&self, inputs: &PricingInputs) -> f64 {
+        self.greeks(inputs).vega
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlackScholes;
+
+struct Terms {
+    d1: f64,
+    d2: f64,
+    sqrt_t: f64,
+    spot_discount: f64,
+    strike_discount: f64,
+}
+
+fn terms(inputs: &PricingInputs) -> Terms {
+    let sqrt_t = inputs.time_to_expiry.sqrt();
+    let sigma_sqrt_t = inputs.volatility * sqrt_t;
+    let d1 = ((inputs.spot / inputs.strike).ln()
+        + (inputs.rate - inputs.dividend_yield + 0.5 * inputs.volatility.powi(2))
+            * inputs.time_to_expiry)
+        / sigma_sqrt_t;
+    Terms {
+        d1,
+        d2: d1 - sigma_sqrt_t,
+        sqrt_t,
+        spot_discount: (-inputs.dividend_yield * inputs.time_to_expiry).exp(),
+        strike_discount: (-inputs.rate * inputs.time_to_expiry).exp(),
+    }
+}
+
+// True when the model collapses to (discounted) intrinsic value.
+fn is_degenerate(inputs: &PricingInputs) -> bool {
+    inputs.time_to_expiry <= 0.0 || inputs.volatility <= 0.0
+}
+
+fn degenerate_price(inputs: &PricingInputs) -> f64 {
+    let t = inputs.time_to_expiry.max(0.0);
+    let discounted_spot = inputs.spot * (-inputs.dividend_yield * t).exp();
+    let discounted_strike = inputs.strike * (-inputs.rate * t).exp();
+    match inputs.option_type {
+        OptionType::Call => (discounted_spot - discounted_strike).max(0.0),
+        OptionType::Put => (discounted_strike - discounted_spot).max(0.0),
+    }
+}
+
+impl OptionPricer for BlackScholes {
+    fn price(&self, inputs: &PricingInputs) -> f64 {
+        if is_degenerate(inputs) {
+            return degenerate_price(inputs);
+        }
+        let t = terms(inputs);
+        match inputs.option_type {
+            OptionType::Call => {
+                inputs.spot * t.spot_discount * normal_cdf(t.d1)
+                    - inputs.strike * t.strike_discount * normal_cdf(t.d2)
+            }
+            OptionType::Put => {
+                inputs.strike * t.strike_discount * normal_cdf(-t.d2)
+                    - inputs.spot * t.spot_discount * normal_cdf(-t.d1)
+            }
+        }
+    }
+
+    fn greeks(&self, inputs: &PricingInputs) -> Greeks {
+        if is_degenerate(inputs) {
+            let t = inputs.time_to_expiry.max(0.0);
+            let forward = inputs.spot * ((inputs.rate - inputs.dividend_yield) * t).exp();
+            let in_the_money = match inputs.option_type {
+                OptionType::Call => forward > inputs.strike,
+                OptionType::Put => forward < inputs.strike,
+            };
+            let delta = match (inputs.option_type, in_the_money) {
+                (OptionType::Call, true) => (-inputs.dividend_yield * t).exp(),
+                (OptionType::Put, true) => -(-inputs.dividend_yield * t).exp(),
+                _ => 0.0,
+            };
+            return Greeks {
+                delta,
+                ..Greeks::default()
+            };
+        }
+
+        let t = terms(inputs);
+        let s = inputs.spot;
+        let k = inputs.strike;
+        let sigma = inputs.volatility;
+        let tau = inputs.time_to_expiry;
+        let r = inputs.rate;
+        let q = inputs.dividend_yield;
+        let pdf_d1 = normal_pdf(t.d1);
+
+        let gamma = t.spot_discount * pdf_d1 / (s * sigma * t.sqrt_t);
+        let vega = s * t.spot_discount * pdf_d1 * t.sqrt_t;
+        let vanna = -t.spot_discount * pdf_d1 * t.d2 / sigma;
+        let volga = vega * t.d1 * t.d2 / sigma;
+        let veta = s
+            * t.spot_discount
+            * pdf_d1
+            * t.sqrt_t
+            * (q + (r - q) * t.d1 / (sigma * t.sqrt_t) - (1.0 + t.d1 * t.d2) / (2.0 * tau));
+        let charm_common =
+            t.spot_discount * pdf_d1 * (2.0 * (r - q) * tau - t.d2 * sigma * t.sqrt_t)
+                / (2.0 * tau * sigma * t.sqrt_t);
+        let theta_common = -s * t.spot_discount * pdf_d1 * sigma / (2.0 * t.sqrt_t);
+
+        match inputs.option_type {
+            OptionType::Call => Greeks {
+                delta: t.spot_discount * normal_cdf(t.d1),
+                vega,
+                theta: theta_common - r * k * t.strike_discount * normal_cdf(t.d2)
+                    + q * s * t.spot_discount * normal_cdf(t.d1),
+                rho: k * tau * t.strike_discount * normal_cdf(t.d2),
+                dividend_rho: -s * tau * t.spot_discount * normal_cdf(t.d1),
+                gamma,
+                vanna,
+                volga,
+                charm: q * t.spot_discount * normal_cdf(t.d1) - charm_common,
+                veta,
+            },
+            OptionType::Put => Greeks {
+                delta: -t.spot_discount * normal_cdf(-t.d1),
+                vega,
+                theta: theta_common + r * k * t.strike_discount * normal_cdf(-t.d2)
+                    - q * s * t.spot_discount * normal_cdf(-t.d1),
+                rho: -k * tau * t.strike_discount * normal_cdf(-t.d2),
+                dividend_rho: s * tau * t.spot_discount * normal_cdf(-t.d1),
+                gamma,
+                vanna,
+                volga,
+                charm: -q * t.spot_discount * normal_cdf(-t.d1) - charm_common,
+                veta,
+            },
+        }
+    }
+}
+
+// ACT/365 year fraction between the quote date and expiration of a contract.
+pub fn time_to_expiry(option: &OptionsData) -> f64 {
+    (option.expiration - option.date).num_days() as f64 / 365.0
+}
+
+// Pricing inputs for a contract using its vendor IV.
+pub fn inputs_from_option(
+    option: &OptionsData,
+    spot: f64,
+    rate: f64,
+    dividend_yield: f64,
+) -> PricingInputs {
+    PricingInputs {
+        option_type: option.contract_type,
+        spot,
+        strike: option.strike,
+        time_to_expiry: time_to_expiry(option),
+        rate,
+        dividend_yield,
+        volatility: option.implied_volatility,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChainValuation {
+    pub contract: String,
+    pub inputs: PricingInputs,
+    pub price: f64,
+    pub greeks: Greeks,
+}
+
+// Price and greeks for every contract in a chain at its vendor IV.
+pub fn value_chain(
+    pricer: &dyn OptionPricer,
+    chain: &[OptionsData],
+    spot: f64,
+    rate: f64,
+    dividend_yield: f64,
+) -> Vec<ChainValuation> {
+    chain
+        .iter()
+        .map(|option| {
+            let inputs = inputs_from_option(option, spot, rate, dividend_yield);
+            ChainValuation {
+                contract: option.contract.clone(),
+                inputs,
+                price: pricer.price(&inputs),
+                greeks: pricer.greeks(&inputs),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(option_type: OptionType) -> PricingInputs {
+        PricingInputs {
+            option_type,
+            spot: 100.0,
+            strike: 100.0,
+            time_to_expiry: 1.0,
+            rate: 0.05,
+            dividend_yield: 0.0,
+            volatility: 0.2,
+        }
+    }
+
+    #[test]
+    fn matches_textbook_prices() {
+        let call = BlackScholes.price(&inputs(OptionType::Call));
+        let put = BlackScholes.price(&inputs(OptionType::Put));
+        assert!((call - 10.450584).abs() < 1e-5, "{}", call);
+        assert!((put - 5.573526).abs() < 1e-5, "{}", put);
+    }
+
+    #[test]
+    fn satisfies_put_call_parity_with_dividends() {
+        for strike in [80.0, 100.0, 130.0] {
+            let call = PricingInputs {
+                strike,
+                dividend_yield: 0.02,
+                ..inputs(OptionType::Call)
+            };
+            let put = PricingInputs {
+                option_type: OptionType::Put,
+                ..call
+            };
+            let parity = (-call.rate).exp() * (call.forward() - strike);
+            let difference = BlackScholes.price(&call) - BlackScholes.price(&put);
+            assert!((difference - parity).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn greeks_match_finite_differences() {
+        for option_type in [OptionType::Call, OptionType::Put] {
+            let base = PricingInputs {
+                strike: 105.0,
+                dividend_yield: 0.01,
+                ..inputs(option_type)
+            };
+            let greeks = BlackScholes.greeks(&base);
+            let bumped = |change: &dyn Fn(&mut PricingInputs, f64), h: f64| {
+                let (mut up, mut down) = (base, base);
+                change(&mut up, h);
+                change(&mut down, -h);
+                (BlackScholes.price(&up) - BlackScholes.price(&down)) / (2.0 * h)
+            };
+            // normal_cdf is good to ~1e-7, so the bumps stay wide enough for
+            // that noise to be small next to the greeks.
+            let close = |analytic: f64, numeric: f64| {
+                assert!(
+                    (analytic - numeric).abs() < 1e-4 * (1.0 + numeric.abs()),
+                    "{:?}: {} vs {}",
+                    option_type,
+                    analytic,
+                    numeric
+                )
+            };
+            close(greeks.delta, bumped(&|i, h| i.spot += h, 0.5));
+            close(greeks.vega, bumped(&|i, h| i.volatility += h, 0.01));
+            close(greeks.rho, bumped(&|i, h| i.rate += h, 0.01));
+            close(greeks.theta, -bumped(&|i, h| i.time_to_expiry += h, 0.01));
+        }
+    }
+
+    #[test]
+    fn zero_volatility_is_discounted_forward_intrinsic() {
+        let call = PricingInputs {
+            volatility: 0.0,
+            strike: 95.0,
+            ..inputs(OptionType::Call)
+        };
+        let expected = 100.0 - 95.0 * (-0.05f64).exp();
+        assert!((BlackScholes.price(&call) - expected).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,156 @@
+// Circular block bootstrap for confidence intervals on summary statistics of
+// dependent (overlapping-window) data. Data must be in time order.
+
+use crate::rng::Rng;
+use crate::stats::ConfidenceInterval;
+
+#[derive(Debug, Clone)]
+pub struct BlockBootstrap {
+    // Block length in observations. None picks ceil(n^(1/3)), but at least
+    // `min_block_length`.
+    pub block_length: Option<usize>,
+    // Floor for the automatic block length, e.g. the forecast overlap plus
+    // one so a block spans every observation an error is correlated with.
+    pub min_block_length: usize,
+    pub resamples: usize,
+    pub level: f64,
+    pub seed: u64,
+}
+
+impl Default for BlockBootstrap {
+    fn default() -> Self {
+        BlockBootstrap {
+            block_length: None,
+            min_block_length: 1,
+            resamples: 2000,
+            level: 0.95,
+            seed: 42,
+        }
+    }
+}
+
+impl BlockBootstrap {
+    pub fn block_length_for(&self, n: usize) -> usize {
+        let length = self
+            .block_length
+            .unwrap_or_else(|| ((n as f64).cbrt().ceil() as usize).max(self.min_block_length));
+        length.clamp(1, n.max(1))
+    }
+
+    // One resample of indices 0..n made of wrapped blocks of consecutive indices.
+    pub fn resample_indices(&self, n: usize, rng: &mut Rng) -> Vec<usize> {
+        let block_length = self.block_length_for(n);
+        let mut indices = Vec::with_capacity(n);
+        while indices.len() < n {
+            let start = rng.below(n);
+            for offset in 0..block_length {
+                if indices.len() == n {
+                    break;
+                }
+                indices.push((start + offset) % n);
+            }
+        }
+        indices
+    }
+
+    // Percentile interval for `statistic`. Resamples where the statistic is
+    // undefined are dropped; returns None if the statistic is undefined on the
+    // original data or on too many resamples.
+    pub fn confidence_interval<T, F>(&self, data: &[T], statistic: F) -> Option<ConfidenceInterval>
+    where
+        T: Clone,
+        F: Fn(&[T]) -> Option<f64>,
+    {
+        if data.len() < 2 || self.resamples == 0 {
+            return None;
+        }
+        let estimate = statistic(data)?;
+
+        let mut rng = Rng::new(self.seed);
+        let mut draws: Vec<f64> = Vec::with_capacity(self.resamples);
+        for _ in 0..self.resamples {
+            let resample: Vec<T> = self
+                .resample_indices(data.len(), &mut rng)
+                .into_iter()
+                .map(|i| data[i].clone())
+                .collect();
+            if let Some(value) = statistic(&resample).filter(|v| v.is_finite()) {
+                draws.push(value);
+            }
+        }
+        if draws.len() < self.resamples / 2 {
+            return None;
+        }
+        draws.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let alpha = (1.0 - self.level) / 2.0;
+        Some(ConfidenceInterval {
+            estimate,
+            lower: percentile(&draws, alpha),
+            upper: percentile(&draws, 1.0 - alpha),
+            level: self.level,
+        })
+    }
+}
+
+// Linear-interpolated percentile of sorted values, q in [0, 1].
+pub fn percentile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let position = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    let fraction = position - lower as f64;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::mean;
+
+    #[test]
+    fn percentile_interpolates_between_ranks() {
+        let sorted = [1.0, 2.0, 4.0, 8.0];
+        assert_eq!(percentile(&sorted, 0.0), 1.0);
+        assert_eq!(percentile(&sorted, 1.0), 8.0);
+        assert!((percentile(&sorted, 0.5) - 3.0).abs() < 1e-12);
+        assert!(percentile(&[], 0.5).is_nan());
+    }
+
+    #[test]
+    fn resamples_are_wrapped_blocks() {
+        let bootstrap = BlockBootstrap {
+            block_length: Some(3),
+            ..BlockBootstrap::default()
+        };
+        let mut rng = Rng::new(7);
+        let indices = bootstrap.resample_indices(10, &mut rng);
+        assert_eq!(indices.len(), 10);
+        for block in indices.chunks(3).filter(|block| block.len() == 3) {
+            assert_eq!(block[1], (block[0] + 1) % 10);
+            assert_eq!(block[2], (block[1] + 1) % 10);
+        }
+        assert_eq!(BlockBootstrap::default().block_length_for(20), 3);
+        let overlapping = BlockBootstrap {
+            min_block_length: 5,
+            ..BlockBootstrap::default()
+        };
+        assert_eq!(overlapping.block_length_for(20), 5);
+        assert_eq!(overlapping.block_length_for(3), 3);
+    }
+
+    #[test]
+    fn interval_brackets_the_estimate() {
+        let data: Vec<f64> = (0..40).map(|i| ((i * 7) % 11) as f64).collect();
+        let ci = BlockBootstrap::default()
+            .confidence_interval(&data, mean)
+            .unwrap();
+        assert!(ci.lower < ci.estimate && ci.estimate < ci.upper);
+        let constant = BlockBootstrap::default()
+            .confidence_interval(&[2.0; 10], mean)
+            .unwrap();
+        assert_eq!((constant.lower, constant.upper), (2.0, 2.0));
+    }
+}
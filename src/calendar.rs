@@ -0,0 +1,178 @@
+// NYSE trading calendar: weekdays minus the exchange's full-day holidays,
+// with Saturday holidays observed on the Friday before and Sunday holidays on
+// the Monday after. One-off closures (national days of mourning, weather) are
+// not included.
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+// The `n`th (1-based) `weekday` of a month.
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u32) -> Option<NaiveDate> {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8)
+}
+
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> Option<NaiveDate> {
+    nth_weekday(year, month, weekday, 5).or_else(|| nth_weekday(year, month, weekday, 4))
+}
+
+// Gregorian Easter Sunday (anonymous Gregorian algorithm).
+fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+// Weekend holidays move to the adjacent weekday.
+fn observed(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Duration::days(1),
+        Weekday::Sun => date + Duration::days(1),
+        _ => date,
+    }
+}
+
+// Full-day NYSE holidays observed in `year`.
+pub fn nyse_holidays(year: i32) -> Vec<NaiveDate> {
+    let fixed = |month: u32, day: u32| NaiveDate::from_ymd_opt(year, month, day).map(observed);
+    let mut holidays = vec![
+        // A Saturday New Year's Day is not moved back into the old year.
+        NaiveDate::from_ymd_opt(year, 1, 1)
+            .map(observed)
+            .filter(|date| date.year() == year),
+        nth_weekday(year, 1, Weekday::Mon, 3),
+        nth_weekday(year, 2, Weekday::Mon, 3),
+        easter_sunday(year).map(|easter| easter - Duration::days(2)),
+        last_weekday(year, 5, Weekday::Mon),
+        fixed(7, 4),
+        nth_weekday(year, 9, Weekday::Mon, 1),
+        nth_weekday(year, 11, Weekday::Thu, 4),
+        fixed(12, 25),
+    ];
+    if year >= 2022 {
+        holidays.push(fixed(6, 19));
+    }
+    let mut holidays: Vec<NaiveDate> = holidays.into_iter().flatten().collect();
+    holidays.sort();
+    holidays
+}
+
+// 1 pm closes in `year`: the day before Independence Day, the day after
+// Thanksgiving and Christmas Eve, whenever those are trading days.
+pub fn nyse_early_closes(year: i32) -> Vec<NaiveDate> {
+    [
+        NaiveDate::from_ymd_opt(year, 7, 3),
+        nth_weekday(year, 11, Weekday::Thu, 4).map(|thanksgiving| thanksgiving + Duration::days(1)),
+        NaiveDate::from_ymd_opt(year, 12, 24),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|date| is_trading_day(*date))
+    .collect()
+}
+
+pub fn is_early_close(date: NaiveDate) -> bool {
+    nyse_early_closes(date.year()).contains(&date)
+}
+
+pub fn is_trading_day(date: NaiveDate) -> bool {
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+        && !nyse_holidays(date.year()).contains(&date)
+}
+
+// Trading days in [from, to].
+pub fn trading_days(from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+    let mut days = Vec::new();
+    let mut holidays = Vec::new();
+    let mut holiday_year = None;
+    let mut date = from;
+    while date <= to {
+        if holiday_year != Some(date.year()) {
+            holidays = nyse_holidays(date.year());
+            holiday_year = Some(date.year());
+        }
+        if !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !holidays.contains(&date) {
+            days.push(date);
+        }
+        date += Duration::days(1);
+    }
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn nyse_holidays_for_2022() {
+        // New Year's Day fell on a Saturday and was not observed; Juneteenth
+        // and Christmas fell on Sundays and were observed on the Monday.
+        assert_eq!(
+            nyse_holidays(2022),
+            vec![
+                date(2022, 1, 17),
+                date(2022, 2, 21),
+                date(2022, 4, 15),
+                date(2022, 5, 30),
+                date(2022, 6, 20),
+                date(2022, 7, 4),
+                date(2022, 9, 5),
+                date(2022, 11, 24),
+                date(2022, 12, 26),
+            ]
+        );
+        assert_eq!(
+            trading_days(date(2022, 1, 1), date(2022, 12, 31)).len(),
+            251
+        );
+    }
+
+    #[test]
+    fn weekend_holidays_move_to_the_adjacent_weekday() {
+        // Christmas 2021 was a Saturday, observed Friday 24 December, but the
+        // following Saturday New Year's Day left 31 December a trading day.
+        assert!(!is_trading_day(date(2021, 12, 24)));
+        assert!(is_trading_day(date(2021, 12, 31)));
+        // Independence Day 2021 was a Sunday, observed Monday 5 July.
+        assert!(!is_trading_day(date(2021, 7, 5)));
+        assert!(is_trading_day(date(2021, 7, 2)));
+        // Juneteenth is only a holiday from 2022.
+        assert!(is_trading_day(date(2021, 6, 18)));
+        assert!(!is_trading_day(date(2023, 6, 19)));
+        // Good Friday follows Easter: 7 April 2023, 29 March 2024.
+        assert!(!is_trading_day(date(2023, 4, 7)));
+        assert!(!is_trading_day(date(2024, 3, 29)));
+    }
+
+    #[test]
+    fn early_closes_skip_weekends_and_holidays() {
+        // 2022: 3 July and Christmas Eve fell on weekends.
+        assert_eq!(nyse_early_closes(2022), vec![date(2022, 11, 25)]);
+        // 2023: Monday 3 July; Christmas Eve was a Sunday.
+        assert_eq!(
+            nyse_early_closes(2023),
+            vec![date(2023, 7, 3), date(2023, 11, 24)]
+        );
+        assert_eq!(
+            nyse_early_closes(2024),
+            vec![date(2024, 7, 3), date(2024, 11, 29), date(2024, 12, 24)]
+        );
+        // 3 July 2020 was the observed Independence Day, a full holiday.
+        assert!(!is_early_close(date(2020, 7, 3)));
+        assert!(!is_trading_day(date(2020, 7, 3)));
+    }
+}
@@ -0,0 +1,357 @@
+// Probabilistic calibration of volatility forecasts. A vol forecast implies a
+// lognormal distribution for the price at the end of the horizon; we check
+// realized outcomes against that whole distribution rather than one magnitude.
+
+use crate::api::{Ohlcv, OptionsData};
+use crate::data::historical_volatility;
+use crate::stats::{chi_squared_sf, normal_cdf, normal_pdf};
+use chrono::NaiveDate;
+
+const ONE_SIGMA_NOMINAL: f64 = 0.682689492137086;
+const TWO_SIGMA_NOMINAL: f64 = 0.954499736103642;
+
+#[derive(Debug, Clone)]
+pub struct DensityObservation {
+    pub date: NaiveDate,
+    // Annualized forecast vol.
+    pub vol: f64,
+    // Realized ln(S_{t+w} / S_t).
+    pub log_return: f64,
+    // Horizon in years, w / 252.
+    pub horizon_years: f64,
+}
+
+impl DensityObservation {
+    // Mean and standard deviation of the forecast log return. The drift is set
+    // so the price is a martingale, ignoring rates and dividends over the horizon.
+    pub fn log_return_moments(&self) -> (f64, f64) {
+        let variance = self.vol.powi(2) * self.horizon_years;
+        (-0.5 * variance, variance.sqrt())
+    }
+
+    pub fn standardized_return(&self) -> Option<f64> {
+        let (mean, sd) = self.log_return_moments();
+        if sd <= 0.0 {
+            return None;
+        }
+        Some((self.log_return - mean) / sd)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UniformityTest {
+    pub statistic: f64,
+    pub p_value: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BandCoverage {
+    pub hit_rate: f64,
+    pub nominal: f64,
+    pub hits: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct CalibrationReport {
+    pub n: usize,
+    pub pit_values: Vec<f64>,
+    // Counts of PIT values in equal-width bins over [0, 1].
+    pub pit_histogram: Vec<usize>,
+    // Pearson chi-squared test of equal bin counts.
+    pub chi_squared: UniformityTest,
+    // Kolmogorov-Smirnov test against U(0, 1).
+    pub kolmogorov_smirnov: UniformityTest,
+    pub one_sigma: BandCoverage,
+    pub two_sigma: BandCoverage,
+    // Mean continuous ranked probability score of the log return, lower is better.
+    pub mean_crps: f64,
+    // Mean negative log density of the log return, lower is better.
+    pub mean_log_score: f64,
+}
+
+fn log_return_over(ohlcv_data: &[Ohlcv], start_idx: usize, window: usize) -> Option<f64> {
+    let end_idx = start_idx.checked_add(window)?;
+    if end_idx >= ohlcv_data.len() {
+        return None;
+    }
+    let s_t = ohlcv_data[start_idx].close;
+    let s_t_plus_w = ohlcv_data[end_idx].close;
+    if s_t <= 0.0 || s_t_plus_w <= 0.0 {
+        return None;
+    }
+    Some((s_t_plus_w / s_t).ln())
+}
+
+pub fn iv_density_observations(
+    option_data: &[OptionsData],
+    ohlcv_data: &[Ohlcv],
+    window: usize,
+) -> Vec<DensityObservation> {
+    let mut observations = Vec::new();
+
+    for option_datum in option_data {
+        let start_index = ohlcv_data.iter().position(|d| d.date == option_datum.date);
+
+        if let Some(start_idx) = start_index {
+            if let Some(log_return) = log_return_over(ohlcv_data, start_idx, window) {
+                observations.push(DensityObservation {
+                    date: option_datum.date,
+                    vol: option_datum.implied_volatility,
+                    log_return,
+                    horizon_years: window as f64 / 252.0,
+                });
+            }
+        }
+    }
+    observations
+}
+
+pub fn hv_density_observations(ohlcv_data: &[Ohlcv], window: usize) -> Vec<DensityObservation> {
+    let mut observations = Vec::new();
+
+    let hv_series = historical_volatility(ohlcv_data, window);
+
+    for (start_idx, ohlcv_entry) in ohlcv_data.iter().enumerate() {
+        if let Some(Some(hv)) = hv_series.get(start_idx) {
+            if let Some(log_return) = log_return_over(ohlcv_data, start_idx, window) {
+                observations.push(DensityObservation {
+                    date: ohlcv_entry.date,
+                    vol: *hv,
+                    log_return,
+                    horizon_years: window as f64 / 252.0,
+                });
+            }
+        }
+    }
+    observations
+}
+
+// CRPS of a normal forecast N(mean, sd^2) for outcome x (Gneiting et al., 2005).
+pub fn normal_crps(mean: f64, sd: f64, x: f64) -> f64 {
+    let z = (x - mean) / sd;
+    sd * (z * (2.0 * normal_cdf(z) - 1.0) + 2.0 * normal_pdf(z) - 1.0 / std::f64::consts::PI.sqrt())
+}
+
+// Asymptotic Kolmogorov distribution tail probability with the Stephens
+// small-sample adjustment.
+fn kolmogorov_smirnov_p_value(statistic: f64, n: usize) -> f64 {
+    let sqrt_n = (n as f64).sqrt();
+    let lambda = (sqrt_n + 0.12 + 0.11 / sqrt_n) * statistic;
+    if lambda < 1e-3 {
+        return 1.0;
+    }
+    let mut sum = 0.0;
+    for k in 1..=100 {
+        let k = k as f64;
+        let term = 2.0 * (-1.0f64).powf(k - 1.0) * (-2.0 * k * k * lambda * lambda).exp();
+        sum += term;
+        if term.abs() < 1e-12 {
+            break;
+        }
+    }
+    sum.clamp(0.0, 1.0)
+}
+
+pub fn calibration_report(
+    observations: &[DensityObservation],
+    bins: usize,
+) -> Option<CalibrationReport> {
+    if bins < 2 {
+        return None;
+    }
+
+    let mut pit_values = Vec::new();
+    let mut crps_sum = 0.0;
+    let mut log_score_sum = 0.0;
+    let mut one_sigma_hits = 0;
+    let mut two_sigma_hits = 0;
+
+    for observation in observations {
+        let Some(z) = observation.standardized_return() else {
+            continue;
+        };
+        let (mean, sd) = observation.log_return_moments();
+
+        pit_values.push(normal_cdf(z));
+        crps_sum += normal_crps(mean, sd, observation.log_return);
+        log_score_sum += -(normal_pdf(z) / sd).ln();
+        if z.abs() <= 1.0 {
+            one_sigma_hits += 1;
+        }
+        if z.abs() <= 2.0 {
+            two_sigma_hits += 1;
+        }
+    }
+
+    let n = pit_values.len();
+    if n == 0 {
+        return None;
+    }
+
+    let mut pit_histogram = vec![0usize; bins];
+    for &u in &pit_values {
+        let bin = ((u * bins as f64) as usize).min(bins - 1);
+        pit_histogram[bin] += 1;
+    }
+    let expected = n as f64 / bins as f64;
+    let chi_squared_statistic: f64 = pit_histogram
+        .iter()
+        .map(|&count| (count as f64 - expected).powi(2) / expected)
+        .sum();
+
+    let mut sorted_pit = pit_values.clone();
+    sorted_pit.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let ks_statistic = sorted_pit
+        .iter()
+        .enumerate()
+        .map(|(i, &u)| {
+            let above = (i + 1) as f64 / n as f64 - u;
+            let below = u - i as f64 / n as f64;
+            above.max(below)
+        })
+        .fold(0.0, f64::max);
+
+    Some(CalibrationReport {
+        n,
+        pit_values,
+        pit_histogram,
+        chi_squared: UniformityTest {
+            statistic: chi_squared_statistic,
+            p_value: chi_squared_sf(chi_squared_statistic, bins - 1),
+        },
+        kolmogorov_smirnov: UniformityTest {
+            statistic: ks_statistic,
+            p_value: kolmogorov_smirnov_p_value(ks_statistic, n),
+        },
+        one_sigma: BandCoverage {
+            hit_rate: one_sigma_hits as f64 / n as f64,
+            nominal: ONE_SIGMA_NOMINAL,
+            hits: one_sigma_hits,
+        },
+        two_sigma: BandCoverage {
+            hit_rate: two_sigma_hits as f64 / n as f64,
+            nominal: TWO_SIGMA_NOMINAL,
+            hits: two_sigma_hits,
+        },
+        mean_crps: crps_sum / n as f64,
+        mean_log_score: log_score_sum / n as f64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use chrono::Duration;
+
+    fn observation(vol: f64, horizon_years: f64, z: f64) -> DensityObservation {
+        let variance = vol * vol * horizon_years;
+        DensityObservation {
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            vol,
+            log_return: -0.5 * variance + z * variance.sqrt(),
+            horizon_years,
+        }
+    }
+
+    #[test]
+    fn normal_crps_matches_closed_form_values() {
+        let inv_sqrt_pi = 1.0 / std::f64::consts::PI.sqrt();
+        // At the median, CRPS(N(0, 1), 0) = 2 phi(0) - 1/sqrt(pi) = (sqrt(2) - 1) / sqrt(pi).
+        let at_median = (2f64.sqrt() - 1.0) * inv_sqrt_pi;
+        assert!((normal_crps(0.0, 1.0, 0.0) - at_median).abs() < 1e-9);
+        // One sd out: (2 Phi(1) - 1) + 2 phi(1) - 1/sqrt(pi).
+        let one_out = 0.682689492137086 + 2.0 * 0.241970724519143 - inv_sqrt_pi;
+        assert!((normal_crps(0.0, 1.0, 1.0) - one_out).abs() < 1e-7);
+        // Location-scale: CRPS(N(m, s^2), x) = s * CRPS(N(0, 1), (x - m) / s).
+        assert!((normal_crps(0.3, 0.2, 0.5) - 0.2 * one_out).abs() < 1e-7);
+    }
+
+    #[test]
+    fn normal_crps_matches_its_integral_definition() {
+        // CRPS = integral of (F(y) - 1{y >= x})^2 dy.
+        let (mean, sd, x) = (0.01, 0.05, -0.04);
+        let steps = 200_000;
+        let (low, high) = (mean - 12.0 * sd, mean + 12.0 * sd);
+        let dy = (high - low) / steps as f64;
+        let integral: f64 = (0..steps)
+            .map(|i| {
+                let y = low + (i as f64 + 0.5) * dy;
+                let step = if y >= x { 1.0 } else { 0.0 };
+                (normal_cdf((y - mean) / sd) - step).powi(2) * dy
+            })
+            .sum();
+        // Quadrature over normal_cdf's own approximation error.
+        assert!((normal_crps(mean, sd, x) - integral).abs() < 1e-5);
+    }
+
+    #[test]
+    fn counts_band_hits_and_scores_each_observation() {
+        let zs = [0.5, -0.9, 1.5, -2.5, 0.0];
+        let observations: Vec<DensityObservation> =
+            zs.iter().map(|&z| observation(0.2, 0.25, z)).collect();
+        let report = calibration_report(&observations, 4).unwrap();
+        assert_eq!(report.n, 5);
+        assert_eq!((report.one_sigma.hits, report.two_sigma.hits), (3, 4));
+        assert!((report.one_sigma.hit_rate - 0.6).abs() < 1e-12);
+        assert!((report.two_sigma.nominal - (2.0 * normal_cdf(2.0) - 1.0)).abs() < 1e-7);
+
+        for (pit, z) in report.pit_values.iter().zip(zs) {
+            assert!((pit - normal_cdf(z)).abs() < 1e-9);
+        }
+        assert_eq!(report.pit_histogram.iter().sum::<usize>(), 5);
+
+        // Log score is 0.5 ln(2 pi) + ln(sd) + z^2 / 2 with sd = 0.2 * 0.5.
+        let sd: f64 = 0.1;
+        let expected_log_score = zs
+            .iter()
+            .map(|z| 0.5 * (2.0 * std::f64::consts::PI).ln() + sd.ln() + z * z / 2.0)
+            .sum::<f64>()
+            / 5.0;
+        assert!((report.mean_log_score - expected_log_score).abs() < 1e-9);
+        let expected_crps = zs
+            .iter()
+            .map(|z| sd * normal_crps(0.0, 1.0, *z))
+            .sum::<f64>()
+            / 5.0;
+        assert!((report.mean_crps - expected_crps).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pit_is_uniform_when_outcomes_come_from_the_forecast() {
+        let mut rng = Rng::new(7);
+        let start = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let observations: Vec<DensityObservation> = (0..2000)
+            .map(|i| {
+                let vol = 0.1 + 0.3 * rng.uniform();
+                let mut observation = observation(vol, 30.0 / 252.0, rng.normal());
+                observation.date = start + Duration::days(i);
+                observation
+            })
+            .collect();
+        let report = calibration_report(&observations, 10).unwrap();
+        assert!(report.kolmogorov_smirnov.p_value > 0.01);
+        assert!(report.chi_squared.p_value > 0.01);
+        assert!((report.one_sigma.hit_rate - ONE_SIGMA_NOMINAL).abs() < 0.03);
+        assert!((report.two_sigma.hit_rate - TWO_SIGMA_NOMINAL).abs() < 0.015);
+
+        // Forecasts half as wide as the truth pile the PIT into the tails.
+        let overconfident: Vec<DensityObservation> = observations
+            .iter()
+            .map(|observation| DensityObservation {
+                vol: observation.vol / 2.0,
+                ..observation.clone()
+            })
+            .collect();
+        let report = calibration_report(&overconfident, 10).unwrap();
+        assert!(report.kolmogorov_smirnov.p_value < 1e-6);
+        assert!(report.one_sigma.hit_rate < 0.45);
+    }
+
+    #[test]
+    fn needs_two_bins_and_a_usable_observation() {
+        let observations = vec![observation(0.2, 0.25, 0.3)];
+        assert!(calibration_report(&observations, 1).is_none());
+        assert!(calibration_report(&[observation(0.0, 0.25, 0.0)], 5).is_none());
+    }
+}
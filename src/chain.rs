@@ -0,0 +1,376 @@
+// One underlying's option chain on one quote date, indexed by expiry, then
+// strike, then call/put, so lookups are ordered-map queries instead of scans.
+
+use crate::api::{OptionType, OptionsData};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+pub struct StrikePair {
+    pub strike: f64,
+    pub call: Option<OptionsData>,
+    pub put: Option<OptionsData>,
+}
+
+impl StrikePair {
+    pub fn get(&self, option_type: OptionType) -> Option<&OptionsData> {
+        match option_type {
+            OptionType::Call => self.call.as_ref(),
+            OptionType::Put => self.put.as_ref(),
+        }
+    }
+
+    // The out-of-the-money contract (put below the forward, call at or above
+    // it) if `usable`, otherwise the other one if that is.
+    pub fn out_of_the_money(
+        &self,
+        forward: f64,
+        usable: impl Fn(&OptionsData) -> bool,
+    ) -> Option<&OptionsData> {
+        let (preferred, other) = if self.strike < forward {
+            (&self.put, &self.call)
+        } else {
+            (&self.call, &self.put)
+        };
+        preferred
+            .as_ref()
+            .filter(|option| usable(option))
+            .or_else(|| other.as_ref().filter(|option| usable(option)))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExpirySlice {
+    pub expiration: NaiveDate,
+    // ACT/365 year fraction from the quote date.
+    pub time_to_expiry: f64,
+    // Keyed by the bit pattern of the strike, which orders like the value for
+    // positive strikes.
+    strikes: BTreeMap<u64, StrikePair>,
+}
+
+// Map key of a non-negative strike. -0.0 has the sign bit set and would sort
+// above every strike, so it is keyed as 0.0.
+fn strike_key(strike: f64) -> u64 {
+    if strike == 0.0 {
+        0
+    } else {
+        strike.to_bits()
+    }
+}
+
+impl ExpirySlice {
+    // Strikes in ascending order.
+    pub fn strikes(&self) -> impl DoubleEndedIterator<Item = &StrikePair> {
+        self.strikes.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.strikes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strikes.is_empty()
+    }
+
+    pub fn strike(&self, strike: f64) -> Option<&StrikePair> {
+        self.strikes.get(&strike_key(strike))
+    }
+
+    pub fn contract(&self, strike: f64, option_type: OptionType) -> Option<&OptionsData> {
+        self.strike(strike)?.get(option_type)
+    }
+
+    // All contracts of one type, by ascending strike.
+    pub fn contracts(&self, option_type: OptionType) -> impl Iterator<Item = &OptionsData> {
+        self.strikes
+            .values()
+            .filter_map(move |pair| pair.get(option_type))
+    }
+
+    // Every contract, calls and puts, by ascending strike.
+    pub fn options(&self) -> Vec<&OptionsData> {
+        self.strikes
+            .values()
+            .flat_map(|pair| pair.call.iter().chain(pair.put.iter()))
+            .collect()
+    }
+
+    // Highest strike at or below `target` and lowest strike at or above it.
+    // Both are the same pair when `target` is a listed strike.
+    pub fn bracketing_strikes(&self, target: f64) -> (Option<&StrikePair>, Option<&StrikePair>) {
+        if target.is_nan() || target < 0.0 {
+            return (None, self.strikes.values().next());
+        }
+        let key = strike_key(target);
+        let below = self.strikes.range(..=key).next_back().map(|(_, pair)| pair);
+        let above = self.strikes.range(key..).next().map(|(_, pair)| pair);
+        (below, above)
+    }
+
+    pub fn nearest_strike(&self, target: f64) -> Option<&StrikePair> {
+        self.nearest_strike_where(target, |_| true)
+    }
+
+    // Nearest strike to `target` among those with a contract of `option_type`
+    // satisfying `usable`.
+    pub fn nearest_contract(
+        &self,
+        target: f64,
+        option_type: OptionType,
+        usable: impl Fn(&OptionsData) -> bool,
+    ) -> Option<&OptionsData> {
+        self.nearest_strike_where(target, |pair| pair.get(option_type).is_some_and(&usable))?
+            .get(option_type)
+    }
+
+    pub fn nearest_strike_where(
+        &self,
+        target: f64,
+        predicate: impl Fn(&StrikePair) -> bool,
+    ) -> Option<&StrikePair> {
+        self.strikes
+            .values()
+            .filter(|pair| predicate(pair))
+            .min_by(|a, b| {
+                (a.strike - target)
+                    .abs()
+                    .total_cmp(&(b.strike - target).abs())
+            })
+    }
+
+    // Strikes in [low, high]; nothing when the bounds are inverted, NaN or
+    // negative.
+    pub fn strike_range(&self, low: f64, high: f64) -> impl Iterator<Item = &StrikePair> {
+        let keys = (low <= high && low >= 0.0).then(|| strike_key(low)..=strike_key(high));
+        keys.into_iter()
+            .flat_map(|keys| self.strikes.range(keys))
+            .map(|(_, pair)| pair)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OptionChain {
+    pub symbol: String,
+    pub date: NaiveDate,
+    expiries: BTreeMap<NaiveDate, ExpirySlice>,
+}
+
+impl OptionChain {
+    // Index a day's contracts. The quote date and symbol come from the first
+    // contract; contracts from other dates or with non-positive strikes are
+    // dropped. A later duplicate of the same expiry, strike and type replaces
+    // the earlier one. None for an empty chain.
+    pub fn new(options: Vec<OptionsData>) -> Option<Self> {
+        let first = options.first()?;
+        let symbol = first.symbol.clone();
+        let date = first.date;
+
+        let mut expiries: BTreeMap<NaiveDate, ExpirySlice> = BTreeMap::new();
+        for option in options {
+            if option.date != date || option.strike.is_nan() || option.strike <= 0.0 {
+                continue;
+            }
+            let expiration = option.expiration;
+            let slice = expiries.entry(expiration).or_insert_with(|| ExpirySlice {
+                expiration,
+                time_to_expiry: (expiration - date).num_days() as f64 / 365.0,
+                strikes: BTreeMap::new(),
+            });
+            let pair = slice
+                .strikes
+                .entry(strike_key(option.strike))
+                .or_insert_with(|| StrikePair {
+                    strike: option.strike,
+                    call: None,
+                    put: None,
+                });
+            match option.contract_type {
+                OptionType::Call => pair.call = Some(option),
+                OptionType::Put => pair.put = Some(option),
+            }
+        }
+
+        Some(OptionChain {
+            symbol,
+            date,
+            expiries,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.expiries.is_empty()
+    }
+
+    // Number of contracts.
+    pub fn len(&self) -> usize {
+        self.expiries
+            .values()
+            .flat_map(|slice| slice.strikes())
+            .map(|pair| pair.call.is_some() as usize + pair.put.is_some() as usize)
+            .sum()
+    }
+
+    // Expiries in ascending order.
+    pub fn expiries(&self) -> impl DoubleEndedIterator<Item = &ExpirySlice> {
+        self.expiries.values()
+    }
+
+    pub fn expiry(&self, expiration: NaiveDate) -> Option<&ExpirySlice> {
+        self.expiries.get(&expiration)
+    }
+
+    // Nearest expiry to `target`, the earlier one on a tie.
+    pub fn nearest_expiry(&self, target: NaiveDate) -> Option<&ExpirySlice> {
+        let (below, above) = self.bracketing_expiries(target);
+        match (below, above) {
+            (Some(below), Some(above)) => {
+                if (above.expiration - target) < (target - below.expiration) {
+                    Some(above)
+                } else {
+                    Some(below)
+                }
+            }
+            (below, above) => below.or(above),
+        }
+    }
+
+    // Latest expiry at or before `target` and earliest one at or after it.
+    pub fn bracketing_expiries(
+        &self,
+        target: NaiveDate,
+    ) -> (Option<&ExpirySlice>, Option<&ExpirySlice>) {
+        let below = self.expiries.range(..=target).next_back().map(|(_, s)| s);
+        let above = self.expiries.range(target..).next().map(|(_, s)| s);
+        (below, above)
+    }
+
+    // Expiries with expiration in [from, to]; nothing when `from` is after `to`.
+    pub fn expiry_range(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> impl Iterator<Item = &ExpirySlice> {
+        (from <= to)
+            .then_some(from..=to)
+            .into_iter()
+            .flat_map(|dates| self.expiries.range(dates))
+            .map(|(_, slice)| slice)
+    }
+
+    // Every contract, by expiry then strike.
+    pub fn options(&self) -> impl Iterator<Item = &OptionsData> {
+        self.expiries.values().flat_map(|slice| {
+            slice
+                .strikes()
+                .flat_map(|pair| pair.call.iter().chain(pair.put.iter()))
+        })
+    }
+
+    pub fn into_options(self) -> Vec<OptionsData> {
+        self.expiries
+            .into_values()
+            .flat_map(|slice| slice.strikes.into_values())
+            .flat_map(|pair| pair.call.into_iter().chain(pair.put))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    fn contract(expiration: NaiveDate, strike: f64, contract_type: OptionType) -> OptionsData {
+        OptionsData {
+            symbol: "SPY".to_string(),
+            contract: format!("SPY{}{:?}{}", expiration, contract_type, strike),
+            contract_type,
+            expiration,
+            date: date(1),
+            strike,
+            last: 1.0,
+            mark: 1.0,
+            bid: 0.9,
+            ask: 1.1,
+            volume: 10.0,
+            open_interest: 100.0,
+            implied_volatility: 0.2,
+            delta: 0.5,
+            gamma: 0.0,
+            theta: 0.0,
+            vega: 0.0,
+            rho: 0.0,
+        }
+    }
+
+    fn chain() -> OptionChain {
+        let mut options = Vec::new();
+        for expiration in [date(8), date(15), date(22)] {
+            for strike in [90.0, 95.0, 100.0, 105.0, 110.0] {
+                options.push(contract(expiration, strike, OptionType::Call));
+                options.push(contract(expiration, strike, OptionType::Put));
+            }
+        }
+        OptionChain::new(options).unwrap()
+    }
+
+    fn strikes<'a>(pairs: impl Iterator<Item = &'a StrikePair>) -> Vec<f64> {
+        pairs.map(|pair| pair.strike).collect()
+    }
+
+    #[test]
+    fn strike_range_is_inclusive() {
+        let chain = chain();
+        let slice = chain.expiry(date(15)).unwrap();
+        assert_eq!(
+            strikes(slice.strike_range(95.0, 105.0)),
+            [95.0, 100.0, 105.0]
+        );
+        assert_eq!(strikes(slice.strike_range(-0.0, 92.0)), [90.0]);
+    }
+
+    #[test]
+    fn inverted_or_invalid_bounds_are_empty() {
+        let chain = chain();
+        let slice = chain.expiry(date(15)).unwrap();
+        assert_eq!(slice.strike_range(110.0, 100.0).count(), 0);
+        assert_eq!(slice.strike_range(100.0, 100.0).count(), 1);
+        assert_eq!(slice.strike_range(f64::NAN, 100.0).count(), 0);
+        assert_eq!(slice.strike_range(90.0, f64::NAN).count(), 0);
+        assert_eq!(chain.expiry_range(date(22), date(8)).count(), 0);
+        assert_eq!(chain.expiry_range(date(8), date(15)).count(), 2);
+    }
+
+    #[test]
+    fn bracketing_strikes_handles_zero_and_listed_strikes() {
+        let chain = chain();
+        let slice = chain.expiry(date(15)).unwrap();
+        let (below, above) = slice.bracketing_strikes(-0.0);
+        assert!(below.is_none());
+        assert_eq!(above.unwrap().strike, 90.0);
+        let (below, above) = slice.bracketing_strikes(100.0);
+        assert_eq!(
+            (below.unwrap().strike, above.unwrap().strike),
+            (100.0, 100.0)
+        );
+        let (below, above) = slice.bracketing_strikes(102.0);
+        assert_eq!(
+            (below.unwrap().strike, above.unwrap().strike),
+            (100.0, 105.0)
+        );
+        let (below, above) = slice.bracketing_strikes(120.0);
+        assert_eq!((below.unwrap().strike, above.is_none()), (110.0, true));
+    }
+
+    #[test]
+    fn nearest_expiry_picks_the_closest() {
+        let chain = chain();
+        assert_eq!(chain.nearest_expiry(date(18)).unwrap().expiration, date(15));
+        assert_eq!(chain.nearest_expiry(date(19)).unwrap().expiration, date(22));
+        assert_eq!(chain.nearest_expiry(date(30)).unwrap().expiration, date(22));
+    }
+}
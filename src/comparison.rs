@@ -0,0 +1,134 @@
+// Tests for comparing the accuracy of two forecasters.
+
+use crate::hac::long_run_variance;
+use crate::loss::{ForecastPair, LossFunction};
+use crate::stats::two_sided_t_p_value;
+use crate::time_series::TimeSeries;
+
+#[derive(Debug, Clone)]
+pub struct DieboldMariano {
+    // HLN-corrected DM statistic. Negative means the first forecaster has the
+    // lower loss.
+    pub statistic: f64,
+    pub p_value: f64,
+    pub n: usize,
+    // Mean of loss(a) - loss(b).
+    pub mean_differential: f64,
+    // Number of autocovariance lags used in the long-run variance.
+    pub lags: usize,
+}
+
+// Number of autocorrelated lags induced by overlapping forecast windows: a
+// forecast over `horizon_days` sampled every `sampling_interval_days` overlaps
+// with the next ceil(h / s) - 1 forecasts. Both arguments in calendar days.
+pub fn overlap_lags(horizon_days: f64, sampling_interval_days: f64) -> usize {
+    if sampling_interval_days <= 0.0 || horizon_days <= 0.0 {
+        return 0;
+    }
+    ((horizon_days / sampling_interval_days).ceil() as usize).saturating_sub(1)
+}
+
+// Diebold-Mariano test of equal predictive accuracy between two forecasters
+// under `loss`, on the dates both forecast. `lags` should cover the forecast
+// overlap, see `overlap_lags`. Uses the Harvey-Leybourne-Newbold small sample
+// correction and a t(n-1) reference distribution.
+pub fn diebold_mariano(
+    pairs_a: &[ForecastPair],
+    pairs_b: &[ForecastPair],
+    loss: &dyn LossFunction,
+    lags: usize,
+) -> Option<DieboldMariano> {
+    let series_a: TimeSeries<&ForecastPair> = pairs_a.iter().map(|p| (p.date, p)).collect();
+    let series_b: TimeSeries<&ForecastPair> = pairs_b.iter().map(|p| (p.date, p)).collect();
+
+    let d: Vec<f64> = series_a
+        .inner_join(&series_b)
+        .values()
+        .filter_map(|(a, b)| {
+            let loss_a = loss.loss(a.forecast, a.realized)?;
+            let loss_b = loss.loss(b.forecast, b.realized)?;
+            let d = loss_a - loss_b;
+            d.is_finite().then_some(d)
+        })
+        .collect();
+
+    let n = d.len();
+    if n < 3 {
+        return None;
+    }
+
+    let mean_differential = d.iter().sum::<f64>() / n as f64;
+    let variance = long_run_variance(&d, lags) / n as f64;
+    if variance <= 0.0 {
+        return None;
+    }
+
+    let raw_statistic = mean_differential / variance.sqrt();
+    let h = (lags + 1) as f64;
+    let n_f = n as f64;
+    let correction = ((n_f + 1.0 - 2.0 * h + h * (h - 1.0) / n_f) / n_f)
+        .max(0.0)
+        .sqrt();
+    let statistic = raw_statistic * correction;
+
+    Some(DieboldMariano {
+        statistic,
+        p_value: two_sided_t_p_value(statistic, n_f - 1.0),
+        n,
+        mean_differential,
+        lags,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loss::Mae;
+    use chrono::{Duration, NaiveDate};
+
+    fn pairs(errors: &[f64]) -> Vec<ForecastPair> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        errors
+            .iter()
+            .enumerate()
+            .map(|(i, error)| ForecastPair {
+                date: start + Duration::days(14 * i as i64),
+                forecast: 0.2 + error,
+                realized: 0.2,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn overlap_lags_count_overlapping_windows() {
+        assert_eq!(overlap_lags(42.0, 14.0), 2);
+        assert_eq!(overlap_lags(43.0, 14.0), 3);
+        assert_eq!(overlap_lags(10.0, 14.0), 0);
+        assert_eq!(overlap_lags(30.0, 0.0), 0);
+    }
+
+    #[test]
+    fn matches_hand_computed_statistic() {
+        let a = pairs(&[0.01, 0.02, 0.01, 0.03, 0.02, 0.01]);
+        let b = pairs(&[0.05, 0.03, 0.06, 0.04, 0.02, 0.05]);
+        let test = diebold_mariano(&a, &b, &Mae, 0).unwrap();
+        let d = [-0.04, -0.01, -0.05, -0.01, 0.0, -0.04];
+        let n = d.len() as f64;
+        let mean = d.iter().sum::<f64>() / n;
+        let variance = d.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        // HLN correction with h = 1 is sqrt((n - 1) / n).
+        let expected = mean / (variance / n).sqrt() * ((n - 1.0) / n).sqrt();
+        assert_eq!(test.n, 6);
+        assert!((test.mean_differential - mean).abs() < 1e-12);
+        assert!((test.statistic - expected).abs() < 1e-9);
+        assert!(test.statistic < 0.0);
+        assert!(test.p_value < 0.05);
+    }
+
+    #[test]
+    fn needs_three_common_dates() {
+        let a = pairs(&[0.01, 0.02]);
+        let b = pairs(&[0.03, 0.01]);
+        assert!(diebold_mariano(&a, &b, &Mae, 0).is_none());
+    }
+}
@@ -0,0 +1,251 @@
+// Constant-maturity ATM implied volatility. Picking the single expiry closest
+// to date + 30 lets the "30-day IV" drift between roughly 20 and 45 days, so
+// instead we interpolate total variance (sigma^2 * T) linearly in log-strike
+// around the forward within each expiry, then linearly in time between the two
+// expiries bracketing the target maturity.
+
+use crate::api::{OptionType, OptionsData};
+use crate::chain::{ExpirySlice, OptionChain};
+use crate::parity::ForwardCurve;
+use chrono::{Duration, NaiveDate};
+
+#[derive(Debug, Clone)]
+pub struct AtmPoint {
+    pub expiration: NaiveDate,
+    // Year fraction, ACT/365.
+    pub time_to_expiry: f64,
+    pub forward: f64,
+    pub atm_iv: f64,
+}
+
+impl AtmPoint {
+    pub fn total_variance(&self) -> f64 {
+        self.atm_iv.powi(2) * self.time_to_expiry
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstantMaturityIv {
+    pub target_years: f64,
+    pub iv: f64,
+    pub forward: f64,
+    // The expiries used. Equal when the target falls on an expiry or outside
+    // the listed range.
+    pub lower_expiration: NaiveDate,
+    pub upper_expiration: NaiveDate,
+    // True when the target is before the first or after the last expiry and
+    // the nearest ATM vol was held flat.
+    pub extrapolated: bool,
+}
+
+impl ConstantMaturityIv {
+    // A synthetic contract carrying the interpolated IV, so it can go through
+    // the same accuracy code as a listed option. Prices and greeks are zero.
+    pub fn as_options_data(&self, symbol: &str, date: NaiveDate) -> Option<OptionsData> {
+        let days = (self.target_years * 365.0).round() as i64;
+        let expiration = date.checked_add_signed(Duration::days(days))?;
+        Some(OptionsData {
+            symbol: symbol.to_string(),
+            contract: format!(
+                "{} CM{}D ATM ({}/{})",
+                symbol, days, self.lower_expiration, self.upper_expiration
+            ),
+            contract_type: OptionType::Call,
+            expiration,
+            date,
+            strike: self.forward,
+            last: 0.0,
+            mark: 0.0,
+            bid: 0.0,
+            ask: 0.0,
+            volume: 0.0,
+            open_interest: 0.0,
+            implied_volatility: self.iv,
+            delta: 0.0,
+            gamma: 0.0,
+            theta: 0.0,
+            vega: 0.0,
+            rho: 0.0,
+        })
+    }
+}
+
+// One IV per strike, from the out-of-the-money side (puts below the forward,
+// calls above) since those quotes are the more liquid, falling back to the
+// other type when the OTM one is missing.
+fn smile_points(slice: &ExpirySlice, forward: f64) -> Vec<(f64, f64)> {
+    slice
+        .strikes()
+        .filter_map(|pair| {
+            let option = pair.out_of_the_money(forward, |o| o.implied_volatility > 0.0)?;
+            Some((pair.strike, option.implied_volatility))
+        })
+        .collect()
+}
+
+// ATM IV of one expiry at `forward`, interpolating variance linearly in
+// log-strike between the two strikes around it. None when the forward is
+// outside the listed strikes.
+pub fn atm_iv_for_expiry(slice: &ExpirySlice, forward: f64) -> Option<f64> {
+    let points = smile_points(slice, forward);
+    let upper = points.iter().position(|&(strike, _)| strike >= forward)?;
+    let (k_high, iv_high) = points[upper];
+    if k_high == forward {
+        return Some(iv_high);
+    }
+    if upper == 0 {
+        return None;
+    }
+    let (k_low, iv_low) = points[upper - 1];
+    let weight = (forward / k_low).ln() / (k_high / k_low).ln();
+    let variance = (1.0 - weight) * iv_low.powi(2) + weight * iv_high.powi(2);
+    Some(variance.sqrt())
+}
+
+// ATM point for every expiry in a chain, at that expiry's forward, sorted by
+// time to expiry. Expired or same-day contracts are skipped.
+pub fn atm_term_points(chain: &OptionChain, forwards: &ForwardCurve) -> Vec<AtmPoint> {
+    chain
+        .expiries()
+        .filter_map(|slice| {
+            let t = slice.time_to_expiry;
+            if t <= 0.0 {
+                return None;
+            }
+            let expiration = slice.expiration;
+            let forward = forwards.forward(expiration, t);
+            Some(AtmPoint {
+                expiration,
+                time_to_expiry: t,
+                forward,
+                atm_iv: atm_iv_for_expiry(slice, forward)?,
+            })
+        })
+        .collect()
+}
+
+// Interpolate ATM total variance linearly in time to `target_years`. Outside
+// the listed expiries the nearest ATM vol is held flat.
+pub fn interpolate_atm_iv(points: &[AtmPoint], target_years: f64) -> Option<ConstantMaturityIv> {
+    if target_years <= 0.0 {
+        return None;
+    }
+    let first = points.first()?;
+    let last = points.last()?;
+
+    let flat = |point: &AtmPoint| ConstantMaturityIv {
+        target_years,
+        iv: point.atm_iv,
+        forward: point.forward,
+        lower_expiration: point.expiration,
+        upper_expiration: point.expiration,
+        extrapolated: point.time_to_expiry != target_years,
+    };
+    if target_years <= first.time_to_expiry {
+        return Some(flat(first));
+    }
+    if target_years >= last.time_to_expiry {
+        return Some(flat(last));
+    }
+
+    let upper = points
+        .iter()
+        .position(|point| point.time_to_expiry >= target_years)?;
+    let high = &points[upper];
+    let low = &points[upper - 1];
+    let weight = (target_years - low.time_to_expiry) / (high.time_to_expiry - low.time_to_expiry);
+    let total_variance = (1.0 - weight) * low.total_variance() + weight * high.total_variance();
+    let forward = (((1.0 - weight) * low.forward.ln()) + weight * high.forward.ln()).exp();
+
+    Some(ConstantMaturityIv {
+        target_years,
+        iv: (total_variance / target_years).sqrt(),
+        forward,
+        lower_expiration: low.expiration,
+        upper_expiration: high.expiration,
+        extrapolated: false,
+    })
+}
+
+// Constant-maturity ATM IV `target_days` calendar days out from one day's chain.
+pub fn constant_maturity_atm_iv(
+    chain: &OptionChain,
+    forwards: &ForwardCurve,
+    target_days: usize,
+) -> Option<ConstantMaturityIv> {
+    let points = atm_term_points(chain, forwards);
+    interpolate_atm_iv(&points, target_days as f64 / 365.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(days: i64) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, 2).unwrap() + Duration::days(days)
+    }
+
+    // Two expiries, 30 and 60 days out, with a different smile on each.
+    fn chain() -> OptionChain {
+        let smiles = [
+            (30, [(95.0, 0.25), (100.0, 0.20), (105.0, 0.18)]),
+            (60, [(95.0, 0.26), (100.0, 0.22), (105.0, 0.21)]),
+        ];
+        let mut options = Vec::new();
+        for (days, smile) in smiles {
+            for (strike, iv) in smile {
+                for contract_type in [OptionType::Call, OptionType::Put] {
+                    options.push(OptionsData {
+                        symbol: "SPY".to_string(),
+                        contract: format!("SPY{}{:?}{}", days, contract_type, strike),
+                        contract_type,
+                        expiration: date(days),
+                        date: date(0),
+                        strike,
+                        last: 1.0,
+                        mark: 1.0,
+                        bid: 0.9,
+                        ask: 1.1,
+                        volume: 10.0,
+                        open_interest: 100.0,
+                        implied_volatility: iv,
+                        delta: 0.5,
+                        gamma: 0.01,
+                        theta: -0.01,
+                        vega: 0.1,
+                        rho: 0.01,
+                    });
+                }
+            }
+        }
+        OptionChain::new(options).unwrap()
+    }
+
+    #[test]
+    fn interpolates_variance_in_log_strike_then_time() {
+        // Forward 102 sits ln(1.02)/ln(1.05) of the way from 100 to 105.
+        let forwards = ForwardCurve::flat(102.0, 0.0, 0.0);
+        let chain = chain();
+        let points = atm_term_points(&chain, &forwards);
+        assert_eq!(points.len(), 2);
+        assert!((points[0].atm_iv - 0.1921337100896731).abs() < 1e-12);
+        assert!((points[1].atm_iv - 0.2159970939671524).abs() < 1e-12);
+
+        // 45 days is halfway in total variance between the two expiries.
+        let cm = constant_maturity_atm_iv(&chain, &forwards, 45).unwrap();
+        assert!((cm.iv - 0.20834654765344865).abs() < 1e-12);
+        assert!((cm.forward - 102.0).abs() < 1e-12);
+        assert_eq!(cm.lower_expiration, date(30));
+        assert_eq!(cm.upper_expiration, date(60));
+        assert!(!cm.extrapolated);
+
+        // On an expiry the interpolation returns that expiry's ATM vol.
+        let on_expiry = constant_maturity_atm_iv(&chain, &forwards, 60).unwrap();
+        assert!((on_expiry.iv - 0.2159970939671524).abs() < 1e-12);
+        assert!(
+            constant_maturity_atm_iv(&chain, &forwards, 90)
+                .unwrap()
+                .extrapolated
+        );
+    }
+}
@@ -1,51 +1,29 @@
 use crate::api::{Ohlcv, OptionsData};
+use crate::hac::{mean_confidence_interval, LagSelection};
+use crate::loss::ForecastPair;
+use crate::regression::{correlation_test, CorrelationTest, CovarianceEstimator};
+use crate::stats::ConfidenceInterval;
+use crate::time_series::TimeSeries;
 use chrono::NaiveDate;
-use std::collections::HashMap;
+use std::fmt;
 
-pub fn find_closest_date(dates: &[NaiveDate], target_date: NaiveDate) -> Option<NaiveDate> {
-    if dates.is_empty() {
-        return None;
-    }
-
-    let mut closest_date = dates[0];
-    let mut min_duration = (dates[0] - target_date).abs();
-    for &date in dates.iter().skip(1) {
-        let current_duration = (date - target_date).abs();
-        if current_duration < min_duration {
-            min_duration = current_duration;
-            closest_date = date;
-        }
-    }
-
-    Some(closest_date)
-}
-
-pub fn find_closest_num(values: &[f64], target_value: f64) -> Option<f64> {
-    values
-        .iter()
-        .min_by(|&&a, &&b| {
-            let diff_a = (a - target_value).abs();
-            let diff_b = (b - target_value).abs();
-
-            diff_a
-                .partial_cmp(&diff_b)
-                .unwrap_or(std::cmp::Ordering::Greater)
-        })
-        .copied()
-}
 // calculate historical volatility
 pub fn historical_volatility(data: &[Ohlcv], window: usize) -> Vec<Option<f64>> {
-    if window < 2 || data.len() < window {
+    // Entry i is the vol of the `window` returns ending at close i, so the
+    // first estimate is at index `window` and uses closes 0..=window.
+    if window < 2 || data.len() <= window {
         return vec![None; data.len()];
     }
 
+    // Returns touching a non-positive close are dropped, which leaves their
+    // windows short and so None, rather than poisoning them with ln(0).
     let mut log_returns = vec![None];
     for i in 1..data.len() {
-        let ret = (data[i].close / data[i - 1].close).ln();
-        log_returns.push(Some(ret));
+        let (prev, curr) = (data[i - 1].close, data[i].close);
+        log_returns.push((prev > 0.0 && curr > 0.0).then(|| (curr / prev).ln()));
     }
 
-    let mut volatility = vec![None; window - 1];
+    let mut volatility = vec![None; window];
     for i in window..log_returns.len() {
         let window_returns: Vec<f64> = log_returns[i - window + 1..=i]
             .iter()
@@ -68,75 +46,220 @@ pub fn historical_volatility(data: &[Ohlcv], window: usize) -> Vec<Option<f64>>
 
     volatility
 }
+// How a volatility forecast is compared with what actually happened over the
+// forecast window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoringMode {
+    // |S_{t+w} - S_t| against S_t * vol * sqrt(w/252). This is the original
+    // scoring and is kept for continuity, but it is a single noisy draw and is
+    // biased low by sqrt(2/pi).
+    PriceMove,
+    // Realized volatility over exactly [t, t+w] minus the forecast vol.
+    RealizedVolatility,
+    // Same as RealizedVolatility but in variance space (vol squared).
+    RealizedVariance,
+}
+
+// Annualized realized volatility over the forecast window [start_idx, start_idx + window].
+// Uses the zero-mean estimator sqrt(252/w * sum(r^2)), which is the usual
+// convention for realized measures over short horizons.
+pub fn realized_volatility(data: &[Ohlcv], start_idx: usize, window: usize) -> Option<f64> {
+    if window == 0 {
+        return None;
+    }
+    let end_idx = start_idx.checked_add(window)?;
+    if end_idx >= data.len() {
+        return None;
+    }
+
+    let mut sum_squared = 0.0;
+    for i in start_idx + 1..=end_idx {
+        let prev = data[i - 1].close;
+        let curr = data[i].close;
+        if prev <= 0.0 || curr <= 0.0 {
+            return None;
+        }
+        sum_squared += (curr / prev).ln().powi(2);
+    }
+
+    Some((sum_squared * 252.0 / window as f64).sqrt())
+}
+
+// Annualized downside semi-volatility over the same window: only negative
+// daily log returns contribute, sqrt(252/w * sum(min(r, 0)^2)).
+pub fn downside_realized_volatility(
+    data: &[Ohlcv],
+    start_idx: usize,
+    window: usize,
+) -> Option<f64> {
+    if window == 0 {
+        return None;
+    }
+    let end_idx = start_idx.checked_add(window)?;
+    if end_idx >= data.len() {
+        return None;
+    }
+
+    let mut sum_squared = 0.0;
+    for i in start_idx + 1..=end_idx {
+        let prev = data[i - 1].close;
+        let curr = data[i].close;
+        if prev <= 0.0 || curr <= 0.0 {
+            return None;
+        }
+        sum_squared += (curr / prev).ln().min(0.0).powi(2);
+    }
+
+    Some((sum_squared * 252.0 / window as f64).sqrt())
+}
+
+// The date a forecast made on `date` for the next `window` trading days is
+// scored up to: the close `window` rows later. None when the data ends first.
+pub fn forecast_window_end(data: &[Ohlcv], date: NaiveDate, window: usize) -> Option<NaiveDate> {
+    let start_idx = data.iter().position(|d| d.date == date)?;
+    data.get(start_idx.checked_add(window)?).map(|d| d.date)
+}
+
+// Score a single forecast made at start_idx for the next `window` trading days.
+fn score_forecast(
+    ohlcv_data: &[Ohlcv],
+    start_idx: usize,
+    window: usize,
+    forecast_vol: f64,
+    mode: ScoringMode,
+) -> Option<f64> {
+    match mode {
+        ScoringMode::PriceMove => {
+            let end_idx = start_idx.checked_add(window)?;
+            if end_idx >= ohlcv_data.len() {
+                return None;
+            }
+            let s_t = ohlcv_data[start_idx].close;
+            let s_t_plus_w = ohlcv_data[end_idx].close;
+            if s_t <= 0.0 || s_t_plus_w <= 0.0 {
+                return None;
+            }
+            let actual_magnitude = (s_t_plus_w - s_t).abs();
+            let time_factor = (window as f64 / 252.0).sqrt();
+            let expected_magnitude = s_t * forecast_vol * time_factor;
+            Some(actual_magnitude - expected_magnitude)
+        }
+        ScoringMode::RealizedVolatility => {
+            let realized = realized_volatility(ohlcv_data, start_idx, window)?;
+            Some(realized - forecast_vol)
+        }
+        ScoringMode::RealizedVariance => {
+            let realized = realized_volatility(ohlcv_data, start_idx, window)?;
+            Some(realized.powi(2) - forecast_vol.powi(2))
+        }
+    }
+}
+
 // Test for HV and IV accuracy. We compare the HV/IV 'prediction' to the next date.
-pub fn iv_accuracy(
+pub fn iv_accuracy_with_mode(
     option_data: &[OptionsData],
     ohlcv_data: &[Ohlcv],
     window: usize,
-) -> Vec<(String, f64)> {
-    let mut accuracy_series: Vec<(String, f64)> = Vec::new();
+    mode: ScoringMode,
+) -> TimeSeries<f64> {
+    let mut accuracy_series = TimeSeries::new();
 
     for option_datum in option_data {
         let start_index = ohlcv_data.iter().position(|d| d.date == option_datum.date);
 
         if let Some(start_idx) = start_index {
-            let end_index = start_idx.checked_add(window);
-
-            if let Some(end_idx) = end_index {
-                if end_idx < ohlcv_data.len() {
-                    let s_t = ohlcv_data[start_idx].close;
-                    let s_t_plus_w = ohlcv_data[end_idx].close;
-
-                    if s_t > 0.0 && s_t_plus_w > 0.0 {
-                        let actual_magnitude = (s_t_plus_w - s_t).abs();
-                        let implied_volatility = option_datum.implied_volatility;
-                        let time_factor = (window as f64 / 252.0).sqrt();
-                        let expected_magnitude = s_t * implied_volatility * time_factor;
-
-                        accuracy_series.push((
-                            option_datum.date.clone(),
-                            actual_magnitude - expected_magnitude,
-                        ));
-                    }
-                }
+            if let Some(error) = score_forecast(
+                ohlcv_data,
+                start_idx,
+                window,
+                option_datum.implied_volatility,
+                mode,
+            ) {
+                accuracy_series.insert(option_datum.date, error);
             }
         }
     }
     accuracy_series
 }
 
-pub fn hv_accuracy(ohlcv_data: &[Ohlcv], window: usize) -> Vec<(String, f64)> {
-    let mut accuracy_series: Vec<(String, f64)> = Vec::new();
+pub fn hv_accuracy_with_mode(
+    ohlcv_data: &[Ohlcv],
+    window: usize,
+    mode: ScoringMode,
+) -> TimeSeries<f64> {
+    let mut accuracy_series = TimeSeries::new();
 
     let hv_series = historical_volatility(ohlcv_data, window);
 
     for (start_idx, ohlcv_entry) in ohlcv_data.iter().enumerate() {
         if let Some(Some(hv)) = hv_series.get(start_idx) {
-            let end_index = start_idx.checked_add(window);
-
-            if let Some(end_idx) = end_index {
-                if end_idx < ohlcv_data.len() {
-                    let s_t = ohlcv_entry.close;
-                    let s_t_plus_w = ohlcv_data[end_idx].close;
-
-                    if s_t > 0.0 && s_t_plus_w > 0.0 {
-                        let actual_magnitude = (s_t_plus_w - s_t).abs();
-                        let time_factor = (window as f64 / 252.0).sqrt();
-                        let expected_magnitude = s_t * hv * time_factor;
-
-                        accuracy_series.push((
-                            ohlcv_entry.date.clone(),
-                            actual_magnitude - expected_magnitude,
-                        ));
-                    }
-                }
+            if let Some(error) = score_forecast(ohlcv_data, start_idx, window, *hv, mode) {
+                accuracy_series.insert(ohlcv_entry.date, error);
             }
         }
     }
     accuracy_series
 }
 
-pub fn calculate_mae(accuracy_data: &[(String, f64)]) -> Option<f64> {
+// Aligned (IV, realized vol over [t, t+w]) pairs for the loss functions.
+pub fn iv_forecast_pairs(
+    option_data: &[OptionsData],
+    ohlcv_data: &[Ohlcv],
+    window: usize,
+) -> Vec<ForecastPair> {
+    let iv_series: TimeSeries<f64> = option_data
+        .iter()
+        .map(|option| (option.date, option.implied_volatility))
+        .collect();
+    forecast_pairs(&iv_series, ohlcv_data, window)
+}
+
+// Aligned (forecast, realized vol over [t, t+w]) pairs for any dated vol
+// forecast, e.g. a model's expected volatility.
+pub fn forecast_pairs(
+    forecasts: &TimeSeries<f64>,
+    ohlcv_data: &[Ohlcv],
+    window: usize,
+) -> Vec<ForecastPair> {
+    let mut pairs = Vec::new();
+
+    for (date, forecast) in forecasts.iter() {
+        let start_index = ohlcv_data.iter().position(|d| d.date == *date);
+
+        if let Some(start_idx) = start_index {
+            if let Some(realized) = realized_volatility(ohlcv_data, start_idx, window) {
+                pairs.push(ForecastPair {
+                    date: *date,
+                    forecast: *forecast,
+                    realized,
+                });
+            }
+        }
+    }
+    pairs
+}
+
+// Aligned (HV, realized vol over [t, t+w]) pairs for the loss functions.
+pub fn hv_forecast_pairs(ohlcv_data: &[Ohlcv], window: usize) -> Vec<ForecastPair> {
+    let mut pairs = Vec::new();
+
+    let hv_series = historical_volatility(ohlcv_data, window);
+
+    for (start_idx, ohlcv_entry) in ohlcv_data.iter().enumerate() {
+        if let Some(Some(hv)) = hv_series.get(start_idx) {
+            if let Some(realized) = realized_volatility(ohlcv_data, start_idx, window) {
+                pairs.push(ForecastPair {
+                    date: ohlcv_entry.date,
+                    forecast: *hv,
+                    realized,
+                });
+            }
+        }
+    }
+    pairs
+}
+
+pub fn calculate_mae(accuracy_data: &[(NaiveDate, f64)]) -> Option<f64> {
     let mut total_abs_diff = 0.0;
     let mut count = 0;
 
@@ -152,44 +275,380 @@ pub fn calculate_mae(accuracy_data: &[(String, f64)]) -> Option<f64> {
     }
 }
 
-pub fn calculate_accuracy_correlation(
-    iv_accuracy_data: &[(String, f64)],
-    hv_accuracy_data: &[(String, f64)],
-) -> Option<f64> {
-    let iv_map: HashMap<String, f64> = iv_accuracy_data.iter().cloned().collect();
-    let hv_map: HashMap<String, f64> = hv_accuracy_data.iter().cloned().collect();
+// HAC confidence interval for the MAE. Overlapping forecast windows make the
+// absolute errors autocorrelated, so an i.i.d. interval would be too narrow.
+pub fn calculate_mae_confidence_interval(
+    accuracy_data: &TimeSeries<f64>,
+    lags: LagSelection,
+    level: f64,
+) -> Option<ConfidenceInterval> {
+    let absolute_errors: Vec<f64> = accuracy_data.values().map(|value| value.abs()).collect();
+    mean_confidence_interval(&absolute_errors, lags, level)
+}
 
-    let mut paired_data: Vec<(f64, f64)> = Vec::new();
+// IV and HV accuracy values on their common dates as (iv, hv).
+pub fn paired_accuracy_series(
+    iv_accuracy_data: &TimeSeries<f64>,
+    hv_accuracy_data: &TimeSeries<f64>,
+) -> TimeSeries<(f64, f64)> {
+    iv_accuracy_data.inner_join(hv_accuracy_data)
+}
 
-    let mut common_dates: Vec<String> = iv_map.keys().cloned().collect();
-    common_dates.retain(|date| hv_map.contains_key(date));
-    common_dates.sort();
+// IV and HV accuracy values on their common dates, sorted by date.
+pub fn paired_accuracy_values(
+    iv_accuracy_data: &TimeSeries<f64>,
+    hv_accuracy_data: &TimeSeries<f64>,
+) -> Vec<(f64, f64)> {
+    paired_accuracy_series(iv_accuracy_data, hv_accuracy_data)
+        .values()
+        .copied()
+        .collect()
+}
 
-    for date in common_dates {
-        if let (Some(&iv_val), Some(&hv_val)) = (iv_map.get(&date), hv_map.get(&date)) {
-            paired_data.push((iv_val, hv_val));
+// Correlation between IV and HV accuracy with a standard error and p-value,
+// HAC-robust when `estimator` is NeweyWest.
+pub fn accuracy_correlation_test(
+    iv_accuracy_data: &TimeSeries<f64>,
+    hv_accuracy_data: &TimeSeries<f64>,
+    estimator: CovarianceEstimator,
+) -> Option<CorrelationTest> {
+    let paired_data = paired_accuracy_values(iv_accuracy_data, hv_accuracy_data);
+    let x: Vec<f64> = paired_data.iter().map(|(x, _)| *x).collect();
+    let y: Vec<f64> = paired_data.iter().map(|(_, y)| *y).collect();
+    correlation_test(&x, &y, estimator)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorrelationMethod {
+    Pearson,
+    // Pearson correlation of the ranks (average rank for ties).
+    Spearman,
+    // Kendall's tau-b, which corrects for ties.
+    Kendall,
+}
+
+// Why a correlation could not be computed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CorrelationError {
+    InsufficientData { common_points: usize },
+    // One of the series is constant, so the correlation is undefined.
+    ZeroVariance { series: &'static str },
+    NonFiniteValue,
+}
+
+impl fmt::Display for CorrelationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorrelationError::InsufficientData { common_points } => write!(
+                f,
+                "not enough common data points ({}, need at least 2)",
+                common_points
+            ),
+            CorrelationError::ZeroVariance { series } => {
+                write!(f, "{} series has zero variance", series)
+            }
+            CorrelationError::NonFiniteValue => write!(f, "series contains NaN or infinite values"),
         }
     }
+}
+
+impl std::error::Error for CorrelationError {}
 
+pub fn calculate_accuracy_correlation(
+    iv_accuracy_data: &TimeSeries<f64>,
+    hv_accuracy_data: &TimeSeries<f64>,
+    method: CorrelationMethod,
+) -> Result<f64, CorrelationError> {
+    let paired_data = paired_accuracy_values(iv_accuracy_data, hv_accuracy_data);
+    paired_correlation(&paired_data, method)
+}
+
+// Correlation over a trailing window of `window` common dates, labelled by the
+// last date in each window.
+pub fn rolling_accuracy_correlation(
+    iv_accuracy_data: &TimeSeries<f64>,
+    hv_accuracy_data: &TimeSeries<f64>,
+    window: usize,
+    method: CorrelationMethod,
+) -> Vec<(NaiveDate, Result<f64, CorrelationError>)> {
+    let paired_data = paired_accuracy_series(iv_accuracy_data, hv_accuracy_data);
+    if window == 0 || paired_data.len() < window {
+        return Vec::new();
+    }
+
+    paired_data
+        .windows(window)
+        .map(|slice| {
+            let values: Vec<(f64, f64)> = slice.iter().map(|(_, pair)| *pair).collect();
+            let date = slice[slice.len() - 1].0;
+            (date, paired_correlation(&values, method))
+        })
+        .collect()
+}
+
+pub fn paired_correlation(
+    paired_data: &[(f64, f64)],
+    method: CorrelationMethod,
+) -> Result<f64, CorrelationError> {
     if paired_data.len() < 2 {
-        return None;
+        return Err(CorrelationError::InsufficientData {
+            common_points: paired_data.len(),
+        });
+    }
+    if paired_data
+        .iter()
+        .any(|(x, y)| !x.is_finite() || !y.is_finite())
+    {
+        return Err(CorrelationError::NonFiniteValue);
+    }
+    let (first_x, first_y) = paired_data[0];
+    if paired_data.iter().all(|(x, _)| *x == first_x) {
+        return Err(CorrelationError::ZeroVariance { series: "first" });
+    }
+    if paired_data.iter().all(|(_, y)| *y == first_y) {
+        return Err(CorrelationError::ZeroVariance { series: "second" });
     }
 
+    match method {
+        CorrelationMethod::Pearson => Ok(pearson(paired_data)),
+        CorrelationMethod::Spearman => {
+            let x_ranks = ranks(&paired_data.iter().map(|(x, _)| *x).collect::<Vec<_>>());
+            let y_ranks = ranks(&paired_data.iter().map(|(_, y)| *y).collect::<Vec<_>>());
+            let ranked: Vec<(f64, f64)> = x_ranks.into_iter().zip(y_ranks).collect();
+            Ok(pearson(&ranked))
+        }
+        CorrelationMethod::Kendall => Ok(kendall_tau_b(paired_data)),
+    }
+}
+
+// Pearson correlation; callers have already ruled out constant series.
+fn pearson(paired_data: &[(f64, f64)]) -> f64 {
     let n = paired_data.len() as f64;
+    let mean_x = paired_data.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = paired_data.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let mut sum_xy = 0.0;
+    let mut sum_x2 = 0.0;
+    let mut sum_y2 = 0.0;
+    for (x, y) in paired_data {
+        sum_xy += (x - mean_x) * (y - mean_y);
+        sum_x2 += (x - mean_x).powi(2);
+        sum_y2 += (y - mean_y).powi(2);
+    }
 
-    let sum_x: f64 = paired_data.iter().map(|(x, _)| x).sum();
-    let sum_y: f64 = paired_data.iter().map(|(_, y)| y).sum();
-    let sum_xy: f64 = paired_data.iter().map(|(x, y)| x * y).sum();
-    let sum_x2: f64 = paired_data.iter().map(|(x, _)| x.powi(2)).sum();
-    let sum_y2: f64 = paired_data.iter().map(|(_, y)| y.powi(2)).sum();
+    (sum_xy / (sum_x2 * sum_y2).sqrt()).clamp(-1.0, 1.0)
+}
 
-    let numerator = n * sum_xy - sum_x * sum_y;
-    let denominator_x = (n * sum_x2 - sum_x.powi(2)).sqrt();
-    let denominator_y = (n * sum_y2 - sum_y.powi(2)).sqrt();
+// 1-based ranks, ties get the average of the ranks they span.
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| {
+        values[a]
+            .partial_cmp(&values[b])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut result = vec![0.0; values.len()];
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i;
+        while j + 1 < order.len() && values[order[j + 1]] == values[order[i]] {
+            j += 1;
+        }
+        let average_rank = (i + j) as f64 / 2.0 + 1.0;
+        for &index in &order[i..=j] {
+            result[index] = average_rank;
+        }
+        i = j + 1;
+    }
+    result
+}
 
-    if denominator_x == 0.0 || denominator_y == 0.0 {
-        return Some(0.0);
+fn kendall_tau_b(paired_data: &[(f64, f64)]) -> f64 {
+    let mut concordant = 0.0_f64;
+    let mut discordant = 0.0_f64;
+    let mut ties_x = 0.0_f64;
+    let mut ties_y = 0.0_f64;
+
+    for i in 0..paired_data.len() {
+        for j in i + 1..paired_data.len() {
+            let dx = paired_data[i].0 - paired_data[j].0;
+            let dy = paired_data[i].1 - paired_data[j].1;
+            if dx == 0.0 && dy == 0.0 {
+                continue;
+            } else if dx == 0.0 {
+                ties_x += 1.0;
+            } else if dy == 0.0 {
+                ties_y += 1.0;
+            } else if dx * dy > 0.0 {
+                concordant += 1.0;
+            } else {
+                discordant += 1.0;
+            }
+        }
     }
 
-    Some(numerator / (denominator_x * denominator_y))
+    let denominator =
+        ((concordant + discordant + ties_x) * (concordant + discordant + ties_y)).sqrt();
+    (concordant - discordant) / denominator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::estimators::{CloseToClose, VolatilityForecaster};
+    use chrono::Duration;
+
+    fn series(values: &[f64]) -> TimeSeries<f64> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| (start + Duration::days(i as i64), value))
+            .collect()
+    }
+
+    fn pairs(x: &[f64], y: &[f64]) -> Vec<(f64, f64)> {
+        x.iter().copied().zip(y.iter().copied()).collect()
+    }
+
+    fn correlation(x: &[f64], y: &[f64], method: CorrelationMethod) -> f64 {
+        paired_correlation(&pairs(x, y), method).unwrap()
+    }
+
+    #[test]
+    fn historical_volatility_is_dated_on_the_last_close_of_its_window() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let data: Vec<Ohlcv> = [100.0, 101.0, 99.0, 102.0, 103.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| Ohlcv {
+                open: close,
+                high: close,
+                low: close,
+                close,
+                date: start + Duration::days(i as i64),
+            })
+            .collect();
+        let hv = historical_volatility(&data, 2);
+        assert_eq!(hv.len(), data.len());
+        assert!(hv[..2].iter().all(Option::is_none));
+        // Entry i uses the two returns ending at close i, as CloseToClose does.
+        let forecasts = CloseToClose { window: 2 }.forecasts(&data, 1);
+        for i in 2..data.len() {
+            let r1 = (data[i - 1].close / data[i - 2].close).ln();
+            let r2 = (data[i].close / data[i - 1].close).ln();
+            let expected = (r1 - r2).abs() / 2.0 * 252f64.sqrt();
+            assert!((hv[i].unwrap() - expected).abs() < 1e-12);
+            assert!((hv[i].unwrap() - forecasts[i].unwrap()).abs() < 1e-12);
+        }
+        assert!(historical_volatility(&data[..2], 2)
+            .iter()
+            .all(Option::is_none));
+    }
+
+    #[test]
+    fn pearson_matches_hand_computation() {
+        // Sxy = 8, Sxx = Syy = 10.
+        let r = correlation(
+            &[1.0, 2.0, 3.0, 4.0, 5.0],
+            &[1.0, 3.0, 2.0, 5.0, 4.0],
+            CorrelationMethod::Pearson,
+        );
+        assert!((r - 0.8).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rank_correlations_are_one_under_monotone_transforms() {
+        let x = [0.1, 0.5, 0.2, 0.9, 0.3, 0.7];
+        let increasing: Vec<f64> = x.iter().map(|v: &f64| v.exp().powi(3)).collect();
+        let decreasing: Vec<f64> = x.iter().map(|v| -v.ln()).collect();
+        for method in [CorrelationMethod::Spearman, CorrelationMethod::Kendall] {
+            assert!((correlation(&x, &increasing, method) - 1.0).abs() < 1e-12);
+            assert!((correlation(&x, &decreasing, method) + 1.0).abs() < 1e-12);
+        }
+        // Pearson only sees the linear part.
+        assert!(correlation(&x, &increasing, CorrelationMethod::Pearson) < 1.0 - 1e-3);
+    }
+
+    #[test]
+    fn ties_get_average_ranks_and_tau_b() {
+        assert_eq!(ranks(&[3.0, 1.0, 2.0, 2.0]), vec![4.0, 1.0, 2.5, 2.5]);
+
+        let x = [1.0, 2.0, 2.0, 3.0];
+        let y = [1.0, 3.0, 2.0, 4.0];
+        // Five concordant pairs, none discordant, one tied in x only:
+        // tau-b = 5 / sqrt((6 - 1) * (6 - 0)).
+        let tau = correlation(&x, &y, CorrelationMethod::Kendall);
+        assert!((tau - 5.0 / 30f64.sqrt()).abs() < 1e-12);
+        // Spearman is Pearson on ranks [1, 2.5, 2.5, 4] and [1, 3, 2, 4].
+        let rho = correlation(&x, &y, CorrelationMethod::Spearman);
+        let expected = correlation(
+            &[1.0, 2.5, 2.5, 4.0],
+            &[1.0, 3.0, 2.0, 4.0],
+            CorrelationMethod::Pearson,
+        );
+        assert!((rho - expected).abs() < 1e-12);
+        assert!((rho - 4.5 / 22.5f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reports_why_a_correlation_is_undefined() {
+        for method in [
+            CorrelationMethod::Pearson,
+            CorrelationMethod::Spearman,
+            CorrelationMethod::Kendall,
+        ] {
+            assert_eq!(
+                paired_correlation(&[], method),
+                Err(CorrelationError::InsufficientData { common_points: 0 })
+            );
+            assert_eq!(
+                paired_correlation(&[(0.1, 0.2)], method),
+                Err(CorrelationError::InsufficientData { common_points: 1 })
+            );
+            assert_eq!(
+                paired_correlation(&pairs(&[0.1, 0.1, 0.1], &[0.1, 0.2, 0.3]), method),
+                Err(CorrelationError::ZeroVariance { series: "first" })
+            );
+            assert_eq!(
+                paired_correlation(&pairs(&[0.1, 0.2, 0.3], &[0.4, 0.4, 0.4]), method),
+                Err(CorrelationError::ZeroVariance { series: "second" })
+            );
+            assert_eq!(
+                paired_correlation(&pairs(&[0.1, f64::NAN], &[0.1, 0.2]), method),
+                Err(CorrelationError::NonFiniteValue)
+            );
+        }
+    }
+
+    #[test]
+    fn accuracy_correlation_uses_common_dates_only() {
+        let iv = series(&[0.1, 0.2, 0.3, 0.4]);
+        // One extra trailing date that IV lacks.
+        let hv = series(&[0.2, 0.4, 0.6, 0.8, -5.0]);
+        let r = calculate_accuracy_correlation(&iv, &hv, CorrelationMethod::Pearson).unwrap();
+        assert!((r - 1.0).abs() < 1e-12);
+        assert_eq!(
+            calculate_accuracy_correlation(&iv, &series(&[0.3]), CorrelationMethod::Pearson),
+            Err(CorrelationError::InsufficientData { common_points: 1 })
+        );
+    }
+
+    #[test]
+    fn rolling_correlation_labels_windows_by_their_last_date() {
+        let iv = series(&[0.1, 0.2, 0.3, 0.3, 0.3]);
+        let hv = series(&[0.3, 0.2, 0.1, 0.5, 0.4]);
+        let rolling = rolling_accuracy_correlation(&iv, &hv, 3, CorrelationMethod::Spearman);
+        let dates: Vec<NaiveDate> = rolling.iter().map(|(date, _)| *date).collect();
+        assert_eq!(dates, iv.dates().skip(2).collect::<Vec<_>>());
+        assert!((rolling[0].1.clone().unwrap() + 1.0).abs() < 1e-12);
+        // The last window's IV values are all 0.3.
+        assert_eq!(
+            rolling[2].1,
+            Err(CorrelationError::ZeroVariance { series: "first" })
+        );
+        assert!(rolling_accuracy_correlation(&iv, &hv, 6, CorrelationMethod::Spearman).is_empty());
+        assert!(rolling_accuracy_correlation(&iv, &hv, 0, CorrelationMethod::Spearman).is_empty());
+    }
 }
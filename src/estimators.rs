@@ -0,0 +1,430 @@
+// Volatility estimators and forecasters behind one interface, so they can be
+// compared on the same data. Each produces, for every close, an annualized
+// vol forecast over the next `horizon` trading days using only data up to
+// that close. The rolling estimators (close-to-close and range-based) and
+// EWMA forecast their current estimate at every horizon; GARCH mean-reverts
+// its forecast towards the long-run variance.
+
+use crate::api::Ohlcv;
+use crate::optimize::NelderMead;
+use crate::simulation::VolatilityModel;
+
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+const MAX_GARCH_PERSISTENCE: f64 = 0.999;
+
+pub trait VolatilityForecaster {
+    fn name(&self) -> String;
+
+    // One entry per element of `data`, None until there is enough history.
+    fn forecasts(&self, data: &[Ohlcv], horizon: usize) -> Vec<Option<f64>>;
+}
+
+// Close-to-close log returns, None on the first day or where either close is
+// not positive.
+fn log_returns(data: &[Ohlcv]) -> Vec<Option<f64>> {
+    let mut returns = vec![None; data.len().min(1)];
+    for pair in data.windows(2) {
+        let (prev, curr) = (pair[0].close, pair[1].close);
+        returns.push((prev > 0.0 && curr > 0.0).then(|| (curr / prev).ln()));
+    }
+    returns
+}
+
+fn has_range(day: &Ohlcv) -> bool {
+    day.open > 0.0 && day.high > 0.0 && day.low > 0.0 && day.close > 0.0 && day.high >= day.low
+}
+
+// Annualized root of the mean of a daily variance measure over each trailing
+// window of `window` days. Days the measure cannot be computed for leave their
+// windows None.
+fn rolling_mean_variance(
+    data: &[Ohlcv],
+    window: usize,
+    daily: impl Fn(&Ohlcv) -> f64,
+) -> Vec<Option<f64>> {
+    let values: Vec<Option<f64>> = data
+        .iter()
+        .map(|day| has_range(day).then(|| daily(day)))
+        .collect();
+    (0..data.len())
+        .map(|i| {
+            if window == 0 || i + 1 < window {
+                return None;
+            }
+            let window_values: Option<Vec<f64>> =
+                values[i + 1 - window..=i].iter().copied().collect();
+            let mean = window_values?.iter().sum::<f64>() / window as f64;
+            Some((mean.max(0.0) * TRADING_DAYS_PER_YEAR).sqrt())
+        })
+        .collect()
+}
+
+// Population-variance close-to-close HV, as in `historical_volatility`, over
+// the `window` returns ending at each close.
+#[derive(Debug, Clone, Copy)]
+pub struct CloseToClose {
+    pub window: usize,
+}
+
+impl VolatilityForecaster for CloseToClose {
+    fn name(&self) -> String {
+        format!("Close-to-close {}d", self.window)
+    }
+
+    fn forecasts(&self, data: &[Ohlcv], _horizon: usize) -> Vec<Option<f64>> {
+        let window = self.window;
+        let returns = log_returns(data);
+        (0..data.len())
+            .map(|i| {
+                if window < 2 || i < window {
+                    return None;
+                }
+                let window_returns: Vec<f64> = returns[i + 1 - window..=i]
+                    .iter()
+                    .copied()
+                    .collect::<Option<_>>()?;
+                let mean = window_returns.iter().sum::<f64>() / window as f64;
+                let variance = window_returns
+                    .iter()
+                    .map(|r| (r - mean).powi(2))
+                    .sum::<f64>()
+                    / window as f64;
+                Some((variance * TRADING_DAYS_PER_YEAR).sqrt())
+            })
+            .collect()
+    }
+}
+
+// High-low range estimator. Sees only the trading session, so it misses
+// overnight moves, and discrete prices bias the range low.
+#[derive(Debug, Clone, Copy)]
+pub struct Parkinson {
+    pub window: usize,
+}
+
+impl VolatilityForecaster for Parkinson {
+    fn name(&self) -> String {
+        format!("Parkinson {}d", self.window)
+    }
+
+    fn forecasts(&self, data: &[Ohlcv], _horizon: usize) -> Vec<Option<f64>> {
+        rolling_mean_variance(data, self.window, |day| {
+            (day.high / day.low).ln().powi(2) / (4.0 * std::f64::consts::LN_2)
+        })
+    }
+}
+
+// Open-high-low-close estimator for a driftless session.
+#[derive(Debug, Clone, Copy)]
+pub struct GarmanKlass {
+    pub window: usize,
+}
+
+impl VolatilityForecaster for GarmanKlass {
+    fn name(&self) -> String {
+        format!("Garman-Klass {}d", self.window)
+    }
+
+    fn forecasts(&self, data: &[Ohlcv], _horizon: usize) -> Vec<Option<f64>> {
+        rolling_mean_variance(data, self.window, |day| {
+            0.5 * (day.high / day.low).ln().powi(2)
+                - (2.0 * std::f64::consts::LN_2 - 1.0) * (day.close / day.open).ln().powi(2)
+        })
+    }
+}
+
+// Session estimator that stays unbiased under a non-zero drift.
+#[derive(Debug, Clone, Copy)]
+pub struct RogersSatchell {
+    pub window: usize,
+}
+
+fn rogers_satchell_variance(day: &Ohlcv) -> f64 {
+    (day.high / day.close).ln() * (day.high / day.open).ln()
+        + (day.low / day.close).ln() * (day.low / day.open).ln()
+}
+
+impl VolatilityForecaster for RogersSatchell {
+    fn name(&self) -> String {
+        format!("Rogers-Satchell {}d", self.window)
+    }
+
+    fn forecasts(&self, data: &[Ohlcv], _horizon: usize) -> Vec<Option<f64>> {
+        rolling_mean_variance(data, self.window, rogers_satchell_variance)
+    }
+}
+
+// Yang-Zhang: overnight variance plus a weighted mix of open-to-close and
+// Rogers-Satchell variance, so opening gaps are counted. Each window needs the
+// close before its first day.
+#[derive(Debug, Clone, Copy)]
+pub struct YangZhang {
+    pub window: usize,
+}
+
+impl VolatilityForecaster for YangZhang {
+    fn name(&self) -> String {
+        format!("Yang-Zhang {}d", self.window)
+    }
+
+    fn forecasts(&self, data: &[Ohlcv], _horizon: usize) -> Vec<Option<f64>> {
+        let n = self.window;
+        if n < 2 {
+            return vec![None; data.len()];
+        }
+        let sample_variance = |values: &[f64]| {
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64
+        };
+        let k = 0.34 / (1.34 + (n + 1) as f64 / (n - 1) as f64);
+
+        (0..data.len())
+            .map(|i| {
+                if i < n {
+                    return None;
+                }
+                let mut overnight = Vec::with_capacity(n);
+                let mut open_to_close = Vec::with_capacity(n);
+                let mut rogers_satchell = 0.0;
+                for j in i + 1 - n..=i {
+                    let (prev, day) = (&data[j - 1], &data[j]);
+                    if !has_range(day) || prev.close <= 0.0 {
+                        return None;
+                    }
+                    overnight.push((day.open / prev.close).ln());
+                    open_to_close.push((day.close / day.open).ln());
+                    rogers_satchell += rogers_satchell_variance(day);
+                }
+                let variance = sample_variance(&overnight)
+                    + k * sample_variance(&open_to_close)
+                    + (1.0 - k) * rogers_satchell / n as f64;
+                Some((variance.max(0.0) * TRADING_DAYS_PER_YEAR).sqrt())
+            })
+            .collect()
+    }
+}
+
+// RiskMetrics-style exponentially weighted squared returns,
+// var_t = lambda * var_{t-1} + (1 - lambda) * r_t^2, seeded with the mean
+// squared return over the first `warmup` returns.
+#[derive(Debug, Clone, Copy)]
+pub struct Ewma {
+    pub lambda: f64,
+    pub warmup: usize,
+}
+
+impl Default for Ewma {
+    fn default() -> Self {
+        Ewma {
+            lambda: 0.94,
+            warmup: 20,
+        }
+    }
+}
+
+impl VolatilityForecaster for Ewma {
+    fn name(&self) -> String {
+        format!("EWMA {:.2}", self.lambda)
+    }
+
+    fn forecasts(&self, data: &[Ohlcv], _horizon: usize) -> Vec<Option<f64>> {
+        let warmup = self.warmup.max(1);
+        let mut variance: Option<f64> = None;
+        let mut seed = Vec::with_capacity(warmup);
+        log_returns(data)
+            .into_iter()
+            .map(|r| {
+                match (r, variance) {
+                    // A missing return breaks the recursion; start again.
+                    (None, _) => {
+                        variance = None;
+                        seed.clear();
+                    }
+                    (Some(r), Some(v)) => {
+                        variance = Some(self.lambda * v + (1.0 - self.lambda) * r * r);
+                    }
+                    (Some(r), None) => {
+                        seed.push(r * r);
+                        if seed.len() == warmup {
+                            variance = Some(seed.iter().sum::<f64>() / warmup as f64);
+                        }
+                    }
+                }
+                variance.map(|v| (v * TRADING_DAYS_PER_YEAR).sqrt())
+            })
+            .collect()
+    }
+}
+
+// GARCH(1,1) fitted by Gaussian quasi-maximum likelihood on the first
+// `fit_days` returns (zero mean), then run forward with those parameters. The
+// forecast is the root of the expected average variance over the horizon.
+#[derive(Debug, Clone, Copy)]
+pub struct GarchForecaster {
+    pub fit_days: usize,
+}
+
+impl VolatilityForecaster for GarchForecaster {
+    fn name(&self) -> String {
+        format!("GARCH(1,1) fit on {}d", self.fit_days)
+    }
+
+    fn forecasts(&self, data: &[Ohlcv], horizon: usize) -> Vec<Option<f64>> {
+        let returns = log_returns(data);
+        let mut forecasts = vec![None; data.len()];
+        // Returns from index 1; a gap anywhere in the fitted sample gives up.
+        let Some(fit_sample) = returns
+            .get(1..=self.fit_days)
+            .and_then(|sample| sample.iter().copied().collect::<Option<Vec<f64>>>())
+        else {
+            return forecasts;
+        };
+        let Some(model) = fit_garch(&fit_sample) else {
+            return forecasts;
+        };
+        let VolatilityModel::Garch { omega, alpha, beta } = model else {
+            return forecasts;
+        };
+
+        let mut daily_variance =
+            fit_sample.iter().map(|r| r * r).sum::<f64>() / self.fit_days as f64;
+        for (i, r) in returns.iter().enumerate().skip(1) {
+            let Some(r) = r else {
+                // Keep the variance through a missing return.
+                continue;
+            };
+            daily_variance = omega + alpha * r * r + beta * daily_variance;
+            if i >= self.fit_days {
+                let average = model.expected_average_variance(
+                    daily_variance * TRADING_DAYS_PER_YEAR,
+                    horizon as f64 / TRADING_DAYS_PER_YEAR,
+                );
+                forecasts[i] = Some(average.sqrt());
+            }
+        }
+        forecasts
+    }
+}
+
+// Gaussian QMLE for GARCH(1,1) on daily returns. Parameters are searched in
+// an unconstrained space mapped to omega > 0, alpha, beta >= 0 and
+// alpha + beta < 0.999, which keeps the long-run variance finite when the
+// returns show no clustering. None for fewer than 30 returns or a failed fit.
+pub fn fit_garch(returns: &[f64]) -> Option<VolatilityModel> {
+    if returns.len() < 30 {
+        return None;
+    }
+    let sample_variance = returns.iter().map(|r| r * r).sum::<f64>() / returns.len() as f64;
+    if sample_variance <= 0.0 {
+        return None;
+    }
+    let logistic = |x: f64| 1.0 / (1.0 + (-x).exp());
+    // (ln omega, logit persistence, logit alpha share of persistence)
+    let unpack = |x: &[f64]| {
+        let persistence = MAX_GARCH_PERSISTENCE * logistic(x[1]);
+        let alpha = persistence * logistic(x[2]);
+        (x[0].exp(), alpha, persistence - alpha)
+    };
+    let negative_log_likelihood = |x: &[f64]| {
+        let (omega, alpha, beta) = unpack(x);
+        let mut variance = sample_variance;
+        let mut total = 0.0;
+        for r in returns {
+            total += variance.ln() + r * r / variance;
+            variance = omega + alpha * r * r + beta * variance;
+        }
+        0.5 * total
+    };
+
+    // Start from persistence 0.95 with alpha 0.05, at the sample variance.
+    let start_persistence: f64 = 0.95;
+    let start = [
+        (sample_variance * (1.0 - start_persistence)).ln(),
+        (start_persistence / (MAX_GARCH_PERSISTENCE - start_persistence)).ln(),
+        (0.05 / (start_persistence - 0.05)).ln(),
+    ];
+    let minimum = NelderMead::default().minimize(negative_log_likelihood, &start);
+    if !minimum.value.is_finite() {
+        return None;
+    }
+    let (omega, alpha, beta) = unpack(&minimum.point);
+    Some(VolatilityModel::Garch { omega, alpha, beta })
+}
+
+// Close-to-close HV at a spread of windows, the range-based estimators at
+// `window`, EWMA and GARCH fitted on the first `fit_days` returns.
+pub fn standard_forecasters(window: usize, fit_days: usize) -> Vec<Box<dyn VolatilityForecaster>> {
+    let mut forecasters: Vec<Box<dyn VolatilityForecaster>> = [10, 21, 30, 63, 126]
+        .into_iter()
+        .chain(std::iter::once(window))
+        .collect::<std::collections::BTreeSet<usize>>()
+        .into_iter()
+        .map(|window| Box::new(CloseToClose { window }) as Box<dyn VolatilityForecaster>)
+        .collect();
+    forecasters.push(Box::new(Parkinson { window }));
+    forecasters.push(Box::new(GarmanKlass { window }));
+    forecasters.push(Box::new(RogersSatchell { window }));
+    forecasters.push(Box::new(YangZhang { window }));
+    forecasters.push(Box::new(Ewma::default()));
+    forecasters.push(Box::new(GarchForecaster { fit_days }));
+    forecasters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::MarketSimulator;
+
+    // Every forecaster must use only data up to and including each bar.
+    #[test]
+    fn forecasts_do_not_look_ahead() {
+        let data = MarketSimulator::default().simulate("SIM").unwrap().ohlcv;
+        let horizon = 21;
+        for forecaster in standard_forecasters(21, 100) {
+            let base = forecaster.forecasts(&data, horizon);
+            assert_eq!(base.len(), data.len(), "{}", forecaster.name());
+            for cutoff in [30, 120, 200] {
+                let mut changed = data.clone();
+                for (k, day) in changed[cutoff + 1..].iter_mut().enumerate() {
+                    let scale = if k % 2 == 0 { 1.05 } else { 0.93 };
+                    day.open *= scale;
+                    day.high *= scale * 1.01;
+                    day.low *= scale * 0.99;
+                    day.close *= scale;
+                }
+                let forecasts = forecaster.forecasts(&changed, horizon);
+                assert_eq!(
+                    forecasts[..=cutoff],
+                    base[..=cutoff],
+                    "{} looks past {}",
+                    forecaster.name(),
+                    cutoff
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn close_to_close_matches_window_of_returns() {
+        let closes = [100.0, 101.0, 99.0, 102.0, 103.0];
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let data: Vec<Ohlcv> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| Ohlcv {
+                open: close,
+                high: close,
+                low: close,
+                close,
+                date: date + chrono::Duration::days(i as i64),
+            })
+            .collect();
+        let forecasts = CloseToClose { window: 2 }.forecasts(&data, 1);
+        assert_eq!(forecasts.len(), data.len());
+        assert!(forecasts[..2].iter().all(Option::is_none));
+        // Returns ln(c1/c0) and ln(c2/c1), the two ending at close 2.
+        let (r1, r2) = ((101.0f64 / 100.0).ln(), (99.0f64 / 101.0).ln());
+        let expected = ((r1 - r2).abs() / 2.0) * TRADING_DAYS_PER_YEAR.sqrt();
+        assert!((forecasts[2].unwrap() - expected).abs() < 1e-12);
+    }
+}
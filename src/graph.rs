@@ -1,18 +1,19 @@
+use crate::monte_carlo::{EstimatorStats, MonteCarloReport};
+use crate::time_series::TimeSeries;
 use chrono::{Datelike, Months, NaiveDate};
 use plotters::prelude::*;
 
 pub fn draw_accuracy_graph(
-    iv_accuracy_data: Vec<(String, f64)>,
-    hv_accuracy_data: Vec<(String, f64)>,
+    iv_accuracy_data: &TimeSeries<f64>,
+    hv_accuracy_data: &TimeSeries<f64>,
     output_path: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let root = BitMapBackend::new(output_path, (1024, 768)).into_drawing_area();
     root.fill(&WHITE)?;
 
     let mut all_dates: Vec<NaiveDate> = iv_accuracy_data
-        .iter()
-        .chain(hv_accuracy_data.iter())
-        .filter_map(|(date_str, _)| NaiveDate::parse_from_str(date_str, "%Y-%m-%d").ok())
+        .dates()
+        .chain(hv_accuracy_data.dates())
         .collect();
     all_dates.sort();
     let min_date = all_dates
@@ -76,34 +77,106 @@ pub fn draw_accuracy_graph(
         .y_label_formatter(&|y| format!("{:.2}", y))
         .draw()?;
     let iv_series_points: Vec<(NaiveDate, f64)> = iv_accuracy_data
-        .into_iter()
-        .filter_map(|(date_str, val)| {
-            NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                .ok()
-                .and_then(|d| if !val.is_nan() { Some((d, val)) } else { None })
-        })
+        .iter()
+        .copied()
+        .filter(|(_, val)| !val.is_nan())
         .collect();
     chart
         .draw_series(LineSeries::new(iv_series_points, &RED).point_size(3))?
         .label("IV Accuracy")
-        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], &RED));
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], RED));
 
     let hv_series_points: Vec<(NaiveDate, f64)> = hv_accuracy_data
-        .into_iter()
-        .filter_map(|(date_str, val)| {
-            NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                .ok()
-                .and_then(|d| if !val.is_nan() { Some((d, val)) } else { None })
-        })
+        .iter()
+        .copied()
+        .filter(|(_, val)| !val.is_nan())
         .collect();
     chart
         .draw_series(LineSeries::new(hv_series_points, &BLUE).point_size(3))?
         .label("HV Accuracy")
-        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], &BLUE));
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], BLUE));
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    root.present()?;
+    Ok(())
+}
+
+// Bias and RMSE per estimator from a Monte Carlo study, as paired bars.
+pub fn draw_estimator_study(
+    report: &MonteCarloReport,
+    output_path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let root = BitMapBackend::new(output_path, (1280, 768)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let estimators: Vec<&EstimatorStats> = report
+        .estimators
+        .iter()
+        .filter(|stats| stats.rmse.is_finite())
+        .collect();
+    let count = estimators.len().max(1);
+    let min_y = estimators
+        .iter()
+        .map(|stats| stats.bias)
+        .fold(0.0, f64::min);
+    let max_y = estimators
+        .iter()
+        .flat_map(|stats| [stats.bias, stats.rmse])
+        .fold(0.01, f64::max);
+    let y_padding = (max_y - min_y) * 0.1;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(
+            format!("{}-day forecast error against true vol", report.horizon),
+            ("sans-serif", 36).into_font(),
+        )
+        .margin(10)
+        .x_label_area_size(60)
+        .y_label_area_size(60)
+        .build_cartesian_2d(
+            -0.5..count as f64 - 0.5,
+            (min_y - y_padding)..(max_y + y_padding),
+        )?;
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(count)
+        .x_label_formatter(&|x| {
+            let index = x.round();
+            if (x - index).abs() > 1e-6 || index < 0.0 {
+                return String::new();
+            }
+            estimators
+                .get(index as usize)
+                .map(|stats| stats.name.clone())
+                .unwrap_or_default()
+        })
+        .y_desc("Annualized vol")
+        .y_label_formatter(&|y| format!("{:.3}", y))
+        .draw()?;
+
+    chart
+        .draw_series(estimators.iter().enumerate().map(|(i, stats)| {
+            let x = i as f64;
+            Rectangle::new([(x - 0.35, 0.0), (x, stats.bias)], RED.filled())
+        }))?
+        .label("Bias")
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], RED.filled()));
+    chart
+        .draw_series(estimators.iter().enumerate().map(|(i, stats)| {
+            let x = i as f64;
+            Rectangle::new([(x, 0.0), (x + 0.35, stats.rmse)], BLUE.filled())
+        }))?
+        .label("RMSE")
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], BLUE.filled()));
     chart
         .configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
+        .position(SeriesLabelPosition::LowerRight)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .draw()?;
     root.present()?;
     Ok(())
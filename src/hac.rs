@@ -0,0 +1,170 @@
+// Heteroskedasticity and autocorrelation consistent (Newey-West) variance
+// estimation. Forecast errors over overlapping windows are autocorrelated, so
+// i.i.d. standard errors understate the uncertainty of every summary statistic.
+
+use crate::stats::{normal_quantile, ConfidenceInterval};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LagSelection {
+    // Newey-West (1994) rule of thumb floor(4 * (n / 100)^(2/9)).
+    Automatic,
+    // Automatic, but never fewer lags than the forecast overlap requires.
+    AtLeast(usize),
+    Fixed(usize),
+}
+
+impl LagSelection {
+    pub fn resolve(&self, n: usize) -> usize {
+        let max_lag = n.saturating_sub(1);
+        match *self {
+            LagSelection::Automatic => automatic_lag(n).min(max_lag),
+            LagSelection::AtLeast(min_lag) => automatic_lag(n).max(min_lag).min(max_lag),
+            LagSelection::Fixed(lag) => lag.min(max_lag),
+        }
+    }
+}
+
+pub fn automatic_lag(n: usize) -> usize {
+    (4.0 * (n as f64 / 100.0).powf(2.0 / 9.0)).floor() as usize
+}
+
+fn bartlett_weight(lag: usize, lags: usize) -> f64 {
+    1.0 - lag as f64 / (lags as f64 + 1.0)
+}
+
+// Newey-West long-run variance of a scalar series (Bartlett kernel, demeaned).
+pub fn long_run_variance(series: &[f64], lags: usize) -> f64 {
+    let n = series.len();
+    if n == 0 {
+        return f64::NAN;
+    }
+    let mean = series.iter().sum::<f64>() / n as f64;
+    let autocovariance = |lag: usize| -> f64 {
+        (lag..n)
+            .map(|t| (series[t] - mean) * (series[t - lag] - mean))
+            .sum::<f64>()
+            / n as f64
+    };
+
+    let mut variance = autocovariance(0);
+    for lag in 1..=lags.min(n.saturating_sub(1)) {
+        variance += 2.0 * bartlett_weight(lag, lags) * autocovariance(lag);
+    }
+    variance
+}
+
+// Newey-West long-run covariance matrix of a vector series, one row per
+// observation (Bartlett kernel, demeaned).
+pub fn long_run_covariance(series: &[Vec<f64>], lags: usize) -> Option<Vec<Vec<f64>>> {
+    let n = series.len();
+    let k = series.first()?.len();
+    if series.iter().any(|row| row.len() != k) {
+        return None;
+    }
+
+    let means: Vec<f64> = (0..k)
+        .map(|j| series.iter().map(|row| row[j]).sum::<f64>() / n as f64)
+        .collect();
+    let centered: Vec<Vec<f64>> = series
+        .iter()
+        .map(|row| row.iter().zip(&means).map(|(v, m)| v - m).collect())
+        .collect();
+
+    let autocovariance = |lag: usize| -> Vec<Vec<f64>> {
+        let mut gamma = vec![vec![0.0; k]; k];
+        for t in lag..n {
+            for a in 0..k {
+                for b in 0..k {
+                    gamma[a][b] += centered[t][a] * centered[t - lag][b];
+                }
+            }
+        }
+        gamma
+            .into_iter()
+            .map(|row| row.into_iter().map(|v| v / n as f64).collect())
+            .collect()
+    };
+
+    let mut covariance = autocovariance(0);
+    for lag in 1..=lags.min(n.saturating_sub(1)) {
+        let weight = bartlett_weight(lag, lags);
+        let gamma = autocovariance(lag);
+        for a in 0..k {
+            for b in 0..k {
+                covariance[a][b] += weight * (gamma[a][b] + gamma[b][a]);
+            }
+        }
+    }
+    Some(covariance)
+}
+
+// HAC standard error of the sample mean.
+pub fn mean_std_error(series: &[f64], lags: LagSelection) -> Option<f64> {
+    let n = series.len();
+    if n < 2 {
+        return None;
+    }
+    let variance = long_run_variance(series, lags.resolve(n));
+    if variance.is_nan() || variance < 0.0 {
+        return None;
+    }
+    Some((variance / n as f64).sqrt())
+}
+
+// Normal-approximation confidence interval for the mean using a HAC standard error.
+pub fn mean_confidence_interval(
+    series: &[f64],
+    lags: LagSelection,
+    level: f64,
+) -> Option<ConfidenceInterval> {
+    let std_error = mean_std_error(series, lags)?;
+    let estimate = series.iter().sum::<f64>() / series.len() as f64;
+    let z = normal_quantile(0.5 + level / 2.0);
+    Some(ConfidenceInterval {
+        estimate,
+        lower: estimate - z * std_error,
+        upper: estimate + z * std_error,
+        level,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_lags_give_the_population_variance() {
+        let series = [1.0, 3.0, 2.0, 6.0];
+        // Mean 3, squared deviations 4, 0, 1, 9.
+        assert!((long_run_variance(&series, 0) - 3.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn bartlett_weights_the_autocovariances() {
+        let series = [1.0, 3.0, 2.0, 6.0];
+        // gamma_1 = (0 * -2 + -1 * 0 + 3 * -1) / 4 = -0.75, weighted by 1 - 1/2.
+        assert!((long_run_variance(&series, 1) - (3.5 - 0.75)).abs() < 1e-12);
+        let matrix = long_run_covariance(&series.map(|v| vec![v]), 1).unwrap();
+        assert!((matrix[0][0] - 2.75).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lag_selection_respects_floor_and_sample_size() {
+        assert_eq!(automatic_lag(100), 4);
+        assert_eq!(LagSelection::AtLeast(6).resolve(100), 6);
+        assert_eq!(LagSelection::AtLeast(2).resolve(100), 4);
+        assert_eq!(LagSelection::Fixed(10).resolve(5), 4);
+    }
+
+    #[test]
+    fn confidence_interval_is_centred_on_the_mean() {
+        let series = [0.1, 0.4, 0.2, 0.5, 0.3, 0.6, 0.2];
+        let ci = mean_confidence_interval(&series, LagSelection::Fixed(0), 0.95).unwrap();
+        let mean = series.iter().sum::<f64>() / series.len() as f64;
+        let variance = series.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / 7.0;
+        let half_width = 1.959963985 * (variance / 7.0).sqrt();
+        assert!((ci.estimate - mean).abs() < 1e-12);
+        assert!((ci.upper - mean - half_width).abs() < 1e-8);
+        assert!((mean - ci.lower - half_width).abs() < 1e-8);
+    }
+}
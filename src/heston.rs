@@ -0,0 +1,606 @@
+// Heston (1993) stochastic volatility: semi-analytic European prices and
+// calibration to a day's chain.
+//
+// Prices use the Lewis (2001) single integral over the characteristic
+// function of ln(S_T / F), in the "little trap" form of Albrecher et al.
+// (2007) that avoids branch-cut jumps in the complex log. A Black-Scholes
+// price at the model's expected average variance is used as a control
+// variate, so only the difference of the two characteristic functions is
+// integrated; it decays fast enough for a fixed Gauss-Legendre rule.
+//
+// Calibration minimises vega-weighted squared price errors, (model - market)
+// / vega, which are approximately IV errors, over kappa, theta, xi, rho and
+// v0 with Nelder-Mead.
+
+use crate::api::OptionType;
+use crate::black_scholes::{BlackScholes, OptionPricer, PricingInputs};
+use crate::chain::OptionChain;
+use crate::optimize::NelderMead;
+use crate::parity::ForwardCurve;
+use crate::simulation::VolatilityModel;
+use chrono::NaiveDate;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// Gauss-Legendre nodes per panel and the number of panels on [0, U].
+const NODES_PER_PANEL: usize = 32;
+const MIN_PANELS: usize = 2;
+// The integral is truncated at U where the characteristic functions have
+// fallen to about e^-40. Past MAX_CUTOFF the remaining tail is below
+// sqrt(FK) / (pi U), a few cents per 100 of spot, and only reached with
+// |rho| near one or extreme vol of vol.
+const CUTOFF_EXPONENT: f64 = 40.0;
+const MAX_CUTOFF: f64 = 2000.0;
+const MIN_POINTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+
+    fn exp(self) -> Self {
+        let scale = self.re.exp();
+        Complex::new(scale * self.im.cos(), scale * self.im.sin())
+    }
+
+    // Principal branch.
+    fn ln(self) -> Self {
+        Complex::new(self.re.hypot(self.im).ln(), self.im.atan2(self.re))
+    }
+
+    // Principal branch, non-negative real part.
+    fn sqrt(self) -> Self {
+        let modulus = self.re.hypot(self.im);
+        let re = (0.5 * (modulus + self.re)).sqrt();
+        let im = (0.5 * (modulus - self.re)).sqrt().copysign(self.im);
+        Complex::new(re, im)
+    }
+
+    fn scale(self, factor: f64) -> Self {
+        Complex::new(self.re * factor, self.im * factor)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, other: Complex) -> Complex {
+        let denominator = other.re * other.re + other.im * other.im;
+        Complex::new(
+            (self.re * other.re + self.im * other.im) / denominator,
+            (self.im * other.re - self.re * other.im) / denominator,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+    fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+// Gauss-Legendre nodes and weights on [-1, 1], by Newton iteration on the
+// Legendre recurrence.
+fn gauss_legendre(n: usize) -> Vec<(f64, f64)> {
+    let mut rule = Vec::with_capacity(n);
+    for i in 0..n {
+        let mut x = (std::f64::consts::PI * (i as f64 + 0.75) / (n as f64 + 0.5)).cos();
+        let mut derivative = 1.0;
+        for _ in 0..100 {
+            let (mut p0, mut p1) = (1.0, x);
+            for k in 2..=n {
+                let p2 = ((2 * k - 1) as f64 * x * p1 - (k - 1) as f64 * p0) / k as f64;
+                p0 = p1;
+                p1 = p2;
+            }
+            derivative = n as f64 * (x * p1 - p0) / (x * x - 1.0);
+            let step = p1 / derivative;
+            x -= step;
+            if step.abs() < 1e-15 {
+                break;
+            }
+        }
+        rule.push((x, 2.0 / ((1.0 - x * x) * derivative * derivative)));
+    }
+    rule
+}
+
+// Variances are annualized; time in ACT/365 years as for the option pricer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HestonParams {
+    // Mean-reversion speed of the variance.
+    pub kappa: f64,
+    // Long-run variance.
+    pub theta: f64,
+    // Volatility of variance.
+    pub xi: f64,
+    // Correlation of spot and variance shocks.
+    pub rho: f64,
+    // Current variance.
+    pub v0: f64,
+}
+
+impl HestonParams {
+    pub fn as_model(&self) -> VolatilityModel {
+        VolatilityModel::Heston {
+            kappa: self.kappa,
+            theta: self.theta,
+            xi: self.xi,
+            rho: self.rho,
+            v0: self.v0,
+        }
+    }
+
+    // Risk-neutral expected average variance over the next `years`.
+    pub fn expected_average_variance(&self, years: f64) -> f64 {
+        self.as_model().expected_average_variance(self.v0, years)
+    }
+
+    // 2 kappa theta / xi^2; at least one keeps the variance off zero.
+    pub fn feller_ratio(&self) -> f64 {
+        2.0 * self.kappa * self.theta / (self.xi * self.xi)
+    }
+
+    // E[exp(iu ln(S_T / F))].
+    fn characteristic_function(&self, u: Complex, time_to_expiry: f64) -> Complex {
+        let i = Complex::new(0.0, 1.0);
+        let iu = i * u;
+        let kappa = Complex::new(self.kappa, 0.0);
+        let xi2 = self.xi * self.xi;
+        let beta = kappa - iu.scale(self.rho * self.xi);
+        let d = (beta * beta + (iu + u * u).scale(xi2)).sqrt();
+        let g = (beta - d) / (beta + d);
+        let decay = (-d.scale(time_to_expiry)).exp();
+        let one = Complex::new(1.0, 0.0);
+        let c = ((beta - d).scale(time_to_expiry)
+            - ((one - g * decay) / (one - g)).ln().scale(2.0))
+        .scale(self.kappa * self.theta / xi2);
+        let d_term = (beta - d).scale(1.0 / xi2) * (one - decay) / (one - g * decay);
+        (c + d_term.scale(self.v0)).exp()
+    }
+
+    // Call prices for one expiry from its forward and discount factor. The
+    // characteristic function is evaluated once and shared by every strike.
+    pub fn call_prices(
+        &self,
+        forward: f64,
+        discount: f64,
+        time_to_expiry: f64,
+        strikes: &[f64],
+    ) -> Vec<f64> {
+        if time_to_expiry <= 0.0 || forward <= 0.0 {
+            return strikes
+                .iter()
+                .map(|strike| discount * (forward - strike).max(0.0))
+                .collect();
+        }
+        let variance = self.expected_average_variance(time_to_expiry).max(1e-8);
+        let total_variance = variance * time_to_expiry;
+        // The Black-Scholes term decays like exp(-u^2 vT / 2), the Heston one
+        // only like exp(-u sqrt(1 - rho^2) (v0 + kappa theta T) / xi).
+        let black_scholes_cutoff = (2.0 * CUTOFF_EXPONENT / total_variance).sqrt();
+        let heston_decay = (1.0 - self.rho * self.rho).sqrt()
+            * (self.v0 + self.kappa * self.theta * time_to_expiry)
+            / self.xi;
+        let cutoff = black_scholes_cutoff.max((CUTOFF_EXPONENT / heston_decay).min(MAX_CUTOFF));
+        // Panels narrow enough for the scale of the characteristic function,
+        // 1 / sqrt(vT), and for the widest strike's oscillation, e^(iuk).
+        let max_log_moneyness = strikes
+            .iter()
+            .filter(|strike| **strike > 0.0)
+            .map(|strike| (forward / strike).ln().abs())
+            .fold(0.0, f64::max);
+        let cycles = max_log_moneyness * cutoff / (2.0 * std::f64::consts::PI);
+        let panels = MIN_PANELS
+            .max((cutoff * total_variance.sqrt() / 4.0).ceil() as usize)
+            .max((cycles / 2.0).ceil() as usize);
+        let width = cutoff / panels as f64;
+
+        // (u, weight / (u^2 + 1/4), phi_heston - phi_bs) at u - i/2.
+        let rule = gauss_legendre(NODES_PER_PANEL);
+        let mut nodes = Vec::with_capacity(panels * NODES_PER_PANEL);
+        for panel in 0..panels {
+            for &(x, w) in &rule {
+                let u = width * (panel as f64 + 0.5 * (x + 1.0));
+                let shifted = Complex::new(u, -0.5);
+                let black_scholes = (-(Complex::new(0.0, 1.0) * shifted + shifted * shifted)
+                    .scale(0.5 * total_variance))
+                .exp();
+                let difference =
+                    self.characteristic_function(shifted, time_to_expiry) - black_scholes;
+                nodes.push((u, 0.5 * width * w / (u * u + 0.25), difference));
+            }
+        }
+
+        let volatility = variance.sqrt();
+        strikes
+            .iter()
+            .map(|&strike| {
+                if strike <= 0.0 {
+                    return discount * forward;
+                }
+                let k = (forward / strike).ln();
+                let integral: f64 = nodes
+                    .iter()
+                    .map(|(u, weight, difference)| {
+                        let (sin, cos) = (u * k).sin_cos();
+                        weight * (cos * difference.re - sin * difference.im)
+                    })
+                    .sum();
+                let control = BlackScholes.price(&PricingInputs {
+                    option_type: OptionType::Call,
+                    spot: forward,
+                    strike,
+                    time_to_expiry,
+                    rate: 0.0,
+                    dividend_yield: 0.0,
+                    volatility,
+                });
+                let price = discount
+                    * (control - (forward * strike).sqrt() * integral / std::f64::consts::PI);
+                price.max(discount * (forward - strike).max(0.0))
+            })
+            .collect()
+    }
+
+    // European price for the contract in `inputs`; its volatility is ignored.
+    pub fn price(&self, inputs: &PricingInputs) -> f64 {
+        let forward = inputs.forward();
+        let discount = (-inputs.rate * inputs.time_to_expiry).exp();
+        let call = self.call_prices(forward, discount, inputs.time_to_expiry, &[inputs.strike])[0];
+        match inputs.option_type {
+            OptionType::Call => call,
+            OptionType::Put => call - discount * (forward - inputs.strike),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HestonCalibration {
+    // Expiries outside [min_days, max_days] calendar days are skipped.
+    pub min_days: i64,
+    pub max_days: i64,
+    // Strikes with |ln(K/F)| / (IV sqrt(T)) above this are skipped.
+    pub max_standardized_moneyness: f64,
+    // Evenly spaced subsample per expiry, to bound the cost of each step.
+    pub max_strikes_per_expiry: usize,
+    pub optimizer: NelderMead,
+}
+
+impl Default for HestonCalibration {
+    fn default() -> Self {
+        HestonCalibration {
+            min_days: 7,
+            max_days: 365,
+            max_standardized_moneyness: 2.5,
+            max_strikes_per_expiry: 15,
+            optimizer: NelderMead {
+                initial_step: 0.3,
+                tolerance: 1e-9,
+                max_iterations: 1500,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HestonFit {
+    pub date: NaiveDate,
+    pub params: HestonParams,
+    // Root mean square of (model - market) / vega.
+    pub iv_rmse: f64,
+    pub quotes: usize,
+    pub expiries: usize,
+    pub converged: bool,
+}
+
+impl HestonFit {
+    // Root of the expected average variance over the next `days` calendar
+    // days, ACT/365 like the calibrated expiries and the other IV forecasts.
+    pub fn expected_volatility(&self, days: usize) -> f64 {
+        self.params
+            .expected_average_variance(days as f64 / 365.0)
+            .sqrt()
+    }
+}
+
+struct CalibrationQuote {
+    strike: f64,
+    option_type: OptionType,
+    market_price: f64,
+    vega: f64,
+    iv: f64,
+}
+
+struct CalibrationExpiry {
+    time_to_expiry: f64,
+    forward: f64,
+    discount: f64,
+    strikes: Vec<f64>,
+    quotes: Vec<CalibrationQuote>,
+}
+
+impl HestonCalibration {
+    // Out-of-the-money quotes per expiry, priced at their IV with the parity
+    // forward so the market and model prices share inputs.
+    fn quotes(&self, chain: &OptionChain, forwards: &ForwardCurve) -> Vec<CalibrationExpiry> {
+        let mut expiries = Vec::new();
+        for slice in chain.expiries() {
+            let days = (slice.expiration - chain.date).num_days();
+            if days < self.min_days || days > self.max_days {
+                continue;
+            }
+            let t = slice.time_to_expiry;
+            let forward = forwards.forward(slice.expiration, t);
+            let mut quotes: Vec<CalibrationQuote> = slice
+                .strikes()
+                .filter_map(|pair| {
+                    let option = pair.out_of_the_money(forward, |o| o.implied_volatility > 0.0)?;
+                    let standardized =
+                        (pair.strike / forward).ln().abs() / (option.implied_volatility * t.sqrt());
+                    if standardized > self.max_standardized_moneyness {
+                        return None;
+                    }
+                    let inputs = forwards.inputs_for(option);
+                    let vega = BlackScholes.vega(&inputs);
+                    (vega > 0.0).then(|| CalibrationQuote {
+                        strike: pair.strike,
+                        option_type: option.contract_type,
+                        market_price: BlackScholes.price(&inputs),
+                        vega,
+                        iv: option.implied_volatility,
+                    })
+                })
+                .collect();
+            if quotes.len() > self.max_strikes_per_expiry && self.max_strikes_per_expiry > 1 {
+                let last = quotes.len() - 1;
+                let keep = self.max_strikes_per_expiry;
+                let picks: Vec<usize> = (0..keep).map(|j| j * last / (keep - 1)).collect();
+                quotes = quotes
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(index, _)| picks.contains(index))
+                    .map(|(_, quote)| quote)
+                    .collect();
+            }
+            if quotes.is_empty() {
+                continue;
+            }
+            expiries.push(CalibrationExpiry {
+                time_to_expiry: t,
+                forward,
+                discount: (-forwards.rate * t).exp(),
+                strikes: quotes.iter().map(|quote| quote.strike).collect(),
+                quotes,
+            });
+        }
+        expiries
+    }
+
+    // Vega-weighted residuals for every quote.
+    fn residuals(params: &HestonParams, expiries: &[CalibrationExpiry]) -> Vec<f64> {
+        let mut residuals = Vec::new();
+        for expiry in expiries {
+            let calls = params.call_prices(
+                expiry.forward,
+                expiry.discount,
+                expiry.time_to_expiry,
+                &expiry.strikes,
+            );
+            for (quote, call) in expiry.quotes.iter().zip(calls) {
+                let model = match quote.option_type {
+                    OptionType::Call => call,
+                    OptionType::Put => call - expiry.discount * (expiry.forward - quote.strike),
+                };
+                residuals.push((model - quote.market_price) / quote.vega);
+            }
+        }
+        residuals
+    }
+
+    // Fit to one day's chain. `start` seeds the search, typically the previous
+    // day's fit; otherwise v0 and theta start at the squared median quote IV.
+    // None with fewer than five usable quotes.
+    pub fn calibrate(
+        &self,
+        chain: &OptionChain,
+        forwards: &ForwardCurve,
+        start: Option<HestonParams>,
+    ) -> Option<HestonFit> {
+        let expiries = self.quotes(chain, forwards);
+        let quotes: usize = expiries.iter().map(|expiry| expiry.quotes.len()).sum();
+        if quotes < MIN_POINTS {
+            return None;
+        }
+        let start = start.unwrap_or_else(|| {
+            let mut ivs: Vec<f64> = expiries
+                .iter()
+                .flat_map(|expiry| expiry.quotes.iter().map(|quote| quote.iv))
+                .collect();
+            ivs.sort_by(f64::total_cmp);
+            let variance = ivs[ivs.len() / 2].powi(2);
+            HestonParams {
+                kappa: 2.0,
+                theta: variance,
+                xi: 0.5,
+                rho: -0.5,
+                v0: variance,
+            }
+        });
+
+        // Searched as (ln kappa, ln theta, ln xi, atanh rho, ln v0).
+        let unpack = |x: &[f64]| HestonParams {
+            kappa: x[0].exp(),
+            theta: x[1].exp(),
+            xi: x[2].exp(),
+            rho: x[3].tanh(),
+            v0: x[4].exp(),
+        };
+        let objective = |x: &[f64]| {
+            let params = unpack(x);
+            // Outside these the fit is chasing noise and the integral degrades.
+            if params.kappa > 50.0 || params.xi > 5.0 || params.rho.abs() > 0.999 {
+                return f64::INFINITY;
+            }
+            Self::residuals(&params, &expiries)
+                .iter()
+                .map(|r| r * r)
+                .sum::<f64>()
+        };
+        let x0 = [
+            start.kappa.max(1e-3).ln(),
+            start.theta.max(1e-6).ln(),
+            start.xi.max(1e-3).ln(),
+            start.rho.clamp(-0.99, 0.99).atanh(),
+            start.v0.max(1e-6).ln(),
+        ];
+        let minimum = self.optimizer.minimize(objective, &x0);
+        if !minimum.value.is_finite() {
+            return None;
+        }
+        let params = unpack(&minimum.point);
+        Some(HestonFit {
+            date: chain.date,
+            params,
+            iv_rmse: (minimum.value / quotes as f64).sqrt(),
+            quotes,
+            expiries: expiries.len(),
+            converged: minimum.converged,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(option_type: OptionType, strike: f64, time_to_expiry: f64) -> PricingInputs {
+        PricingInputs {
+            option_type,
+            spot: 100.0,
+            strike,
+            time_to_expiry,
+            rate: 0.03,
+            dividend_yield: 0.01,
+            volatility: 0.0,
+        }
+    }
+
+    // With no vol of vol the variance path is deterministic. Uncorrelated, so
+    // the price moves only at second order in xi.
+    #[test]
+    fn matches_black_scholes_as_vol_of_vol_vanishes() {
+        let params = HestonParams {
+            kappa: 2.0,
+            theta: 0.06,
+            xi: 1e-3,
+            rho: 0.0,
+            v0: 0.03,
+        };
+        for time_to_expiry in [0.1, 0.5, 2.0] {
+            let volatility = params.expected_average_variance(time_to_expiry).sqrt();
+            for strike in [70.0, 90.0, 100.0, 110.0, 140.0] {
+                for option_type in [OptionType::Call, OptionType::Put] {
+                    let contract = inputs(option_type, strike, time_to_expiry);
+                    let heston = params.price(&contract);
+                    let black_scholes = BlackScholes.price(&contract.with_volatility(volatility));
+                    assert!(
+                        (heston - black_scholes).abs() < 1e-5,
+                        "T {} K {} {:?}: {} vs {}",
+                        time_to_expiry,
+                        strike,
+                        option_type,
+                        heston,
+                        black_scholes
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn satisfies_put_call_parity() {
+        let params = HestonParams {
+            kappa: 1.5,
+            theta: 0.04,
+            xi: 0.8,
+            rho: -0.7,
+            v0: 0.05,
+        };
+        for strike in [80.0, 100.0, 125.0] {
+            let call = params.price(&inputs(OptionType::Call, strike, 0.75));
+            let put = params.price(&inputs(OptionType::Put, strike, 0.75));
+            let contract = inputs(OptionType::Call, strike, 0.75);
+            let parity = (-contract.rate * 0.75).exp() * (contract.forward() - strike);
+            assert!((call - put - parity).abs() < 1e-10);
+            assert!(call > 0.0 && put > 0.0);
+        }
+    }
+
+    // Albrecher et al. (2007) parameters, with the reference price
+    // 5.785155450 from Fang and Oosterlee (2008), table 4.
+    #[test]
+    fn matches_published_reference_price() {
+        let params = HestonParams {
+            kappa: 1.5768,
+            theta: 0.0398,
+            xi: 0.5751,
+            rho: -0.5711,
+            v0: 0.0175,
+        };
+        let price = params.call_prices(100.0, 1.0, 1.0, &[100.0])[0];
+        assert!((price - 5.785155450).abs() < 1e-4, "{}", price);
+    }
+
+    #[test]
+    fn expected_volatility_uses_calendar_days() {
+        let params = HestonParams {
+            kappa: 3.0,
+            theta: 0.04,
+            xi: 0.5,
+            rho: -0.7,
+            v0: 0.09,
+        };
+        let fit = HestonFit {
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            params,
+            iv_rmse: 0.0,
+            quotes: 0,
+            expiries: 0,
+            converged: true,
+        };
+        // 30 trading days from 2024-01-02 end on 2024-02-14, 43 days later.
+        let expected = params.expected_average_variance(43.0 / 365.0).sqrt();
+        assert!((fit.expected_volatility(43) - expected).abs() < 1e-15);
+        assert!(fit.expected_volatility(43) < fit.expected_volatility(30));
+    }
+}
@@ -0,0 +1,289 @@
+// Implied volatility inversion from option prices, so IV can be computed
+// consistently instead of trusting each vendor's number. Works with any
+// `OptionPricer`, European or American.
+
+use crate::api::OptionsData;
+use crate::black_scholes::{OptionPricer, PricingInputs};
+use crate::parity::ForwardCurve;
+use std::fmt;
+
+const MIN_VOL: f64 = 1e-4;
+const MAX_VOL: f64 = 5.0;
+const PRICE_TOLERANCE: f64 = 1e-8;
+const MAX_ITERATIONS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceSource {
+    // (bid + ask) / 2, only for a two-sided, non-crossed quote.
+    Mid,
+    Mark,
+    Last,
+    Bid,
+    Ask,
+}
+
+impl PriceSource {
+    pub fn price_of(&self, option: &OptionsData) -> Option<f64> {
+        let price = match self {
+            PriceSource::Mid => {
+                if option.bid <= 0.0 || option.ask <= 0.0 || option.ask < option.bid {
+                    return None;
+                }
+                (option.bid + option.ask) / 2.0
+            }
+            PriceSource::Mark => option.mark,
+            PriceSource::Last => option.last,
+            PriceSource::Bid => option.bid,
+            PriceSource::Ask => option.ask,
+        };
+        (price > 0.0).then_some(price)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImpliedVolError {
+    NonPositivePrice,
+    InvalidInputs,
+    // Price is below the no-arbitrage lower bound (the zero-vol price).
+    BelowLowerBound { price: f64, lower_bound: f64 },
+    // Price is above the model price at the maximum volatility (500%), which
+    // for European options is close to the S * exp(-qT) / K * exp(-rT) bound.
+    AboveUpperBound { price: f64, upper_bound: f64 },
+    // The pricer has no valid price at this volatility, e.g. a lattice whose
+    // steps are too coarse for the carry.
+    PricingFailed { volatility: f64 },
+    NoConvergence,
+}
+
+impl fmt::Display for ImpliedVolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImpliedVolError::NonPositivePrice => write!(f, "price is not positive"),
+            ImpliedVolError::InvalidInputs => write!(f, "invalid pricing inputs"),
+            ImpliedVolError::BelowLowerBound { price, lower_bound } => write!(
+                f,
+                "price {:.4} is below the arbitrage lower bound {:.4}",
+                price, lower_bound
+            ),
+            ImpliedVolError::AboveUpperBound { price, upper_bound } => write!(
+                f,
+                "price {:.4} is above the maximum-volatility price {:.4}",
+                price, upper_bound
+            ),
+            ImpliedVolError::PricingFailed { volatility } => {
+                write!(
+                    f,
+                    "pricer has no valid price at volatility {:.4}",
+                    volatility
+                )
+            }
+            ImpliedVolError::NoConvergence => write!(f, "solver did not converge"),
+        }
+    }
+}
+
+impl std::error::Error for ImpliedVolError {}
+
+// Solve pricer(inputs with vol) = price for vol. Newton steps on vega, falling
+// back to bisection whenever a step leaves the current bracket or vega
+// vanishes. The volatility in `inputs` is ignored.
+pub fn implied_volatility(
+    pricer: &dyn OptionPricer,
+    inputs: &PricingInputs,
+    price: f64,
+) -> Result<f64, ImpliedVolError> {
+    if price.is_nan() || price <= 0.0 {
+        return Err(ImpliedVolError::NonPositivePrice);
+    }
+    if inputs.spot <= 0.0 || inputs.strike <= 0.0 || inputs.time_to_expiry <= 0.0 {
+        return Err(ImpliedVolError::InvalidInputs);
+    }
+
+    let lower_bound = pricer.price(&inputs.with_volatility(0.0));
+    let upper_bound = pricer.price(&inputs.with_volatility(MAX_VOL));
+    if !upper_bound.is_finite() {
+        return Err(ImpliedVolError::PricingFailed {
+            volatility: MAX_VOL,
+        });
+    }
+    if price < lower_bound - PRICE_TOLERANCE {
+        return Err(ImpliedVolError::BelowLowerBound { price, lower_bound });
+    }
+    if price > upper_bound + PRICE_TOLERANCE {
+        return Err(ImpliedVolError::AboveUpperBound { price, upper_bound });
+    }
+
+    let mut low = MIN_VOL;
+    let mut high = MAX_VOL;
+    // Brenner-Subrahmanyam ATM approximation as the starting point.
+    let mut vol = (price / inputs.spot
+        * (2.0 * std::f64::consts::PI / inputs.time_to_expiry).sqrt())
+    .clamp(0.05, 2.0);
+
+    for _ in 0..MAX_ITERATIONS {
+        let candidate = inputs.with_volatility(vol);
+        let diff = pricer.price(&candidate) - price;
+        if !diff.is_finite() {
+            return Err(ImpliedVolError::PricingFailed { volatility: vol });
+        }
+        if diff.abs() < PRICE_TOLERANCE {
+            return Ok(vol);
+        }
+        if diff > 0.0 {
+            high = vol;
+        } else {
+            low = vol;
+        }
+
+        let vega = pricer.vega(&candidate);
+        let newton = vol - diff / vega;
+        vol = if vega > 1e-12 && newton > low && newton < high {
+            newton
+        } else {
+            0.5 * (low + high)
+        };
+
+        if high - low < 1e-12 {
+            return Ok(vol);
+        }
+    }
+
+    Err(ImpliedVolError::NoConvergence)
+}
+
+#[derive(Debug, Clone)]
+pub struct ContractImpliedVol {
+    pub contract: String,
+    pub iv: Result<f64, ImpliedVolError>,
+    pub bid_iv: Option<f64>,
+    pub ask_iv: Option<f64>,
+}
+
+impl ContractImpliedVol {
+    pub fn bid_ask_iv_spread(&self) -> Option<f64> {
+        Some(self.ask_iv? - self.bid_iv?)
+    }
+}
+
+fn solve_from_source(
+    pricer: &dyn OptionPricer,
+    option: &OptionsData,
+    inputs: &PricingInputs,
+    source: PriceSource,
+) -> Result<f64, ImpliedVolError> {
+    let price = source
+        .price_of(option)
+        .ok_or(ImpliedVolError::NonPositivePrice)?;
+    implied_volatility(pricer, inputs, price)
+}
+
+// Our own IV for every contract in a chain from `source`, plus bid and ask IVs,
+// priced off each expiry's forward.
+pub fn solve_chain_ivs(
+    pricer: &dyn OptionPricer,
+    chain: &[OptionsData],
+    forwards: &ForwardCurve,
+    source: PriceSource,
+) -> Vec<ContractImpliedVol> {
+    chain
+        .iter()
+        .map(|option| {
+            let inputs = forwards.inputs_for(option);
+            ContractImpliedVol {
+                contract: option.contract.clone(),
+                iv: solve_from_source(pricer, option, &inputs, source),
+                bid_iv: solve_from_source(pricer, option, &inputs, PriceSource::Bid).ok(),
+                ask_iv: solve_from_source(pricer, option, &inputs, PriceSource::Ask).ok(),
+            }
+        })
+        .collect()
+}
+
+// Replace the vendor IV of each contract with our own, dropping contracts
+// whose price cannot be inverted.
+pub fn with_solved_implied_volatility(
+    pricer: &dyn OptionPricer,
+    chain: Vec<OptionsData>,
+    forwards: &ForwardCurve,
+    source: PriceSource,
+) -> Vec<OptionsData> {
+    chain
+        .into_iter()
+        .filter_map(|mut option| {
+            let inputs = forwards.inputs_for(&option);
+            let iv = solve_from_source(pricer, &option, &inputs, source).ok()?;
+            option.implied_volatility = iv;
+            Some(option)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::OptionType;
+    use crate::black_scholes::BlackScholes;
+
+    fn inputs(option_type: OptionType, strike: f64, time_to_expiry: f64) -> PricingInputs {
+        PricingInputs {
+            option_type,
+            spot: 100.0,
+            strike,
+            time_to_expiry,
+            rate: 0.04,
+            dividend_yield: 0.015,
+            volatility: 0.0,
+        }
+    }
+
+    #[test]
+    fn round_trips_black_scholes_prices() {
+        for option_type in [OptionType::Call, OptionType::Put] {
+            for time_to_expiry in [7.0 / 365.0, 0.25, 2.0] {
+                for strike in [60.0, 90.0, 100.0, 115.0, 160.0] {
+                    for volatility in [0.05, 0.2, 0.6, 1.5] {
+                        let contract = inputs(option_type, strike, time_to_expiry);
+                        let price = BlackScholes.price(&contract.with_volatility(volatility));
+                        // Far out-of-the-money prices below ~1e-6 carry no vol
+                        // information at the solver's price tolerance.
+                        if price < 1e-6 {
+                            continue;
+                        }
+                        let solved = implied_volatility(&BlackScholes, &contract, price).unwrap();
+                        let repriced = BlackScholes.price(&contract.with_volatility(solved));
+                        assert!(
+                            (repriced - price).abs() < 1e-7,
+                            "{:?} K {} T {} vol {}: solved {}",
+                            option_type,
+                            strike,
+                            time_to_expiry,
+                            volatility,
+                            solved
+                        );
+                        let vega = BlackScholes.vega(&contract.with_volatility(volatility));
+                        if vega > 1e-2 {
+                            assert!((solved - volatility).abs() < 1e-6);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_prices_outside_the_arbitrage_bounds() {
+        let call = inputs(OptionType::Call, 80.0, 0.5);
+        assert!(matches!(
+            implied_volatility(&BlackScholes, &call, 5.0),
+            Err(ImpliedVolError::BelowLowerBound { .. })
+        ));
+        assert!(matches!(
+            implied_volatility(&BlackScholes, &call, 150.0),
+            Err(ImpliedVolError::AboveUpperBound { .. })
+        ));
+        assert_eq!(
+            implied_volatility(&BlackScholes, &call, 0.0),
+            Err(ImpliedVolError::NonPositivePrice)
+        );
+    }
+}
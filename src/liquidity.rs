@@ -0,0 +1,274 @@
+// Liquidity and quote-quality filters for a day's chain. A positive last
+// price only says the contract traded at some point, possibly weeks ago, so
+// contracts are also checked on open interest, volume, the quote itself and
+// how their IV compares with neighbouring strikes. Each dropped contract is
+// counted under the first check it fails.
+
+use crate::api::{OptionType, OptionsData};
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FilterReason {
+    LowOpenInterest,
+    LowVolume,
+    // Missing bid or ask, or bid above ask.
+    CrossedOrOneSided,
+    WideSpread,
+    MarkOutsideQuote,
+    IvOutlier,
+}
+
+impl fmt::Display for FilterReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            FilterReason::LowOpenInterest => "open interest",
+            FilterReason::LowVolume => "volume",
+            FilterReason::CrossedOrOneSided => "crossed/one-sided",
+            FilterReason::WideSpread => "spread",
+            FilterReason::MarkOutsideQuote => "mark outside quote",
+            FilterReason::IvOutlier => "IV outlier",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LiquidityFilter {
+    pub min_open_interest: f64,
+    pub min_volume: f64,
+    // Maximum (ask - bid) / mid. None disables the check.
+    pub max_relative_spread: Option<f64>,
+    // Require bid > 0 and ask >= bid.
+    pub require_two_sided: bool,
+    // Require bid <= mark <= ask when a mark is given.
+    pub require_mark_inside: bool,
+    // Maximum absolute IV difference from the value interpolated between the
+    // neighbouring strikes of the same type and expiry. None disables it.
+    pub max_iv_deviation: Option<f64>,
+}
+
+impl Default for LiquidityFilter {
+    fn default() -> Self {
+        LiquidityFilter {
+            min_open_interest: 10.0,
+            min_volume: 0.0,
+            max_relative_spread: Some(0.5),
+            require_two_sided: true,
+            require_mark_inside: true,
+            max_iv_deviation: Some(0.10),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FilterReport {
+    pub date: NaiveDate,
+    pub input: usize,
+    pub kept: usize,
+    pub removed: BTreeMap<FilterReason, usize>,
+}
+
+impl fmt::Display for FilterReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "kept {}/{}", self.kept, self.input)?;
+        if !self.removed.is_empty() {
+            let removed: Vec<String> = self
+                .removed
+                .iter()
+                .map(|(reason, count)| format!("{} {}", reason, count))
+                .collect();
+            write!(f, " (removed: {})", removed.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl LiquidityFilter {
+    fn quote_check(&self, option: &OptionsData) -> Option<FilterReason> {
+        if option.open_interest < self.min_open_interest {
+            return Some(FilterReason::LowOpenInterest);
+        }
+        if option.volume < self.min_volume {
+            return Some(FilterReason::LowVolume);
+        }
+        let two_sided = option.bid > 0.0 && option.ask >= option.bid;
+        if self.require_two_sided && !two_sided {
+            return Some(FilterReason::CrossedOrOneSided);
+        }
+        if let Some(max_spread) = self.max_relative_spread {
+            let mid = 0.5 * (option.bid + option.ask);
+            if !two_sided || (option.ask - option.bid) / mid > max_spread {
+                return Some(FilterReason::WideSpread);
+            }
+        }
+        if self.require_mark_inside
+            && option.mark > 0.0
+            && (option.mark < option.bid || option.mark > option.ask)
+        {
+            return Some(FilterReason::MarkOutsideQuote);
+        }
+        None
+    }
+
+    // Indices of contracts whose IV is further than `max_deviation` from the
+    // line between the IVs of their neighbouring strikes (same type and
+    // expiry). The worst offender is dropped first and its neighbours
+    // re-checked, so one bad quote does not take good neighbours with it.
+    // End strikes are compared with their single neighbour.
+    fn iv_outliers(chain: &[OptionsData], max_deviation: f64) -> HashSet<usize> {
+        let mut groups: BTreeMap<(NaiveDate, bool), Vec<usize>> = BTreeMap::new();
+        for (i, option) in chain.iter().enumerate() {
+            if option.implied_volatility > 0.0 {
+                let is_call = option.contract_type == OptionType::Call;
+                groups
+                    .entry((option.expiration, is_call))
+                    .or_default()
+                    .push(i);
+            }
+        }
+
+        let deviation = |indices: &[usize], position: usize| -> f64 {
+            let option = &chain[indices[position]];
+            let expected = match position {
+                0 => chain[indices[1]].implied_volatility,
+                p if p == indices.len() - 1 => chain[indices[p - 1]].implied_volatility,
+                p => {
+                    let low = &chain[indices[p - 1]];
+                    let high = &chain[indices[p + 1]];
+                    // Duplicate strikes leave nothing to interpolate across.
+                    let weight = if high.strike > low.strike {
+                        (option.strike - low.strike) / (high.strike - low.strike)
+                    } else {
+                        0.5
+                    };
+                    (1.0 - weight) * low.implied_volatility + weight * high.implied_volatility
+                }
+            };
+            (option.implied_volatility - expected).abs()
+        };
+
+        let mut outliers = HashSet::new();
+        for indices in groups.values_mut() {
+            indices.sort_by(|&a, &b| chain[a].strike.total_cmp(&chain[b].strike));
+            while indices.len() >= 3 {
+                let Some((worst, largest)) = (0..indices.len())
+                    .map(|position| (position, deviation(indices, position)))
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                else {
+                    break;
+                };
+                if largest <= max_deviation {
+                    break;
+                }
+                outliers.insert(indices.remove(worst));
+            }
+        }
+        outliers
+    }
+
+    // Filtered chain and a report of what was removed. The IV check runs on
+    // the contracts that passed the quote checks, so bad quotes cannot mark
+    // good neighbours as outliers.
+    pub fn apply(
+        &self,
+        chain: Vec<OptionsData>,
+        date: NaiveDate,
+    ) -> (Vec<OptionsData>, FilterReport) {
+        let input = chain.len();
+        let mut removed: BTreeMap<FilterReason, usize> = BTreeMap::new();
+
+        let mut kept: Vec<OptionsData> = chain
+            .into_iter()
+            .filter(|option| match self.quote_check(option) {
+                Some(reason) => {
+                    *removed.entry(reason).or_default() += 1;
+                    false
+                }
+                None => true,
+            })
+            .collect();
+
+        if let Some(max_deviation) = self.max_iv_deviation {
+            let outliers = Self::iv_outliers(&kept, max_deviation);
+            if !outliers.is_empty() {
+                *removed.entry(FilterReason::IvOutlier).or_default() += outliers.len();
+                let mut index = 0;
+                kept.retain(|_| {
+                    let keep = !outliers.contains(&index);
+                    index += 1;
+                    keep
+                });
+            }
+        }
+
+        let report = FilterReport {
+            date,
+            input,
+            kept: kept.len(),
+            removed,
+        };
+        (kept, report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(strike: f64, iv: f64) -> OptionsData {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        OptionsData {
+            symbol: "SPY".to_string(),
+            contract: format!("SPYC{}", strike),
+            contract_type: OptionType::Call,
+            expiration: NaiveDate::from_ymd_opt(2024, 3, 15).unwrap(),
+            date,
+            strike,
+            last: 1.0,
+            mark: 1.0,
+            bid: 0.9,
+            ask: 1.1,
+            volume: 10.0,
+            open_interest: 100.0,
+            implied_volatility: iv,
+            delta: 0.5,
+            gamma: 0.01,
+            theta: -0.01,
+            vega: 0.1,
+            rho: 0.01,
+        }
+    }
+
+    fn apply(chain: Vec<OptionsData>) -> (Vec<OptionsData>, FilterReport) {
+        let date = chain[0].date;
+        LiquidityFilter::default().apply(chain, date)
+    }
+
+    #[test]
+    fn drops_only_the_iv_outlier() {
+        let chain: Vec<OptionsData> = [0.22, 0.21, 0.45, 0.19, 0.18]
+            .iter()
+            .enumerate()
+            .map(|(i, &iv)| call(90.0 + 5.0 * i as f64, iv))
+            .collect();
+        let (kept, report) = apply(chain);
+        assert_eq!(report.kept, 4);
+        assert_eq!(report.removed.get(&FilterReason::IvOutlier), Some(&1));
+        assert!(kept.iter().all(|option| option.strike != 100.0));
+    }
+
+    #[test]
+    fn duplicate_strikes_do_not_produce_nan_deviations() {
+        let chain = vec![
+            call(95.0, 0.21),
+            call(100.0, 0.20),
+            call(100.0, 0.20),
+            call(100.0, 0.20),
+            call(105.0, 0.19),
+        ];
+        let (kept, report) = apply(chain);
+        assert_eq!(kept.len(), 5, "{}", report);
+    }
+}
@@ -0,0 +1,230 @@
+// Forecast loss functions. Every loss works on aligned (forecast, realized)
+// volatility pairs rather than on pre-computed errors, so that asymmetric
+// losses such as QLIKE can be evaluated.
+
+use crate::time_series::TimeSeries;
+use chrono::NaiveDate;
+
+#[derive(Debug, Clone)]
+pub struct ForecastPair {
+    pub date: NaiveDate,
+    // Annualized forecast volatility (e.g. IV or HV) made on `date`.
+    pub forecast: f64,
+    // Annualized realized volatility over the forecast window starting on `date`.
+    pub realized: f64,
+}
+
+pub trait LossFunction {
+    fn name(&self) -> &'static str;
+
+    // Loss for a single observation. Returns None when the loss is undefined
+    // for these inputs (e.g. QLIKE with a zero forecast).
+    fn loss(&self, forecast: f64, realized: f64) -> Option<f64>;
+
+    // Combine per-observation losses into one number. Defaults to the mean.
+    fn aggregate(&self, losses: &[f64]) -> Option<f64> {
+        if losses.is_empty() {
+            return None;
+        }
+        Some(losses.iter().sum::<f64>() / losses.len() as f64)
+    }
+}
+
+// Mean absolute error in vol space.
+pub struct Mae;
+
+impl LossFunction for Mae {
+    fn name(&self) -> &'static str {
+        "MAE"
+    }
+
+    fn loss(&self, forecast: f64, realized: f64) -> Option<f64> {
+        Some((forecast - realized).abs())
+    }
+}
+
+// Root mean squared error in vol space. The per-observation loss is the squared
+// error, the square root is taken on aggregation.
+pub struct Rmse;
+
+impl LossFunction for Rmse {
+    fn name(&self) -> &'static str {
+        "RMSE"
+    }
+
+    fn loss(&self, forecast: f64, realized: f64) -> Option<f64> {
+        Some((forecast - realized).powi(2))
+    }
+
+    fn aggregate(&self, losses: &[f64]) -> Option<f64> {
+        if losses.is_empty() {
+            return None;
+        }
+        Some((losses.iter().sum::<f64>() / losses.len() as f64).sqrt())
+    }
+}
+
+// Mean squared error on variance. Robust to noise in the realized proxy
+// (Patton, 2011), unlike MAE/RMSE on vol.
+pub struct MseVariance;
+
+impl LossFunction for MseVariance {
+    fn name(&self) -> &'static str {
+        "MSE (variance)"
+    }
+
+    fn loss(&self, forecast: f64, realized: f64) -> Option<f64> {
+        Some((forecast.powi(2) - realized.powi(2)).powi(2))
+    }
+}
+
+// QLIKE in the normalised form r/f - ln(r/f) - 1 on variances, which is zero
+// for a perfect forecast. Robust to a noisy proxy and penalises
+// under-prediction more than over-prediction.
+pub struct Qlike;
+
+impl LossFunction for Qlike {
+    fn name(&self) -> &'static str {
+        "QLIKE"
+    }
+
+    fn loss(&self, forecast: f64, realized: f64) -> Option<f64> {
+        let forecast_var = forecast.powi(2);
+        let realized_var = realized.powi(2);
+        if forecast_var <= 0.0 || realized_var <= 0.0 {
+            return None;
+        }
+        let ratio = realized_var / forecast_var;
+        Some(ratio - ratio.ln() - 1.0)
+    }
+}
+
+// Mean absolute percentage error relative to the realized vol.
+pub struct Mape;
+
+impl LossFunction for Mape {
+    fn name(&self) -> &'static str {
+        "MAPE"
+    }
+
+    fn loss(&self, forecast: f64, realized: f64) -> Option<f64> {
+        if realized <= 0.0 {
+            return None;
+        }
+        Some(((forecast - realized) / realized).abs())
+    }
+}
+
+pub fn standard_losses() -> Vec<Box<dyn LossFunction>> {
+    vec![
+        Box::new(Mae),
+        Box::new(Rmse),
+        Box::new(MseVariance),
+        Box::new(Qlike),
+        Box::new(Mape),
+    ]
+}
+
+// Per-observation losses, dropping pairs where the loss is undefined.
+pub fn loss_series(pairs: &[ForecastPair], loss: &dyn LossFunction) -> TimeSeries<f64> {
+    pairs
+        .iter()
+        .filter_map(|pair| {
+            loss.loss(pair.forecast, pair.realized)
+                .filter(|value| value.is_finite())
+                .map(|value| (pair.date, value))
+        })
+        .collect()
+}
+
+pub fn evaluate_loss(pairs: &[ForecastPair], loss: &dyn LossFunction) -> Option<f64> {
+    let losses: Vec<f64> = loss_series(pairs, loss).values().copied().collect();
+    loss.aggregate(&losses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn pairs(values: &[(f64, f64)]) -> Vec<ForecastPair> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &(forecast, realized))| ForecastPair {
+                date: start + Duration::days(i as i64),
+                forecast,
+                realized,
+            })
+            .collect()
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("loss should be defined");
+        assert!(
+            (actual - expected).abs() < 1e-12,
+            "{} vs {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn matches_hand_computed_values() {
+        let data = pairs(&[(0.2, 0.1), (0.3, 0.4)]);
+        assert_close(evaluate_loss(&data, &Mae), 0.1);
+        // Errors are 0.1 and -0.1.
+        assert_close(evaluate_loss(&data, &Rmse), 0.1);
+        // Variance errors are 0.04 - 0.01 and 0.09 - 0.16.
+        assert_close(
+            evaluate_loss(&data, &MseVariance),
+            (0.03f64.powi(2) + 0.07f64.powi(2)) / 2.0,
+        );
+        // Relative errors are 1 and 0.25.
+        assert_close(evaluate_loss(&data, &Mape), 0.625);
+        // Variance ratios 0.25 and 16/9.
+        let qlike = |ratio: f64| ratio - ratio.ln() - 1.0;
+        assert_close(
+            evaluate_loss(&data, &Qlike),
+            (qlike(0.25) + qlike(16.0 / 9.0)) / 2.0,
+        );
+    }
+
+    #[test]
+    fn rmse_takes_the_root_after_averaging() {
+        let data = pairs(&[(0.3, 0.2), (0.2, 0.2), (0.2, 0.2), (0.2, 0.5)]);
+        // Squared errors 0.01, 0, 0, 0.09.
+        assert_close(evaluate_loss(&data, &Rmse), 0.025f64.sqrt());
+    }
+
+    #[test]
+    fn perfect_forecasts_have_zero_loss() {
+        let data = pairs(&[(0.15, 0.15), (0.2, 0.2), (0.35, 0.35)]);
+        for loss in standard_losses() {
+            assert_close(evaluate_loss(&data, loss.as_ref()), 0.0);
+        }
+    }
+
+    #[test]
+    fn qlike_penalises_under_prediction_more() {
+        let under = Qlike.loss(0.1, 0.2).unwrap();
+        let over = Qlike.loss(0.2, 0.1).unwrap();
+        assert!(under > over);
+    }
+
+    #[test]
+    fn undefined_losses_are_dropped() {
+        assert_eq!(Qlike.loss(0.0, 0.2), None);
+        assert_eq!(Qlike.loss(0.2, 0.0), None);
+        assert_eq!(Mape.loss(0.2, 0.0), None);
+        assert_eq!(Mape.loss(0.2, -0.1), None);
+        // The zero-forecast and zero-realized pairs are skipped, not averaged.
+        let data = pairs(&[(0.0, 0.2), (0.2, 0.1), (0.1, 0.0)]);
+        assert_eq!(loss_series(&data, &Qlike).len(), 1);
+        assert_close(evaluate_loss(&data, &Qlike), 0.25 - 0.25f64.ln() - 1.0);
+        assert_close(evaluate_loss(&data, &Mape), 1.0);
+        assert_eq!(evaluate_loss(&pairs(&[(0.0, 0.0)]), &Qlike), None);
+        assert_eq!(evaluate_loss(&[], &Mae), None);
+    }
+}
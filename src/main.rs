@@ -1,16 +1,79 @@
-use std::collections::HashMap;
 use std::collections::HashSet;
 use std::error::Error;
-use tokio;
 mod api;
+mod audit;
+mod black_scholes;
+mod bootstrap;
+mod calendar;
+mod calibration;
+mod chain;
+mod comparison;
+mod constant_maturity;
 mod data;
+mod estimators;
 mod graph;
-use crate::api::{historical_data, options_data, Ohlcv, OptionsData};
+mod hac;
+mod heston;
+mod implied_vol;
+mod liquidity;
+mod loss;
+mod model_free;
+mod monte_carlo;
+mod optimize;
+mod parity;
+mod regression;
+mod rng;
+mod selection;
+mod simulation;
+mod skew;
+mod stats;
+mod svi;
+mod term_structure;
+mod time_series;
+mod trees;
+use crate::api::{historical_data, options_data, OptionType, OptionsData};
+use crate::audit::{AuditPolicy, AuditReport, DataAudit};
+use crate::black_scholes::{value_chain, BlackScholes, ChainValuation, OptionPricer};
+use crate::bootstrap::BlockBootstrap;
+use crate::calendar::is_early_close;
+use crate::calibration::{
+    calibration_report, hv_density_observations, iv_density_observations, CalibrationReport,
+};
+use crate::chain::OptionChain;
+use crate::comparison::{diebold_mariano, overlap_lags};
 use crate::data::{
-    calculate_accuracy_correlation, calculate_mae, find_closest_date, find_closest_num,
-    hv_accuracy, iv_accuracy,
+    accuracy_correlation_test, calculate_accuracy_correlation, calculate_mae,
+    calculate_mae_confidence_interval, forecast_pairs, forecast_window_end, hv_accuracy_with_mode,
+    hv_forecast_pairs, iv_accuracy_with_mode, iv_forecast_pairs, paired_accuracy_values,
+    paired_correlation, rolling_accuracy_correlation, CorrelationMethod, ScoringMode,
+};
+use crate::estimators::standard_forecasters;
+use crate::graph::{draw_accuracy_graph, draw_estimator_study};
+use crate::hac::LagSelection;
+use crate::heston::{HestonCalibration, HestonFit};
+use crate::implied_vol::{
+    solve_chain_ivs, with_solved_implied_volatility, ImpliedVolError, PriceSource,
+};
+use crate::liquidity::{FilterReport, LiquidityFilter};
+use crate::loss::{evaluate_loss, standard_losses};
+use crate::model_free::model_free_vol;
+use crate::monte_carlo::MonteCarloStudy;
+use crate::parity::ForwardCurve;
+use crate::regression::{
+    encompassing_regression, mincer_zarnowitz, CovarianceEstimator, EncompassingTest,
+    MincerZarnowitz,
 };
-use crate::graph::draw_accuracy_graph;
+use crate::selection::{
+    AtmCallPutAverage, ConstantMaturityAtm, NearestAtm, OptionSelector, SelectionContext,
+    SelectionError, TargetDelta,
+};
+use crate::simulation::{MarketSimulator, VolatilityModel};
+use crate::skew::{skew_at_tenor, skew_downside_regression, SkewMetrics};
+use crate::stats::mean;
+use crate::svi::{fit_chain_smiles, SmileWeighting};
+use crate::term_structure::{build_term_structure, term_structure_error_study, TermStructure};
+use crate::time_series::{Frequency, TimeSeries};
+use crate::trees::{BinomialTree, TrinomialTree};
 use chrono::{Duration, NaiveDate};
 
 // Let's just say i was vibing while 'coding' most of this
@@ -23,12 +86,141 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let hv_window_days = 30;
     let iv_option_target_window_days = 30;
 
-    let max_options_requests = 24; // Limit for options_data calls, necessary for free api
-    let fetch_interval_days = 14; // Fetch options data every 2 weeks, cus cant get every day
+    // Limit for options_data calls, necessary for free api
+    let max_options_requests = 24;
+    // Fetch options data every 2 weeks, cus cant get every day
+    let fetch_interval_days = 14;
+    // None uses the vendor IV. Some(source) re-solves every contract's IV from that
+    // price with Black-Scholes, so IV is computed the same way whatever the vendor.
+    let iv_price_source: Option<PriceSource> = None;
+    // SPY options are American. Some(steps) solves IV with an American binomial
+    // tree of that many steps instead of Black-Scholes (slower, ~200 is plenty).
+    let iv_tree_steps: Option<usize> = None;
+    // Lattice steps for the American IV check on the selected contract
+    let american_check_steps = 200;
+    // Which contract's IV is the forecast on each date. NearestAtm is the
+    // original selection: the expiry nearest the end of the scoring window
+    // (iv_option_target_window_days trading days out), strike nearest the
+    // forward. TargetDelta { option_type,
+    // delta, tolerance } and AtmCallPutAverage use the same expiry;
+    // ConstantMaturityAtm instead interpolates ATM IV to exactly the end of
+    // the scoring window.
+    let option_selector: Box<dyn OptionSelector> = Box::new(NearestAtm {
+        option_type: OptionType::Call,
+    });
+    // Other selectors whose pick is printed next to the forecast's on each
+    // date, to see how much the choice moves the IV. Empty to skip.
+    let comparison_selectors: Vec<Box<dyn OptionSelector>> = vec![
+        Box::new(TargetDelta {
+            option_type: OptionType::Put,
+            delta: -0.25,
+            tolerance: 0.05,
+        }),
+        Box::new(AtmCallPutAverage),
+        Box::new(ConstantMaturityAtm),
+    ];
+    // Quote-quality filters applied to each day's chain after the IVs are set.
+    // None keeps every contract with a positive IV, as the original selection
+    // did. Some(LiquidityFilter::default()) drops contracts with open interest
+    // under 10, one-sided or crossed quotes, spreads over 50% of the mid, marks
+    // outside the quote and IVs more than 10 points off their neighbours.
+    let liquidity_filter: Option<LiquidityFilter> = None;
+    // Data-quality checks on the prices and each raw chain. Report prints the
+    // findings at the end and carries on. FailOnErrors stops the run on
+    // problems that break the calculations (duplicate or unordered dates,
+    // non-positive closes); FailOnWarnings also on gaps, stale prices,
+    // outliers and bad quotes.
+    let data_audit = DataAudit::default();
+    let audit_policy = AuditPolicy::Report;
+    // Weighting of strikes in the per-expiry SVI smile fits: Vega, Uniform or
+    // Spread (tight bid-ask quotes dominate).
+    let smile_weighting = SmileWeighting::Vega;
+    // Some(HestonCalibration::default()) fits Heston to each day's chain,
+    // seeded from the previous day's, and scores its expected average vol to
+    // the end of the scoring window alongside IV and HV. Off by default, as
+    // it adds a Nelder-Mead calibration per fetched date.
+    let heston_calibration: Option<HestonCalibration> = None;
+    // Continuously compounded, used when solving IV
+    let risk_free_rate = 0.045;
+    // SPY trailing yield, used when solving IV
+    let dividend_yield = 0.013;
+    // PriceMove is the original |S_{t+w} - S_t| scoring. RealizedVolatility and
+    // RealizedVariance compare against realized vol over the whole forecast window.
+    let scoring_mode = ScoringMode::PriceMove;
+    // Newey-West lags for every HAC standard error. None picks the rule of
+    // thumb, but never fewer lags than the forecast overlap.
+    let hac_lag_count: Option<usize> = None;
+    // Block bootstrap for confidence intervals. None picks n^(1/3), but never
+    // shorter than the forecast overlap plus one.
+    let bootstrap_block_length: Option<usize> = None;
+    let bootstrap_resamples = 2000;
+    // Number of common IV/HV dates per rolling window
+    let rolling_correlation_window = 8;
+    // Keep small, there are only ~20 IV dates
+    let pit_histogram_bins = 5;
+    // Some(simulator) runs everything on a simulated market with a known true
+    // volatility instead of the API, e.g. Some(MarketSimulator::default()).
+    let market_simulator: Option<MarketSimulator> = None;
+    // Some(study) first compares close-to-close HV windows, range-based
+    // estimators, EWMA and GARCH on simulated paths with a known true vol, to
+    // choose hv_window_days. Set its horizon to the forecast window studied.
+    let estimator_study: Option<MonteCarloStudy> = None;
+
+    let iv_pricer: Box<dyn OptionPricer> = match iv_tree_steps {
+        Some(steps) => Box::new(BinomialTree::american(steps)),
+        None => Box::new(BlackScholes),
+    };
+
+    if let Some(study) = &estimator_study {
+        // The ranking depends on the dynamics, so the study also runs under
+        // constant vol and GARCH with the same long-run vol as a check.
+        let long_run_vol = study
+            .simulator
+            .model
+            .expected_average_variance(0.0, 100.0)
+            .sqrt();
+        let models = [
+            ("estimator_study.png", study.simulator.model),
+            (
+                "estimator_study_gbm.png",
+                VolatilityModel::Gbm {
+                    volatility: long_run_vol,
+                },
+            ),
+            (
+                "estimator_study_garch.png",
+                VolatilityModel::garch(long_run_vol, 0.08, 0.9),
+            ),
+        ];
+        for (output_file, model) in models {
+            let study = MonteCarloStudy {
+                simulator: MarketSimulator {
+                    model,
+                    ..study.simulator.clone()
+                },
+                ..study.clone()
+            };
+            let report = study.run(&standard_forecasters(hv_window_days, study.burn_in))?;
+            println!("\nEstimator study: {}", report);
+            if let Some(best) = report.best() {
+                println!("Lowest RMSE: {} ({:.4})", best.name, best.rmse);
+            }
+            match draw_estimator_study(&report, output_file) {
+                Ok(_) => println!("Graph generated successfully at {}", output_file),
+                Err(e) => eprintln!("Failed to generate graph: {}", e),
+            }
+        }
+    }
 
-    let mut ohlcv_data = historical_data(key.clone(), symbol.clone())
-        .await
-        .expect("Failed to fetch historical OHLCV data.");
+    let simulated_market = market_simulator
+        .map(|simulator| simulator.simulate(&symbol))
+        .transpose()?;
+    let mut ohlcv_data = match &simulated_market {
+        Some(market) => market.ohlcv.clone(),
+        None => historical_data(key.clone(), symbol.clone())
+            .await
+            .expect("Failed to fetch historical OHLCV data."),
+    };
 
     if ohlcv_data.is_empty() {
         eprintln!(
@@ -38,46 +230,36 @@ async fn main() -> Result<(), Box<dyn Error>> {
         return Ok(());
     }
 
-    let mut latest_date: Option<NaiveDate> = None;
-    for ohlcv_entry in ohlcv_data.iter() {
-        if let Ok(date) = NaiveDate::parse_from_str(&ohlcv_entry.date, "%Y-%m-%d") {
-            if latest_date.is_none() || date > latest_date.unwrap() {
-                latest_date = Some(date);
-            }
-        }
-    }
-
-    let Some(latest_date_actual) = latest_date else {
+    let Some(latest_date_actual) = ohlcv_data.iter().map(|ohlcv| ohlcv.date).max() else {
         eprintln!("Could not determine the latest date from OHLCV data.");
         return Ok(());
     };
 
     let twelve_months_ago = latest_date_actual - Duration::days(365);
 
-    ohlcv_data.retain(|ohlcv| {
-        if let Ok(date) = NaiveDate::parse_from_str(&ohlcv.date, "%Y-%m-%d") {
-            date >= twelve_months_ago && date <= latest_date_actual // Ensure it's within the 12-month window
-        } else {
-            false // If date parsing fails, exclude the entry
-        }
-    });
+    ohlcv_data.retain(|ohlcv| ohlcv.date >= twelve_months_ago && ohlcv.date <= latest_date_actual);
 
-    // Optional: Sort the data by date if it's not already,
-    // which can be helpful for sequential processing later.
-    ohlcv_data.sort_by(|a, b| {
-        NaiveDate::parse_from_str(&a.date, "%Y-%m-%d")
-            .unwrap_or_default()
-            .cmp(&NaiveDate::parse_from_str(&b.date, "%Y-%m-%d").unwrap_or_default())
-    });
+    let mut audit_report = AuditReport {
+        prices: data_audit.audit_prices(&ohlcv_data),
+        ..AuditReport::default()
+    };
+    if let Err(failure) = audit_report.enforce(audit_policy) {
+        eprintln!("{}", audit_report);
+        return Err(failure.into());
+    }
 
     println!(
-        "Filtered OHLCV data to the most recent 12 months, {} entries remaining (from {} to {}).",
+        "Filtered OHLCV data to the most recent 12 months, {} entries remaining (from {} to {}, {} early closes).",
         ohlcv_data.len(),
         twelve_months_ago,
-        latest_date_actual
+        latest_date_actual,
+        ohlcv_data
+            .iter()
+            .filter(|ohlcv| is_early_close(ohlcv.date))
+            .count()
     );
 
-    let hv_accuracy_full_results = hv_accuracy(&ohlcv_data, hv_window_days);
+    let hv_accuracy_full_results = hv_accuracy_with_mode(&ohlcv_data, hv_window_days, scoring_mode);
     println!(
         "\nHV Accuracy (first 100 entries): {:?}",
         hv_accuracy_full_results
@@ -86,16 +268,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .collect::<Vec<_>>()
     );
 
-    let ohlcv_map: HashMap<NaiveDate, &Ohlcv> = ohlcv_data
-        .iter()
-        .filter_map(|ohlcv| {
-            NaiveDate::parse_from_str(&ohlcv.date, "%Y-%m-%d")
-                .ok()
-                .map(|d| (d, ohlcv))
-        })
-        .collect();
-
     let mut all_relevant_options: Vec<OptionsData> = Vec::new();
+    // (date, model-free vol) from the whole chain, VIX methodology.
+    let mut model_free_vols: TimeSeries<f64> = TimeSeries::new();
+    let mut term_structures: Vec<TermStructure> = Vec::new();
+    // Skew at the IV target tenor per date, alongside the ATM IV series.
+    let mut skew_history: Vec<SkewMetrics> = Vec::new();
+    let mut selection_rejections: Vec<(NaiveDate, SelectionError)> = Vec::new();
+    let mut liquidity_reports: Vec<FilterReport> = Vec::new();
+    let mut heston_fits: Vec<HestonFit> = Vec::new();
+    // (date, Heston expected vol to the scoring horizon), scored with IV and HV.
+    let mut heston_vols: TimeSeries<f64> = TimeSeries::new();
     let mut fetched_options_dates: HashSet<NaiveDate> = HashSet::new(); // Track dates for which options are successfully processed
 
     let mut options_requests_count = 0;
@@ -110,22 +293,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
             break; // Exit the loop if we've hit our request limit
         }
 
-        let current_date_naive = match NaiveDate::parse_from_str(&ohlcv_entry.date, "%Y-%m-%d") {
-            Ok(date) => date,
-            Err(_) => {
-                eprintln!(
-                    "Could not parse OHLCV date: {}. Skipping.",
-                    ohlcv_entry.date
-                );
-                continue;
-            }
-        };
+        let current_date = ohlcv_entry.date;
 
         // If we've already processed options for this date, skip it.
-        if fetched_options_dates.contains(&current_date_naive) {
+        if fetched_options_dates.contains(&current_date) {
             println!(
                 "Skipping {}: Options for this date already processed.",
-                current_date_naive.format("%Y-%m-%d")
+                current_date
             );
             continue;
         }
@@ -133,7 +307,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         // Implement the bi-weekly fetch logic
         let should_fetch = match last_fetch_date {
             Some(last_date) => {
-                let duration = current_date_naive.signed_duration_since(last_date);
+                let duration = current_date.signed_duration_since(last_date);
                 duration.num_days() >= fetch_interval_days as i64
             }
             None => true, // Always fetch the first available date
@@ -142,132 +316,353 @@ async fn main() -> Result<(), Box<dyn Error>> {
         if !should_fetch {
             println!(
                 "Skipping {}: Not yet two weeks since last options fetch.",
-                current_date_naive.format("%Y-%m-%d")
+                current_date
             );
             continue;
         }
 
-        let current_date_str = ohlcv_entry.date.clone();
         let current_ohlcv_close = ohlcv_entry.close;
-
-        let Some(target_expiration_date) = current_date_naive
-            .checked_add_signed(Duration::days(iv_option_target_window_days as i64))
-        else {
-            println!(
-                "Skipping {}: Could not calculate target expiration date.",
-                current_date_str
-            );
-            continue;
-        };
+        // Close the forecast made today is scored against. Every forecast
+        // below targets the calendar days to it, ACT/365 like the pricer, so
+        // each covers exactly the trading days it is scored on. Past the end
+        // of the price data the window is approximated.
+        let horizon_end =
+            forecast_window_end(&ohlcv_data, current_date, iv_option_target_window_days);
+        let horizon_days = horizon_end.map_or(
+            (iv_option_target_window_days as f64 * 365.0 / 252.0).round() as usize,
+            |end| (end - current_date).num_days().max(0) as usize,
+        );
+        let target_years = horizon_days as f64 / 365.0;
 
         println!(
             "Fetching options for {} (Request {}/{})",
-            current_date_str,
+            current_date,
             options_requests_count + 1,
             max_options_requests
         );
-        let options_chain_result =
-            options_data(key.clone(), symbol.clone(), current_date_str.clone()).await;
+        let options_chain_result = match &simulated_market {
+            Some(market) => Ok(market.options_on(current_date)),
+            None => options_data(key.clone(), symbol.clone(), current_date).await,
+        };
         options_requests_count += 1; // Increment immediately after calling the API
 
-        let Ok(mut options_chain_for_day) = options_chain_result else {
-            eprintln!(
-                "Skipping {}: Error fetching options data: {:?}",
-                current_date_str,
-                options_chain_result.unwrap_err()
-            );
-            continue;
+        let options = match options_chain_result {
+            Ok(options) => options,
+            Err(err) => {
+                eprintln!(
+                    "Skipping {}: Error fetching options data: {:?}",
+                    current_date, err
+                );
+                continue;
+            }
         };
-
-        if options_chain_for_day.is_empty() {
-            println!(
-                "Skipping {}: No options data found for this date after fetch.",
-                current_date_str
-            );
-            continue;
+        if !options.is_empty() {
+            audit_report.chains.extend(data_audit.audit_chain(&options));
+            audit_report.chains_audited += 1;
+            audit_report.contracts_audited += options.len();
+            // Stop before spending the remaining requests.
+            if let Err(failure) = audit_report.enforce(audit_policy) {
+                eprintln!("{}", audit_report);
+                return Err(failure.into());
+            }
         }
-
-        let available_expirations: Vec<NaiveDate> = options_chain_for_day
-            .iter()
-            .filter_map(|opt| NaiveDate::parse_from_str(&opt.expiration, "%Y-%m-%d").ok())
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect();
-
-        let Some(closest_expiration_date) =
-            find_closest_date(&available_expirations, target_expiration_date)
-        else {
+        let Some(raw_chain) = OptionChain::new(options) else {
             println!(
-                "Skipping {}: No closest expiration date found (target: {}). Available expirations: {:?}",
-                current_date_str, target_expiration_date, available_expirations
+                "Skipping {}: No options data found for this date after fetch.",
+                current_date
             );
             continue;
         };
 
-        if !ohlcv_map.contains_key(&closest_expiration_date) {
+        // Forwards from put-call parity on the raw chain, before any filtering.
+        // ATM, moneyness and pricing are all measured against these.
+        let forwards = ForwardCurve::from_chain(
+            &raw_chain,
+            current_ohlcv_close,
+            risk_free_rate,
+            dividend_yield,
+        );
+        if let Some(estimate) = forwards.estimates.iter().min_by(|a, b| {
+            (a.time_to_expiry - target_years)
+                .abs()
+                .total_cmp(&(b.time_to_expiry - target_years).abs())
+        }) {
             println!(
-                "Skipping options from {} targeting expiry {}: No OHLCV data for this actual expiry date. Cannot calculate realized volatility.",
-                current_date_str, closest_expiration_date
+                "Parity forward for {} expiry {}: {:.2} vs close {:.2} (median of {} pairs, dispersion {:.3}), implied dividend/borrow yield {:.4} ({} expiries)",
+                current_date,
+                estimate.expiration,
+                estimate.forward,
+                current_ohlcv_close,
+                estimate.pairs_used,
+                estimate.dispersion,
+                estimate.implied_dividend_yield,
+                forwards.estimates.len()
             );
-            continue;
         }
 
-        let options_at_closest_expiry: Vec<OptionsData> = options_chain_for_day
-            .drain(..)
-            .filter(|opt| {
-                NaiveDate::parse_from_str(&opt.expiration, "%Y-%m-%d").ok()
-                    == Some(closest_expiration_date)
-            })
-            .collect();
-
-        if options_at_closest_expiry.is_empty() {
+        // The VIX strike walk stops at two consecutive zero bids, so it runs on
+        // the unfiltered chain rather than on what the liquidity filter keeps.
+        if let Some(model_free) = model_free_vol(&raw_chain, risk_free_rate, horizon_days) {
             println!(
-                "Skipping {}: No options found for closest expiration date {}.",
-                current_date_str, closest_expiration_date
+                "Model-free {:.0}-day IV for {}: {:.2} (VIX-style, variance {:.5})",
+                model_free.target_years * 365.0,
+                current_date,
+                model_free.vol * 100.0,
+                model_free.variance
             );
-            continue;
+            for (term, expiry) in [("near", &model_free.near), ("next", &model_free.next)] {
+                println!(
+                    "  {} term {}: forward {:.2}, K0 {:.2}, {} strikes, variance {:.5}",
+                    term,
+                    expiry.expiration,
+                    expiry.forward,
+                    expiry.k0,
+                    expiry.strikes_used,
+                    expiry.variance
+                );
+            }
+            model_free_vols.insert(current_date, model_free.vol);
         }
 
-        let mut available_strikes_temp: Vec<f64> = options_at_closest_expiry
-            .iter()
-            .map(|opt| opt.strike)
-            .collect();
+        let raw_options = raw_chain.into_options();
+        print_vendor_check(
+            current_date,
+            &value_chain(
+                &BlackScholes,
+                &raw_options,
+                current_ohlcv_close,
+                risk_free_rate,
+                dividend_yield,
+            ),
+            &raw_options,
+        );
+        print_iv_check(current_date, &raw_options, &forwards);
 
-        available_strikes_temp.sort_unstable_by(|a, b| {
-            a.partial_cmp(b)
-                .expect("Strike prices should be comparable and not NaN")
-        });
+        let mut options_chain_for_day = match iv_price_source {
+            None => raw_options
+                .into_iter()
+                .filter(|opt| opt.implied_volatility > 0.0 && opt.last > 0.0)
+                .collect(),
+            Some(source) => {
+                with_solved_implied_volatility(iv_pricer.as_ref(), raw_options, &forwards, source)
+            }
+        };
 
-        available_strikes_temp.dedup();
+        if let Some(filter) = &liquidity_filter {
+            let (filtered, report) = filter.apply(options_chain_for_day, current_date);
+            println!("Liquidity filter for {}: {}", report.date, report);
+            options_chain_for_day = filtered;
+            liquidity_reports.push(report);
+        }
 
-        let available_strikes: Vec<f64> = available_strikes_temp;
-        let Some(closest_strike) = find_closest_num(&available_strikes, current_ohlcv_close) else {
+        let Some(chain) = OptionChain::new(options_chain_for_day) else {
             println!(
-                "Skipping {}: No closest strike found (target close: {}). Available strikes: {:?}",
-                current_date_str, current_ohlcv_close, available_strikes
+                "Skipping {}: No options left for this date after filtering.",
+                current_date
             );
             continue;
         };
+        let in_window = horizon_end.map_or(0, |end| chain.expiry_range(current_date, end).count());
+        let (iv_low, iv_high) = chain
+            .options()
+            .map(|option| option.implied_volatility)
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), iv| {
+                (low.min(iv), high.max(iv))
+            });
+        println!(
+            "{} chain for {}: {} contracts over {} expiries ({} inside the forecast window), IV {:.4} to {:.4}",
+            chain.symbol,
+            chain.date,
+            chain.len(),
+            chain.expiries().count(),
+            in_window,
+            iv_low,
+            iv_high
+        );
 
-        if let Some(target_option) = options_at_closest_expiry.into_iter().find(|opt| {
-            opt.strike == closest_strike
-                && NaiveDate::parse_from_str(&opt.expiration, "%Y-%m-%d").ok()
-                    == Some(closest_expiration_date)
+        let smiles = fit_chain_smiles(&chain, &forwards, smile_weighting);
+        if let Some(smile) = smiles.iter().min_by(|a, b| {
+            (a.time_to_expiry - target_years)
+                .abs()
+                .total_cmp(&(b.time_to_expiry - target_years).abs())
         }) {
-            all_relevant_options.push(target_option.clone());
-            fetched_options_dates.insert(current_date_naive);
-            last_fetch_date = Some(current_date_naive); // Update the last fetch date
+            let natural = smile.natural();
             println!(
-                "Successfully processed option for {}. Total relevant options: {}",
-                current_date_str,
-                all_relevant_options.len()
+                "SVI smile for {} expiry {} (forward {:.2}): ATM IV {:.4}, IV RMSE {:.4} (weighted {:.4}, max {:.4}, R^2 {:.3}) over {} strikes ({} expiries fitted)",
+                current_date,
+                smile.expiration,
+                smile.forward,
+                smile.atm_iv().unwrap_or(f64::NAN),
+                smile.quality.rmse,
+                smile.quality.weighted_rmse,
+                smile.quality.max_abs_error,
+                smile.quality.r_squared,
+                smile.quality.n,
+                smiles.len()
             );
-        } else {
             println!(
-                "Skipping {}: Could not find a specific option matching closest strike {} and expiry {}.",
-                current_date_str, closest_strike, closest_expiration_date
+                "  natural SVI: delta {:.5}, omega {:.5}, mu {:.4}, zeta {:.3}, rho {:.3}; {} after {} iterations",
+                natural.delta,
+                natural.omega,
+                natural.mu,
+                natural.zeta,
+                natural.rho,
+                if smile.converged { "converged" } else { "not converged" },
+                smile.iterations
             );
         }
+
+        if let Some(skew) = skew_at_tenor(&smiles, current_date, horizon_days) {
+            println!(
+                "{}-day skew for {} ({}/{}): RR25 {:.4}, BF25 {:.4}, RR10 {:.4}, BF10 {:.4}, ATM slope {:.4}",
+                skew.tenor_days,
+                skew.date,
+                skew.lower_expiration,
+                skew.upper_expiration,
+                skew.risk_reversal_25,
+                skew.butterfly_25,
+                skew.risk_reversal_10,
+                skew.butterfly_10,
+                skew.slope
+            );
+            skew_history.push(skew);
+        }
+
+        if let Some(term_structure) = build_term_structure(&chain, &forwards) {
+            let tenors: Vec<String> = term_structure
+                .tenors
+                .iter()
+                .map(|tenor| {
+                    let flat = if tenor.extrapolated { " (flat)" } else { "" };
+                    format!("{}d {:.4}{}", tenor.days, tenor.iv, flat)
+                })
+                .collect();
+            match term_structure.metrics() {
+                Some(metrics) => println!(
+                    "ATM term structure for {}: {} ({:?}, slope {:.4}/yr, 30d-90d spread {:.4}, curvature {:.4})",
+                    current_date,
+                    tenors.join(", "),
+                    metrics.shape,
+                    metrics.slope,
+                    metrics.front_spread,
+                    metrics.curvature
+                ),
+                None => println!(
+                    "ATM term structure for {}: {}",
+                    current_date,
+                    tenors.join(", ")
+                ),
+            }
+            if let (Some(iv), Some(front_slope)) = (
+                term_structure.iv_at(horizon_days),
+                term_structure.slope(7, 30),
+            ) {
+                println!(
+                    "  {}-day ATM IV {:.4}, 7d-30d slope {:.4}/yr",
+                    horizon_days, iv, front_slope
+                );
+            }
+            term_structures.push(term_structure);
+        }
+
+        if let Some(calibration) = &heston_calibration {
+            let start = heston_fits.last().map(|fit| fit.params);
+            match calibration.calibrate(&chain, &forwards, start) {
+                Some(fit) => {
+                    println!(
+                        "Heston fit for {}: kappa {:.3}, theta {:.4}, xi {:.3}, rho {:.3}, v0 {:.4}, IV RMSE {:.4} over {} quotes in {} expiries, {}-day vol {:.4}",
+                        current_date,
+                        fit.params.kappa,
+                        fit.params.theta,
+                        fit.params.xi,
+                        fit.params.rho,
+                        fit.params.v0,
+                        fit.iv_rmse,
+                        fit.quotes,
+                        fit.expiries,
+                        horizon_days,
+                        fit.expected_volatility(horizon_days)
+                    );
+                    heston_vols.insert(current_date, fit.expected_volatility(horizon_days));
+                    heston_fits.push(fit);
+                }
+                None => println!("Heston fit for {}: not enough quotes", current_date),
+            }
+        }
+
+        let context = SelectionContext {
+            chain: &chain,
+            forwards: &forwards,
+            symbol: &symbol,
+            date: current_date,
+            target_days: horizon_days,
+            last_price_date: latest_date_actual,
+            horizon_end,
+        };
+        match option_selector.select(&context) {
+            Ok(target_option) => {
+                println!(
+                    "Selected {} for {}: strike {:.2}, expiry {}, IV {:.4}",
+                    target_option.contract,
+                    current_date,
+                    target_option.strike,
+                    target_option.expiration,
+                    target_option.implied_volatility
+                );
+                if let Some(iv) = smiles
+                    .iter()
+                    .find(|smile| smile.expiration == target_option.expiration)
+                    .and_then(|smile| smile.iv_at_strike(target_option.strike))
+                {
+                    println!("  SVI IV at that strike: {:.4}", iv);
+                }
+                if let Some(market) = &simulated_market {
+                    println!(
+                        "  True vol: spot {:.4}, next {} days {:.4}",
+                        market.spot_volatility(current_date).unwrap_or(f64::NAN),
+                        iv_option_target_window_days,
+                        market
+                            .forward_volatility(current_date, iv_option_target_window_days)
+                            .unwrap_or(f64::NAN)
+                    );
+                }
+                print_strike_context(&chain, &target_option, &forwards);
+                print_selected_contract(
+                    &target_option,
+                    &forwards,
+                    american_check_steps,
+                    heston_fits.last().filter(|fit| fit.date == current_date),
+                );
+                for selector in &comparison_selectors {
+                    match selector.select(&context) {
+                        Ok(option) => println!(
+                            "  {}: {} IV {:.4}",
+                            selector.name(),
+                            option.contract,
+                            option.implied_volatility
+                        ),
+                        Err(reason) => println!("  {}: {}", selector.name(), reason),
+                    }
+                }
+                all_relevant_options.push(target_option);
+                fetched_options_dates.insert(current_date);
+                last_fetch_date = Some(current_date); // Update the last fetch date
+                println!(
+                    "Successfully processed option for {}. Total relevant options: {}",
+                    current_date,
+                    all_relevant_options.len()
+                );
+            }
+            Err(reason) => {
+                println!(
+                    "Skipping {}: {} selection rejected the date: {}",
+                    current_date,
+                    option_selector.name(),
+                    reason
+                );
+                selection_rejections.push((current_date, reason));
+            }
+        }
     }
 
     if options_requests_count >= max_options_requests {
@@ -277,15 +672,73 @@ async fn main() -> Result<(), Box<dyn Error>> {
         "\nTotal options_data requests made: {}",
         options_requests_count
     );
+    if audit_report.is_clean() {
+        println!(
+            "\nData audit: no issues in {} price bars and {} chains",
+            ohlcv_data.len(),
+            audit_report.chains_audited
+        );
+    } else {
+        println!("\nData audit:\n{}", audit_report);
+    }
     println!(
         "Total relevant options collected for IV accuracy: {}",
         all_relevant_options.len()
     );
+    if !liquidity_reports.is_empty() {
+        let input: usize = liquidity_reports.iter().map(|r| r.input).sum();
+        let kept: usize = liquidity_reports.iter().map(|r| r.kept).sum();
+        println!(
+            "Liquidity filters kept {}/{} contracts over {} dates",
+            kept,
+            input,
+            liquidity_reports.len()
+        );
+    }
+    println!(
+        "Dates rejected by {} selection: {}",
+        option_selector.name(),
+        selection_rejections.len()
+    );
+    for (date, reason) in &selection_rejections {
+        println!("  {}: {}", date, reason);
+    }
+    // Compare against published VIX closes on the same dates as a sanity check.
+    println!(
+        "Model-free IV to the scoring horizon (VIX points): {:?}",
+        model_free_vols
+            .iter()
+            .map(|(date, vol)| (date.to_string(), (vol * 10000.0).round() / 100.0))
+            .collect::<Vec<_>>()
+    );
 
-    let iv_accuracy_results = iv_accuracy(
+    if !heston_fits.is_empty() {
+        println!("\nHeston parameters (kappa, theta, xi, rho, v0, Feller ratio, IV RMSE):");
+        for fit in &heston_fits {
+            println!(
+                "  {}: {:.3}, {:.4}, {:.3}, {:.3}, {:.4}, {:.2}, {:.4}{}",
+                fit.date,
+                fit.params.kappa,
+                fit.params.theta,
+                fit.params.xi,
+                fit.params.rho,
+                fit.params.v0,
+                fit.params.feller_ratio(),
+                fit.iv_rmse,
+                if fit.converged {
+                    ""
+                } else {
+                    " (not converged)"
+                }
+            );
+        }
+    }
+
+    let iv_accuracy_results = iv_accuracy_with_mode(
         &all_relevant_options,
         &ohlcv_data,
         iv_option_target_window_days,
+        scoring_mode,
     );
 
     println!(
@@ -295,18 +748,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     );
 
     // Filter HV accuracy results to match the dates present in iv_accuracy_results
-    let iv_dates: HashSet<NaiveDate> = iv_accuracy_results
-        .iter()
-        .map(|(date_str, _)| NaiveDate::parse_from_str(date_str, "%Y-%m-%d").unwrap())
-        .collect();
-
-    let hv_accuracy_filtered_results: Vec<(String, f64)> = hv_accuracy_full_results
-        .into_iter()
-        .filter(|(date_str, _)| {
-            NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-                .map_or(false, |date| iv_dates.contains(&date))
-        })
-        .collect();
+    let hv_accuracy_filtered_results = hv_accuracy_full_results.restrict_to(&iv_accuracy_results);
 
     println!(
         "\nFiltered HV Accuracy (first 100 entries): {:?}",
@@ -315,31 +757,324 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .take(100)
             .collect::<Vec<_>>()
     );
-    if let Some(mae) = calculate_mae(&iv_accuracy_results) {
-        println!("Mean Absolute Error (MAE) of IV: {:.4}", mae);
-    } else {
-        println!("Could not calculate MAE. Not enough common data points.");
+
+    // Consecutive forecasts overlap, so errors are autocorrelated. The horizon
+    // is in trading days, the fetch interval in calendar days.
+    let overlap = overlap_lags(
+        iv_option_target_window_days as f64 * 365.0 / 252.0,
+        fetch_interval_days as f64,
+    );
+    let hac_lags = match (hac_lag_count, overlap) {
+        (Some(lags), _) => LagSelection::Fixed(lags),
+        (None, 0) => LagSelection::Automatic,
+        (None, overlap) => LagSelection::AtLeast(overlap),
+    };
+    let hac_estimator = CovarianceEstimator::NeweyWest(hac_lags);
+    let bootstrap = BlockBootstrap {
+        block_length: bootstrap_block_length,
+        min_block_length: overlap + 1,
+        resamples: bootstrap_resamples,
+        ..BlockBootstrap::default()
+    };
+
+    for (label, results) in [
+        ("IV", &iv_accuracy_results),
+        ("HV", &hv_accuracy_filtered_results),
+    ] {
+        if let Some(mae) = calculate_mae(results) {
+            match calculate_mae_confidence_interval(results, hac_lags, 0.95) {
+                Some(ci) => println!(
+                    "Mean Absolute Error (MAE) of {}: {:.4} ({:.0}% HAC CI {:.4} to {:.4})",
+                    label,
+                    ci.estimate,
+                    ci.level * 100.0,
+                    ci.lower,
+                    ci.upper
+                ),
+                None => println!("Mean Absolute Error (MAE) of {}: {:.4}", label, mae),
+            }
+            if let Some(ci) = bootstrap.confidence_interval(results, calculate_mae) {
+                println!(
+                    "  {:.0}% block bootstrap CI: {:.4} to {:.4}",
+                    ci.level * 100.0,
+                    ci.lower,
+                    ci.upper
+                );
+            }
+        } else {
+            println!("Could not calculate MAE. Not enough common data points.");
+        }
     }
 
-    if let Some(mae) = calculate_mae(&hv_accuracy_filtered_results) {
-        println!("Mean Absolute Error (MAE) of HV: {:.4}", mae);
-    } else {
-        println!("Could not calculate MAE. Not enough common data points.");
+    // MAE under every scoring mode, to show how much the choice of scoring
+    // moves the IV/HV comparison.
+    println!("\nMAE by scoring mode (IV / HV):");
+    for mode in [
+        ScoringMode::PriceMove,
+        ScoringMode::RealizedVolatility,
+        ScoringMode::RealizedVariance,
+    ] {
+        let iv_results = iv_accuracy_with_mode(
+            &all_relevant_options,
+            &ohlcv_data,
+            iv_option_target_window_days,
+            mode,
+        );
+        let hv_results =
+            hv_accuracy_with_mode(&ohlcv_data, hv_window_days, mode).restrict_to(&iv_results);
+        let format_mae = |results: &TimeSeries<f64>| match calculate_mae(results) {
+            Some(mae) => format!("{:.4}", mae),
+            None => "n/a".to_string(),
+        };
+        println!(
+            "  {:<20} {} / {}",
+            format!("{:?}", mode),
+            format_mae(&iv_results),
+            format_mae(&hv_results)
+        );
     }
 
-    if let Some(correlation) =
-        calculate_accuracy_correlation(&iv_accuracy_results, &hv_accuracy_filtered_results)
-    {
-        println!("Correlation between IV and HV accuracy: {:.4}", correlation);
-    } else {
-        println!("Could not calculate correlation. Not enough common data points.");
+    // Calendar views of the errors: the last quarter, month-end IV and
+    // week-end HV errors, and how much of the trading calendar the model-free
+    // IV covers when carried forward between fetches.
+    let format_mae = |results: &[(NaiveDate, f64)]| match calculate_mae(results) {
+        Some(mae) => format!("{:.4}", mae),
+        None => "n/a".to_string(),
+    };
+    if let (Some(first), Some(last)) = (
+        iv_accuracy_results.first_date(),
+        iv_accuracy_results.last_date(),
+    ) {
+        println!(
+            "\nIV forecasts scored from {} to {}, MAE over the last quarter {}",
+            first,
+            last,
+            format_mae(iv_accuracy_results.between(last - Duration::days(91), last))
+        );
+    }
+    println!(
+        "Month-end IV errors: {:?}",
+        iv_accuracy_results
+            .resample(Frequency::Monthly)
+            .into_points()
+            .into_iter()
+            .map(|(date, error)| (date.to_string(), (error * 10000.0).round() / 10000.0))
+            .collect::<Vec<_>>()
+    );
+    let weekly_hv = hv_accuracy_full_results.resample(Frequency::Weekly);
+    println!(
+        "Week-end HV errors: {} weeks, MAE {}",
+        weekly_hv.len(),
+        format_mae(&weekly_hv)
+    );
+    let model_free_daily = model_free_vols
+        .forward_fill_to(&hv_accuracy_full_results, Some(fetch_interval_days as i64));
+    let model_free_on_iv_dates = model_free_vols.align_to(&iv_accuracy_results);
+    println!(
+        "Model-free IV carried forward over {} of {} trading days, quoted on {} of {} IV forecast dates",
+        model_free_daily.len(),
+        hv_accuracy_full_results.len(),
+        model_free_on_iv_dates
+            .values()
+            .filter(|vol| vol.is_some())
+            .count(),
+        model_free_on_iv_dates.len()
+    );
+
+    // Loss functions on (forecast vol, realized vol over the window) pairs.
+    let iv_pairs = iv_forecast_pairs(
+        &all_relevant_options,
+        &ohlcv_data,
+        iv_option_target_window_days,
+    );
+    let hv_pairs: Vec<_> = hv_forecast_pairs(&ohlcv_data, hv_window_days)
+        .into_iter()
+        .filter(|pair| iv_accuracy_results.contains_date(pair.date))
+        .collect();
+    let heston_pairs = forecast_pairs(&heston_vols, &ohlcv_data, iv_option_target_window_days);
+    println!(
+        "\nForecast losses vs realized vol ({} IV pairs, {} HV pairs, {} Heston pairs):",
+        iv_pairs.len(),
+        hv_pairs.len(),
+        heston_pairs.len()
+    );
+    for loss in standard_losses() {
+        let format_loss = |value: Option<f64>| match value {
+            Some(v) => format!("{:.6}", v),
+            None => "n/a".to_string(),
+        };
+        println!(
+            "  {:<15} IV: {:>12}  HV: {:>12}  Heston: {:>12}",
+            loss.name(),
+            format_loss(evaluate_loss(&iv_pairs, loss.as_ref())),
+            format_loss(evaluate_loss(&hv_pairs, loss.as_ref())),
+            format_loss(evaluate_loss(&heston_pairs, loss.as_ref()))
+        );
+    }
+
+    // Diebold-Mariano: is the IV-vs-HV loss gap statistically meaningful?
+    println!("\nDiebold-Mariano tests, IV vs HV (negative favours IV):");
+    for loss in standard_losses() {
+        match diebold_mariano(&iv_pairs, &hv_pairs, loss.as_ref(), overlap) {
+            Some(dm) => println!(
+                "  {:<15} DM = {:>8.4}, p = {:.4}, n = {}, {} HAC lags, mean loss difference {:.6}",
+                loss.name(),
+                dm.statistic,
+                dm.p_value,
+                dm.n,
+                dm.lags,
+                dm.mean_differential
+            ),
+            None => println!("  {:<15} not enough common data points", loss.name()),
+        }
+    }
+
+    // Density calibration: PIT of the realized return under each forecast's lognormal.
+    let iv_density = iv_density_observations(
+        &all_relevant_options,
+        &ohlcv_data,
+        iv_option_target_window_days,
+    );
+    let hv_density: Vec<_> = hv_density_observations(&ohlcv_data, hv_window_days)
+        .into_iter()
+        .filter(|obs| iv_accuracy_results.contains_date(obs.date))
+        .collect();
+    for (label, observations) in [("IV", &iv_density), ("HV", &hv_density)] {
+        match calibration_report(observations, pit_histogram_bins) {
+            Some(report) => print_calibration(label, &report),
+            None => println!(
+                "\nCould not compute {} calibration. No usable data points.",
+                label
+            ),
+        }
+    }
+
+    // Mincer-Zarnowitz: is each forecast unbiased for realized vol? Newey-West
+    // standard errors throughout.
+    match mincer_zarnowitz(&iv_pairs, hac_estimator) {
+        Some(mz) => print_mincer_zarnowitz("IV", &mz),
+        None => println!("Could not run Mincer-Zarnowitz regression for IV."),
+    }
+    match mincer_zarnowitz(&hv_pairs, hac_estimator) {
+        Some(mz) => print_mincer_zarnowitz("HV", &mz),
+        None => println!("Could not run Mincer-Zarnowitz regression for HV."),
+    }
+    match encompassing_regression(&iv_pairs, &hv_pairs, hac_estimator) {
+        Some(test) => print_encompassing(&test),
+        None => println!("Could not run encompassing regression. Not enough common data points."),
+    }
+
+    let paired = paired_accuracy_values(&iv_accuracy_results, &hv_accuracy_filtered_results);
+    for (label, method) in [
+        ("Pearson", CorrelationMethod::Pearson),
+        ("Spearman", CorrelationMethod::Spearman),
+        ("Kendall", CorrelationMethod::Kendall),
+    ] {
+        match calculate_accuracy_correlation(
+            &iv_accuracy_results,
+            &hv_accuracy_filtered_results,
+            method,
+        ) {
+            Ok(correlation) => {
+                println!(
+                    "{} correlation between IV and HV accuracy: {:.4}",
+                    label, correlation
+                );
+                if let Some(ci) = bootstrap
+                    .confidence_interval(&paired, |sample| paired_correlation(sample, method).ok())
+                {
+                    println!(
+                        "  {:.0}% block bootstrap CI: {:.4} to {:.4}",
+                        ci.level * 100.0,
+                        ci.lower,
+                        ci.upper
+                    );
+                }
+            }
+            Err(e) => println!("Could not calculate {} correlation: {}.", label, e),
+        }
+    }
+    if let Some(test) = accuracy_correlation_test(
+        &iv_accuracy_results,
+        &hv_accuracy_filtered_results,
+        hac_estimator,
+    ) {
+        println!(
+            "  Pearson {:.4}, HAC standard error {:.4}, p-value {:.4} (n = {})",
+            test.correlation, test.std_error, test.p_value, test.n
+        );
+    }
+
+    let rolling = rolling_accuracy_correlation(
+        &iv_accuracy_results,
+        &hv_accuracy_filtered_results,
+        rolling_correlation_window,
+        CorrelationMethod::Spearman,
+    );
+    println!(
+        "\nRolling {}-point Spearman correlation of IV and HV accuracy:",
+        rolling_correlation_window
+    );
+    for (date, result) in &rolling {
+        match result {
+            Ok(correlation) => println!("  {}: {:.4}", date, correlation),
+            Err(e) => println!("  {}: n/a ({})", date, e),
+        }
+    }
+
+    let study = term_structure_error_study(
+        &term_structures,
+        &iv_accuracy_results,
+        &hv_accuracy_filtered_results,
+    );
+    println!("\nIV and HV errors by ATM term-structure shape on the forecast date:");
+    for summary in &study.by_shape {
+        println!(
+            "  {:?}: n = {}, mean IV error {:.4}, mean HV error {:.4}",
+            summary.shape, summary.n, summary.mean_iv_error, summary.mean_hv_error
+        );
+    }
+    match &study.slope_vs_excess_error {
+        Ok(correlation) => println!(
+            "  Spearman correlation of 30d-180d slope with IV minus HV error: {:.4}",
+            correlation
+        ),
+        Err(e) => println!(
+            "  Spearman correlation of 30d-180d slope with IV minus HV error: n/a ({})",
+            e
+        ),
+    }
+
+    match skew_downside_regression(
+        &skew_history,
+        &ohlcv_data,
+        iv_option_target_window_days,
+        hac_estimator,
+    ) {
+        Some(test) => {
+            println!(
+                "\nDownside realized vol on ATM IV and RR25 (n = {}, HAC lags {}):",
+                test.ols.n, test.ols.lags
+            );
+            for (name, (coefficient, std_error)) in ["alpha", "beta_atm", "beta_rr25"]
+                .iter()
+                .zip(test.ols.coefficients.iter().zip(&test.ols.std_errors))
+            {
+                println!("  {} = {:.4} (se {:.4})", name, coefficient, std_error);
+            }
+            println!(
+                "  Skew adds nothing beyond ATM IV: Wald {:.4}, p-value {:.4}",
+                test.skew_adds_nothing.statistic, test.skew_adds_nothing.p_value
+            );
+        }
+        None => println!("\nNot enough skew history for the downside regression."),
     }
 
     if !iv_accuracy_results.is_empty() || !hv_accuracy_filtered_results.is_empty() {
         let output_file = "accuracy_comparison.png";
         match draw_accuracy_graph(
-            iv_accuracy_results,
-            hv_accuracy_filtered_results,
+            &iv_accuracy_results,
+            &hv_accuracy_filtered_results,
             output_file,
         ) {
             Ok(_) => println!("Graph generated successfully at {}", output_file),
@@ -352,3 +1087,274 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+fn print_mincer_zarnowitz(label: &str, mz: &MincerZarnowitz) {
+    let ols = &mz.ols;
+    println!(
+        "\nMincer-Zarnowitz ({}): realized = {:.4} (se {:.4}) + {:.4} (se {:.4}) * {}, R^2 = {:.4} (adjusted {:.4}), n = {}, Durbin-Watson {:.2}",
+        label,
+        ols.coefficients[0],
+        ols.std_errors[0],
+        ols.coefficients[1],
+        ols.std_errors[1],
+        label,
+        ols.r_squared,
+        ols.adjusted_r_squared,
+        ols.n,
+        ols.durbin_watson()
+    );
+    println!(
+        "  Wald alpha=0: {:.4} (p={:.4}), beta=1: {:.4} (p={:.4}), joint chi^2({}): {:.4} (p={:.4})",
+        mz.alpha_zero.statistic,
+        mz.alpha_zero.p_value,
+        mz.beta_one.statistic,
+        mz.beta_one.p_value,
+        mz.unbiasedness.df,
+        mz.unbiasedness.statistic,
+        mz.unbiasedness.p_value
+    );
+}
+
+fn print_encompassing(test: &EncompassingTest) {
+    let ols = &test.ols;
+    println!(
+        "\nEncompassing: realized = {:.4} (se {:.4}) + {:.4} (se {:.4}) * IV + {:.4} (se {:.4}) * HV, R^2 = {:.4} (adjusted {:.4}), n = {}, Durbin-Watson {:.2}",
+        ols.coefficients[0],
+        ols.std_errors[0],
+        ols.coefficients[1],
+        ols.std_errors[1],
+        ols.coefficients[2],
+        ols.std_errors[2],
+        ols.r_squared,
+        ols.adjusted_r_squared,
+        ols.n,
+        ols.durbin_watson()
+    );
+    println!(
+        "  Wald beta_hv=0: {:.4} (p={:.4}), beta_iv=0: {:.4} (p={:.4})",
+        test.hv_adds_nothing.statistic,
+        test.hv_adds_nothing.p_value,
+        test.iv_adds_nothing.statistic,
+        test.iv_adds_nothing.p_value
+    );
+}
+
+fn print_calibration(label: &str, report: &CalibrationReport) {
+    println!(
+        "\nCalibration of {} lognormal forecasts (n = {}):",
+        label, report.n
+    );
+    println!(
+        "  PIT values: {:?}",
+        report
+            .pit_values
+            .iter()
+            .map(|u| (u * 1000.0).round() / 1000.0)
+            .collect::<Vec<_>>()
+    );
+    println!("  PIT histogram: {:?}", report.pit_histogram);
+    println!(
+        "  Uniformity: chi^2 = {:.4} (p={:.4}), KS = {:.4} (p={:.4})",
+        report.chi_squared.statistic,
+        report.chi_squared.p_value,
+        report.kolmogorov_smirnov.statistic,
+        report.kolmogorov_smirnov.p_value
+    );
+    println!(
+        "  1-sigma hit rate {:.1}% ({}/{}, nominal {:.1}%), 2-sigma hit rate {:.1}% ({}/{}, nominal {:.1}%)",
+        report.one_sigma.hit_rate * 100.0,
+        report.one_sigma.hits,
+        report.n,
+        report.one_sigma.nominal * 100.0,
+        report.two_sigma.hit_rate * 100.0,
+        report.two_sigma.hits,
+        report.n,
+        report.two_sigma.nominal * 100.0
+    );
+    println!(
+        "  Mean CRPS {:.6}, mean log score {:.4}",
+        report.mean_crps, report.mean_log_score
+    );
+}
+
+// Our Black-Scholes delta at the vendor IV against the vendor's own delta.
+// Large gaps point at a different pricing model or stale greeks.
+fn print_vendor_check(date: NaiveDate, valuations: &[ChainValuation], options: &[OptionsData]) {
+    // Only contracts the vendor gave an IV, since their greeks are zero otherwise.
+    let delta_gaps: Vec<(f64, &ChainValuation)> = valuations
+        .iter()
+        .zip(options)
+        .filter(|(_, option)| option.implied_volatility > 0.0)
+        .map(|(valuation, option)| ((valuation.greeks.delta - option.delta).abs(), valuation))
+        .collect();
+    let Some((largest, worst)) = delta_gaps.iter().max_by(|a, b| a.0.total_cmp(&b.0)) else {
+        return;
+    };
+    let mean_gap = delta_gaps.iter().map(|(gap, _)| gap).sum::<f64>() / delta_gaps.len() as f64;
+    println!(
+        "Vendor delta check for {}: mean |BS - vendor| {:.4} over {} contracts, largest {:.4} on {} (strike {:.2}, {:.0} days, BS price {:.4})",
+        date,
+        mean_gap,
+        delta_gaps.len(),
+        largest,
+        worst.contract,
+        worst.inputs.strike,
+        worst.inputs.time_to_expiry * 365.0,
+        worst.price
+    );
+}
+
+// Our Black-Scholes IV from each price against the vendor IV, to show which
+// price the vendor solves from, and how wide the bid-ask spread is in vol.
+fn print_iv_check(date: NaiveDate, options: &[OptionsData], forwards: &ForwardCurve) {
+    let mut gaps = Vec::new();
+    let mut spreads: Vec<(f64, String)> = Vec::new();
+    for source in [PriceSource::Mid, PriceSource::Mark, PriceSource::Last] {
+        let solved = solve_chain_ivs(&BlackScholes, options, forwards, source);
+        let differences: Vec<f64> = options
+            .iter()
+            .zip(&solved)
+            .filter(|(option, _)| option.implied_volatility > 0.0)
+            .filter_map(|(option, own)| {
+                Some((own.iv.as_ref().ok()? - option.implied_volatility).abs())
+            })
+            .collect();
+        if let Some(mean_gap) = mean(&differences) {
+            gaps.push(format!(
+                "{:?} {:.4} ({})",
+                source,
+                mean_gap,
+                differences.len()
+            ));
+        }
+        if source == PriceSource::Mid {
+            spreads = solved
+                .into_iter()
+                .filter_map(|own| Some((own.bid_ask_iv_spread()?, own.contract)))
+                .collect();
+        }
+    }
+    if gaps.is_empty() {
+        return;
+    }
+    println!(
+        "Own vs vendor IV for {}: mean |difference| by price {}",
+        date,
+        gaps.join(", ")
+    );
+    spreads.sort_by(|a, b| a.0.total_cmp(&b.0));
+    if let (Some(median), Some(widest)) = (spreads.get(spreads.len() / 2), spreads.last()) {
+        println!(
+            "  Bid-ask IV spread: median {:.4}, widest {:.4} on {}",
+            median.0, widest.0, widest.1
+        );
+    }
+}
+
+// Where the selected contract sits in its expiry's strikes, and the IV of the
+// other type at the same strike. Synthetic contracts have no listed expiry.
+fn print_strike_context(chain: &OptionChain, option: &OptionsData, forwards: &ForwardCurve) {
+    let Some(slice) = chain.expiry(option.expiration) else {
+        return;
+    };
+    if slice.is_empty() {
+        return;
+    }
+    let forward = forwards.forward(slice.expiration, slice.time_to_expiry);
+    println!(
+        "  Expiry {}: {} strikes, {} contracts, {} strikes within 5% of the forward",
+        slice.expiration,
+        slice.len(),
+        slice.options().len(),
+        slice.strike_range(0.95 * forward, 1.05 * forward).count()
+    );
+    // Same-type IVs at the strikes around the forward.
+    let iv_at = |strike: f64| {
+        slice
+            .contract(strike, option.contract_type)
+            .map_or(f64::NAN, |contract| contract.implied_volatility)
+    };
+    if let ((Some(below), Some(above)), Some(nearest)) = (
+        slice.bracketing_strikes(forward),
+        slice.nearest_strike(forward),
+    ) {
+        println!(
+            "  Forward {:.2} is between strikes {:.2} (IV {:.4}) and {:.2} (IV {:.4}), nearest {:.2}",
+            forward,
+            below.strike,
+            iv_at(below.strike),
+            above.strike,
+            iv_at(above.strike),
+            nearest.strike
+        );
+    }
+    let other_type = match option.contract_type {
+        OptionType::Call => OptionType::Put,
+        OptionType::Put => OptionType::Call,
+    };
+    if let Some(other) = slice
+        .strike(option.strike)
+        .and_then(|pair| pair.get(other_type))
+    {
+        println!(
+            "  {:?} at the same strike: IV {:.4}",
+            other_type, other.implied_volatility
+        );
+    }
+}
+
+fn print_selected_contract(
+    option: &OptionsData,
+    forwards: &ForwardCurve,
+    tree_steps: usize,
+    heston: Option<&HestonFit>,
+) {
+    let inputs = forwards.inputs_for(option);
+    let greeks = BlackScholes.greeks(&inputs);
+    println!(
+        "  Forward {:.2}, greeks: delta {:.4}, gamma {:.5}, vega {:.4}, theta {:.4}, rho {:.4}, dividend rho {:.4}, vanna {:.4}, volga {:.4}, charm {:.4}, veta {:.4}",
+        inputs.forward(),
+        greeks.delta,
+        greeks.gamma,
+        greeks.vega,
+        greeks.theta,
+        greeks.rho,
+        greeks.dividend_rho,
+        greeks.vanna,
+        greeks.volga,
+        greeks.charm,
+        greeks.veta
+    );
+
+    // The vendor quotes are for American contracts, so compare lattice IVs
+    // from the mid with the European one and show the early-exercise premium.
+    let binomial = BinomialTree::american(tree_steps);
+    let trinomial = TrinomialTree::american(tree_steps);
+    let format_iv = |iv: Result<f64, ImpliedVolError>| match iv {
+        Ok(iv) => format!("{:.2}%", iv * 100.0),
+        Err(e) => format!("n/a ({})", e),
+    };
+    if let Some(mid) = PriceSource::Mid.price_of(option) {
+        println!(
+            "  American IV from mid {:.2}: binomial {}, trinomial {}",
+            mid,
+            format_iv(binomial.implied_volatility(&inputs, mid)),
+            format_iv(trinomial.implied_volatility(&inputs, mid))
+        );
+    }
+    match binomial.checked_price(&inputs) {
+        Some(american) => println!(
+            "  Early-exercise premium at {:.2}% vol: {:.4}",
+            inputs.volatility * 100.0,
+            american - BlackScholes.price(&inputs)
+        ),
+        None => println!("  Early-exercise premium: lattice too coarse for these inputs"),
+    }
+    if let Some(fit) = heston {
+        println!(
+            "  Heston price {:.4} vs Black-Scholes {:.4} at the selected IV",
+            fit.params.price(&inputs),
+            BlackScholes.price(&inputs)
+        );
+    }
+}
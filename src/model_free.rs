@@ -0,0 +1,353 @@
+// Model-free implied variance following the CBOE VIX methodology, using the
+// whole chain rather than one contract. Per expiry:
+//
+//   sigma^2 = (2/T) sum dK_i / K_i^2 e^{RT} Q(K_i) - (1/T) (F/K_0 - 1)^2
+//
+// over out-of-the-money puts below K_0 and calls above it, with Q(K_0) the
+// average of the put and call. The two expiries bracketing the target are then
+// interpolated in total variance. Times are ACT/365 days rather than the
+// minutes CBOE uses, so expect small differences from published VIX levels.
+
+use crate::api::{OptionType, OptionsData};
+use crate::chain::{ExpirySlice, OptionChain};
+use chrono::NaiveDate;
+
+#[derive(Debug, Clone)]
+pub struct ExpiryVariance {
+    pub expiration: NaiveDate,
+    // Year fraction, ACT/365.
+    pub time_to_expiry: f64,
+    // Forward from put-call parity at the strike where call and put mids are closest.
+    pub forward: f64,
+    // First strike at or below the forward.
+    pub k0: f64,
+    pub variance: f64,
+    // Number of strikes that contributed.
+    pub strikes_used: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModelFreeVol {
+    pub target_years: f64,
+    pub variance: f64,
+    // Annualized, sqrt(variance). Multiply by 100 for VIX points.
+    pub vol: f64,
+    pub near: ExpiryVariance,
+    pub next: ExpiryVariance,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct StrikeQuotes {
+    call_bid: f64,
+    call_mid: Option<f64>,
+    put_bid: f64,
+    put_mid: Option<f64>,
+}
+
+fn mid(option: &OptionsData) -> Option<f64> {
+    (option.bid > 0.0 && option.ask >= option.bid).then_some(0.5 * (option.bid + option.ask))
+}
+
+fn quotes_by_strike(slice: &ExpirySlice) -> Vec<(f64, StrikeQuotes)> {
+    slice
+        .strikes()
+        .map(|pair| {
+            let mut quotes = StrikeQuotes::default();
+            if let Some(call) = &pair.call {
+                quotes.call_bid = call.bid;
+                quotes.call_mid = mid(call);
+            }
+            if let Some(put) = &pair.put {
+                quotes.put_bid = put.bid;
+                quotes.put_mid = mid(put);
+            }
+            (pair.strike, quotes)
+        })
+        .collect()
+}
+
+// OTM strikes walking away from K_0, skipping zero bids and stopping after two
+// consecutive zero bids, as in the VIX rules.
+fn otm_prices<'a>(
+    strikes: impl Iterator<Item = &'a (f64, StrikeQuotes)>,
+    side: OptionType,
+) -> Vec<(f64, f64)> {
+    let mut selected = Vec::new();
+    let mut zero_bids = 0;
+    for (strike, quotes) in strikes {
+        let (bid, price) = match side {
+            OptionType::Call => (quotes.call_bid, quotes.call_mid),
+            OptionType::Put => (quotes.put_bid, quotes.put_mid),
+        };
+        match price {
+            Some(price) if bid > 0.0 => {
+                zero_bids = 0;
+                selected.push((*strike, price));
+            }
+            _ => {
+                zero_bids += 1;
+                if zero_bids >= 2 {
+                    break;
+                }
+            }
+        }
+    }
+    selected
+}
+
+// Model-free variance of a single expiry. None when the forward cannot be implied or fewer than two strikes
+// have usable quotes.
+pub fn expiry_variance(slice: &ExpirySlice, rate: f64) -> Option<ExpiryVariance> {
+    let t = slice.time_to_expiry;
+    if t <= 0.0 {
+        return None;
+    }
+    let growth = (rate * t).exp();
+    let quotes = quotes_by_strike(slice);
+
+    let (parity_strike, call, put) = quotes
+        .iter()
+        .filter_map(|(strike, q)| Some((*strike, q.call_mid?, q.put_mid?)))
+        .min_by(|a, b| (a.1 - a.2).abs().total_cmp(&(b.1 - b.2).abs()))?;
+    let forward = parity_strike + growth * (call - put);
+
+    let k0_index = quotes.iter().rposition(|(strike, _)| *strike <= forward)?;
+    let (k0, k0_quotes) = quotes[k0_index];
+    let k0_price = match (k0_quotes.call_mid, k0_quotes.put_mid) {
+        (Some(call), Some(put)) => 0.5 * (call + put),
+        (Some(price), None) | (None, Some(price)) => price,
+        (None, None) => return None,
+    };
+
+    let mut selected = otm_prices(quotes[..k0_index].iter().rev(), OptionType::Put);
+    selected.reverse();
+    selected.push((k0, k0_price));
+    selected.extend(otm_prices(quotes[k0_index + 1..].iter(), OptionType::Call));
+    if selected.len() < 2 {
+        return None;
+    }
+
+    let last = selected.len() - 1;
+    let sum: f64 = selected
+        .iter()
+        .enumerate()
+        .map(|(i, &(strike, price))| {
+            let delta_k = match i {
+                0 => selected[1].0 - strike,
+                i if i == last => strike - selected[i - 1].0,
+                i => 0.5 * (selected[i + 1].0 - selected[i - 1].0),
+            };
+            delta_k / strike.powi(2) * growth * price
+        })
+        .sum();
+    let variance = 2.0 / t * sum - (forward / k0 - 1.0).powi(2) / t;
+
+    Some(ExpiryVariance {
+        expiration: slice.expiration,
+        time_to_expiry: t,
+        forward,
+        k0,
+        variance,
+        strikes_used: selected.len(),
+    })
+}
+
+// Model-free variance for every expiry in a chain, sorted by time to expiry.
+pub fn expiry_variances(chain: &OptionChain, rate: f64) -> Vec<ExpiryVariance> {
+    chain
+        .expiries()
+        .filter_map(|slice| expiry_variance(slice, rate))
+        .filter(|expiry| expiry.variance > 0.0)
+        .collect()
+}
+
+// Model-free implied vol `target_days` calendar days out, interpolating total
+// variance between the last expiry at or before the target and the first one
+// after it. None unless two expiries bracket the target.
+pub fn model_free_vol(chain: &OptionChain, rate: f64, target_days: usize) -> Option<ModelFreeVol> {
+    let target_years = target_days as f64 / 365.0;
+    let variances = expiry_variances(chain, rate);
+    let next_index = variances
+        .iter()
+        .position(|expiry| expiry.time_to_expiry > target_years)?;
+    if next_index == 0 {
+        return None;
+    }
+    let near = variances[next_index - 1].clone();
+    let next = variances[next_index].clone();
+
+    let weight = (next.time_to_expiry - target_years) / (next.time_to_expiry - near.time_to_expiry);
+    let total_variance = weight * near.time_to_expiry * near.variance
+        + (1.0 - weight) * next.time_to_expiry * next.variance;
+    let variance = total_variance / target_years;
+    if variance <= 0.0 {
+        return None;
+    }
+
+    Some(ModelFreeVol {
+        target_years,
+        variance,
+        vol: variance.sqrt(),
+        near,
+        next,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::black_scholes::{BlackScholes, OptionPricer, PricingInputs};
+    use chrono::Duration;
+
+    fn date(days: i64) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, 2).unwrap() + Duration::days(days)
+    }
+
+    fn quote(days: i64, strike: f64, contract_type: OptionType, bid: f64, ask: f64) -> OptionsData {
+        OptionsData {
+            symbol: "SPX".to_string(),
+            contract: format!("SPX{}{:?}{}", days, contract_type, strike),
+            contract_type,
+            expiration: date(days),
+            date: date(0),
+            strike,
+            last: 0.5 * (bid + ask),
+            mark: 0.5 * (bid + ask),
+            bid,
+            ask,
+            volume: 10.0,
+            open_interest: 100.0,
+            implied_volatility: 0.2,
+            delta: 0.0,
+            gamma: 0.0,
+            theta: 0.0,
+            vega: 0.0,
+            rho: 0.0,
+        }
+    }
+
+    // (strike, call bid, call ask, put bid, put ask)
+    type Row = (f64, f64, f64, f64, f64);
+
+    fn chain(expiries: &[(i64, &[Row])]) -> OptionChain {
+        let mut options = Vec::new();
+        for &(days, rows) in expiries {
+            for &(strike, call_bid, call_ask, put_bid, put_ask) in rows {
+                options.push(quote(days, strike, OptionType::Call, call_bid, call_ask));
+                options.push(quote(days, strike, OptionType::Put, put_bid, put_ask));
+            }
+        }
+        OptionChain::new(options).unwrap()
+    }
+
+    const NEAR: &[Row] = &[
+        (1890.0, 71.5, 72.5, 0.1, 0.2),
+        (1900.0, 62.5, 63.5, 0.0, 0.1),
+        (1910.0, 53.5, 54.5, 0.0, 0.1),
+        (1920.0, 43.5, 44.5, 0.9, 1.1),
+        (1930.0, 34.5, 35.5, 0.0, 0.2),
+        (1940.0, 25.5, 26.5, 2.9, 3.1),
+        (1950.0, 17.5, 18.5, 5.8, 6.2),
+        (1960.0, 11.8, 12.2, 9.8, 10.2),
+        (1970.0, 6.8, 7.2, 15.0, 16.0),
+        (1980.0, 3.9, 4.1, 21.5, 22.5),
+        (1990.0, 0.0, 0.1, 29.5, 30.5),
+        (2000.0, 1.4, 1.6, 38.5, 39.5),
+        (2010.0, 0.0, 0.05, 47.5, 48.5),
+        (2020.0, 0.0, 0.05, 57.5, 58.5),
+        (2030.0, 0.1, 0.2, 67.5, 68.5),
+    ];
+
+    const NEXT: &[Row] = &[
+        (1930.0, 40.5, 41.5, 0.0, 0.1),
+        (1940.0, 30.5, 31.5, 3.9, 4.1),
+        (1950.0, 23.5, 24.5, 6.8, 7.2),
+        (1960.0, 17.8, 18.2, 10.8, 11.2),
+        (1970.0, 14.9, 15.1, 16.9, 17.1),
+        (1980.0, 9.8, 10.2, 22.8, 23.2),
+        (1990.0, 5.8, 6.2, 29.5, 30.5),
+        (2000.0, 2.9, 3.1, 37.5, 38.5),
+    ];
+
+    // A scaled-down version of the CBOE white paper's worked example, with
+    // the expected values worked through by hand from the VIX rules.
+    #[test]
+    fn follows_the_cboe_worked_example() {
+        let rate = 0.01;
+        let chain = chain(&[(25, NEAR), (32, NEXT)]);
+
+        // Near term: call and put mids are closest at 1960 (12 vs 10), so
+        // F = 1960 + e^{RT} * 2 and K0 = 1960. Walking down the puts, the
+        // single zero bid at 1930 is skipped but 1910 and 1900 end the walk,
+        // so the 1890 put is excluded even though it has a bid. The calls
+        // likewise stop at 2010 and 2020. The strikes used are 1920, 1940,
+        // 1950, 1960 (put/call average 11), 1970, 1980 and 2000, with the
+        // edge strikes weighted by the distance to their one neighbour
+        // (20 each) and the rest by half the gap between neighbours.
+        let near = expiry_variance(chain.expiries().next().unwrap(), rate).unwrap();
+        assert_eq!(near.expiration, date(25));
+        assert!((near.forward - 1962.001370332252).abs() < 1e-9);
+        assert_eq!(near.k0, 1960.0);
+        assert_eq!(near.strikes_used, 7);
+        assert!((near.variance - 0.0029838191343490457).abs() < 1e-12);
+
+        // Next term: mids are closest at 1970 (15 vs 17), so the forward is
+        // below the parity strike and K0 is the strike under it, 1960, whose
+        // price is the 18/11 average. The 1940 put and 2000 call are edges.
+        let next = expiry_variance(chain.expiries().nth(1).unwrap(), rate).unwrap();
+        assert!((next.forward - 1967.9982458064933).abs() < 1e-9);
+        assert_eq!(next.k0, 1960.0);
+        assert_eq!(next.strikes_used, 7);
+        assert!((next.variance - 0.0033169333931064235).abs() < 1e-12);
+
+        // 30 days is 5/7 of the way from the 25-day to the 32-day expiry:
+        // sigma^2 = (T1 s1^2 (N2 - 30)/(N2 - N1) + T2 s2^2 (30 - N1)/(N2 - N1)) * 365/30.
+        let vol = model_free_vol(&chain, rate, 30).unwrap();
+        assert_eq!(vol.near.expiration, date(25));
+        assert_eq!(vol.next.expiration, date(32));
+        assert!((vol.target_years - 30.0 / 365.0).abs() < 1e-15);
+        assert!((vol.variance - 0.003237620474354667).abs() < 1e-12);
+        assert!((vol.vol - 0.056900092041706464).abs() < 1e-12);
+
+        // Targets outside the two expiries are not extrapolated.
+        assert!(model_free_vol(&chain, rate, 20).is_none());
+        assert!(model_free_vol(&chain, rate, 40).is_none());
+    }
+
+    #[test]
+    fn recovers_the_vol_of_a_flat_black_scholes_chain() {
+        let (spot, rate, volatility) = (100.0, 0.01, 0.2);
+        let mut options = Vec::new();
+        for days in [20, 40] {
+            for strike in (60..=150).map(f64::from) {
+                for contract_type in [OptionType::Call, OptionType::Put] {
+                    let price = BlackScholes.price(&PricingInputs {
+                        option_type: contract_type,
+                        spot,
+                        strike,
+                        time_to_expiry: days as f64 / 365.0,
+                        rate,
+                        dividend_yield: 0.0,
+                        volatility,
+                    });
+                    // Quotes under a cent get a zero bid, which truncates the
+                    // tails as a real chain would.
+                    let (bid, ask) = if price >= 0.01 {
+                        (0.98 * price, 1.02 * price)
+                    } else {
+                        (0.0, 0.01)
+                    };
+                    options.push(quote(days, strike, contract_type, bid, ask));
+                }
+            }
+        }
+        let chain = OptionChain::new(options).unwrap();
+
+        for expiry in expiry_variances(&chain, rate) {
+            assert!((expiry.forward - spot * (rate * expiry.time_to_expiry).exp()).abs() < 1e-9);
+            assert!((expiry.variance.sqrt() - volatility).abs() < 0.003);
+        }
+        let vol = model_free_vol(&chain, rate, 30).unwrap();
+        assert!((vol.vol - volatility).abs() < 0.003, "{}", vol.vol);
+    }
+}
@@ -0,0 +1,166 @@
+// Derivative-free minimisation for the model calibrations. Nelder-Mead with the
+// standard coefficients (reflection 1, expansion 2, contraction 1/2, shrink
+// 1/2). Constraints are left to the objective, e.g. by mapping parameters or
+// returning a large value outside the feasible region.
+
+#[derive(Debug, Clone)]
+pub struct Minimum {
+    pub point: Vec<f64>,
+    pub value: f64,
+    pub iterations: usize,
+    // False when `max_iterations` was hit before the simplex values agreed
+    // within the tolerance.
+    pub converged: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct NelderMead {
+    // Initial simplex edge per coordinate, relative to the scale of the problem.
+    pub initial_step: f64,
+    // Stop when the spread of function values over the simplex is below this.
+    pub tolerance: f64,
+    pub max_iterations: usize,
+}
+
+impl Default for NelderMead {
+    fn default() -> Self {
+        NelderMead {
+            initial_step: 0.1,
+            tolerance: 1e-10,
+            max_iterations: 2000,
+        }
+    }
+}
+
+impl NelderMead {
+    pub fn minimize<F: Fn(&[f64]) -> f64>(&self, objective: F, start: &[f64]) -> Minimum {
+        let n = start.len();
+        let evaluate = |point: &[f64]| {
+            let value = objective(point);
+            if value.is_nan() {
+                f64::INFINITY
+            } else {
+                value
+            }
+        };
+
+        let mut simplex: Vec<Vec<f64>> = vec![start.to_vec()];
+        for i in 0..n {
+            let mut vertex = start.to_vec();
+            vertex[i] += if vertex[i] == 0.0 {
+                self.initial_step
+            } else {
+                self.initial_step * vertex[i].abs()
+            };
+            simplex.push(vertex);
+        }
+        let mut values: Vec<f64> = simplex.iter().map(|v| evaluate(v)).collect();
+
+        let mut iterations = 0;
+        let mut converged = false;
+        while iterations < self.max_iterations {
+            iterations += 1;
+
+            let mut order: Vec<usize> = (0..=n).collect();
+            order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+            simplex = order.iter().map(|&i| simplex[i].clone()).collect();
+            values = order.iter().map(|&i| values[i]).collect();
+
+            if (values[n] - values[0]).abs() <= self.tolerance * (1.0 + values[0].abs()) {
+                converged = true;
+                break;
+            }
+
+            let centroid: Vec<f64> = (0..n)
+                .map(|j| simplex[..n].iter().map(|v| v[j]).sum::<f64>() / n as f64)
+                .collect();
+            let along = |t: f64| -> Vec<f64> {
+                centroid
+                    .iter()
+                    .zip(&simplex[n])
+                    .map(|(c, w)| c + t * (c - w))
+                    .collect()
+            };
+
+            let reflected = along(1.0);
+            let reflected_value = evaluate(&reflected);
+            if reflected_value < values[0] {
+                let expanded = along(2.0);
+                let expanded_value = evaluate(&expanded);
+                if expanded_value < reflected_value {
+                    simplex[n] = expanded;
+                    values[n] = expanded_value;
+                } else {
+                    simplex[n] = reflected;
+                    values[n] = reflected_value;
+                }
+                continue;
+            }
+            if reflected_value < values[n - 1] {
+                simplex[n] = reflected;
+                values[n] = reflected_value;
+                continue;
+            }
+
+            let (contracted, contracted_value) = if reflected_value < values[n] {
+                let outside = along(0.5);
+                let value = evaluate(&outside);
+                (outside, value)
+            } else {
+                let inside = along(-0.5);
+                let value = evaluate(&inside);
+                (inside, value)
+            };
+            if contracted_value < values[n].min(reflected_value) {
+                simplex[n] = contracted;
+                values[n] = contracted_value;
+                continue;
+            }
+
+            for i in 1..=n {
+                simplex[i] = simplex[0]
+                    .iter()
+                    .zip(&simplex[i])
+                    .map(|(best, v)| best + 0.5 * (v - best))
+                    .collect();
+                values[i] = evaluate(&simplex[i]);
+            }
+        }
+
+        let best = (0..=n)
+            .min_by(|&a, &b| values[a].total_cmp(&values[b]))
+            .unwrap_or(0);
+        Minimum {
+            point: simplex[best].clone(),
+            value: values[best],
+            iterations,
+            converged,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimizes_the_rosenbrock_function() {
+        let rosenbrock = |x: &[f64]| (1.0 - x[0]).powi(2) + 100.0 * (x[1] - x[0] * x[0]).powi(2);
+        let minimum = NelderMead::default().minimize(rosenbrock, &[-1.2, 1.0]);
+        assert!(minimum.converged);
+        assert!((minimum.point[0] - 1.0).abs() < 1e-3);
+        assert!((minimum.point[1] - 1.0).abs() < 1e-3);
+        assert!(minimum.value < 1e-8);
+    }
+
+    #[test]
+    fn reports_hitting_the_iteration_limit() {
+        let optimizer = NelderMead {
+            max_iterations: 5,
+            ..NelderMead::default()
+        };
+        let minimum = optimizer.minimize(|x: &[f64]| x[0] * x[0] + x[1] * x[1], &[3.0, -2.0]);
+        assert!(!minimum.converged);
+        assert_eq!(minimum.iterations, 5);
+    }
+}
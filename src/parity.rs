@@ -0,0 +1,213 @@
+// Forwards implied by put-call parity, C - P = e^{-rT} (F - K), per expiry.
+// Each call/put pair at a strike gives F = K + e^{rT} (C - P); we take the
+// median over the pairs closest to the money, where early exercise premia
+// of American options and stale wings distort parity least. The implied
+// dividend yield q = r - ln(F / S) / T then carries dividends and borrow
+// cost together.
+
+use crate::api::OptionsData;
+use crate::black_scholes::{inputs_from_option, PricingInputs};
+use crate::chain::{ExpirySlice, OptionChain};
+use chrono::NaiveDate;
+
+// Call/put pairs nearest the money used per expiry.
+const MAX_PAIRS: usize = 6;
+
+#[derive(Debug, Clone)]
+pub struct ParityEstimate {
+    pub expiration: NaiveDate,
+    // Year fraction, ACT/365.
+    pub time_to_expiry: f64,
+    pub forward: f64,
+    // Continuous dividend plus borrow yield implied by the forward.
+    pub implied_dividend_yield: f64,
+    pub pairs_used: usize,
+    // Median absolute deviation of the per-strike forwards, a noise gauge.
+    pub dispersion: f64,
+}
+
+fn mid(option: &OptionsData) -> Option<f64> {
+    (option.bid > 0.0 && option.ask >= option.bid).then_some(0.5 * (option.bid + option.ask))
+}
+
+fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let middle = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        0.5 * (values[middle - 1] + values[middle])
+    } else {
+        values[middle]
+    })
+}
+
+// Parity forward for one expiry. None without at least one strike quoted
+// two-sided on both calls and puts.
+pub fn parity_estimate(slice: &ExpirySlice, spot: f64, rate: f64) -> Option<ParityEstimate> {
+    let t = slice.time_to_expiry;
+    if t <= 0.0 || spot <= 0.0 {
+        return None;
+    }
+
+    let mut pairs: Vec<(f64, f64)> = slice
+        .strikes()
+        .filter_map(|pair| {
+            let call = mid(pair.call.as_ref()?)?;
+            let put = mid(pair.put.as_ref()?)?;
+            Some((pair.strike, call - put))
+        })
+        .collect();
+    pairs.sort_by(|a, b| a.1.abs().total_cmp(&b.1.abs()));
+    pairs.truncate(MAX_PAIRS);
+
+    let growth = (rate * t).exp();
+    let mut forwards: Vec<f64> = pairs
+        .iter()
+        .map(|(strike, difference)| strike + growth * difference)
+        .filter(|forward| *forward > 0.0)
+        .collect();
+    let forward = median(&mut forwards)?;
+    let mut deviations: Vec<f64> = forwards.iter().map(|f| (f - forward).abs()).collect();
+    let dispersion = median(&mut deviations)?;
+
+    Some(ParityEstimate {
+        expiration: slice.expiration,
+        time_to_expiry: t,
+        forward,
+        implied_dividend_yield: rate - (forward / spot).ln() / t,
+        pairs_used: forwards.len(),
+        dispersion,
+    })
+}
+
+// Forwards for every expiry of one day's chain. Expiries without a parity
+// estimate fall back to the flat `fallback_dividend_yield` carry.
+#[derive(Debug, Clone)]
+pub struct ForwardCurve {
+    pub spot: f64,
+    pub rate: f64,
+    pub fallback_dividend_yield: f64,
+    pub estimates: Vec<ParityEstimate>,
+}
+
+impl ForwardCurve {
+    // Cost-of-carry forwards only, F = S e^{(r - q) T}.
+    pub fn flat(spot: f64, rate: f64, dividend_yield: f64) -> Self {
+        ForwardCurve {
+            spot,
+            rate,
+            fallback_dividend_yield: dividend_yield,
+            estimates: Vec::new(),
+        }
+    }
+
+    pub fn from_chain(
+        chain: &OptionChain,
+        spot: f64,
+        rate: f64,
+        fallback_dividend_yield: f64,
+    ) -> Self {
+        let estimates: Vec<ParityEstimate> = chain
+            .expiries()
+            .filter_map(|slice| parity_estimate(slice, spot, rate))
+            .collect();
+        ForwardCurve {
+            estimates,
+            ..ForwardCurve::flat(spot, rate, fallback_dividend_yield)
+        }
+    }
+
+    pub fn estimate(&self, expiration: NaiveDate) -> Option<&ParityEstimate> {
+        self.estimates
+            .iter()
+            .find(|estimate| estimate.expiration == expiration)
+    }
+
+    pub fn dividend_yield(&self, expiration: NaiveDate) -> f64 {
+        self.estimate(expiration)
+            .map_or(self.fallback_dividend_yield, |e| e.implied_dividend_yield)
+    }
+
+    pub fn forward(&self, expiration: NaiveDate, time_to_expiry: f64) -> f64 {
+        self.spot * ((self.rate - self.dividend_yield(expiration)) * time_to_expiry).exp()
+    }
+
+    // Pricing inputs for a contract at its vendor IV, with the dividend yield
+    // implied for its expiry, so the model forward matches the parity forward.
+    pub fn inputs_for(&self, option: &OptionsData) -> PricingInputs {
+        inputs_from_option(
+            option,
+            self.spot,
+            self.rate,
+            self.dividend_yield(option.expiration),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::OptionType;
+    use crate::black_scholes::{BlackScholes, OptionPricer};
+
+    const RATE: f64 = 0.04;
+    const DIVIDEND_YIELD: f64 = 0.02;
+
+    fn quote(expiration: NaiveDate, strike: f64, contract_type: OptionType) -> OptionsData {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let price = BlackScholes.price(&PricingInputs {
+            option_type: contract_type,
+            spot: 100.0,
+            strike,
+            time_to_expiry: (expiration - date).num_days() as f64 / 365.0,
+            rate: RATE,
+            dividend_yield: DIVIDEND_YIELD,
+            volatility: 0.2,
+        });
+        OptionsData {
+            symbol: "SPY".to_string(),
+            contract: format!("SPY{}{:?}{}", expiration, contract_type, strike),
+            contract_type,
+            expiration,
+            date,
+            strike,
+            last: price,
+            mark: price,
+            bid: price - 0.05,
+            ask: price + 0.05,
+            volume: 10.0,
+            open_interest: 100.0,
+            implied_volatility: 0.2,
+            delta: 0.5,
+            gamma: 0.01,
+            theta: -0.01,
+            vega: 0.1,
+            rho: 0.01,
+        }
+    }
+
+    #[test]
+    fn recovers_the_carry_from_european_prices() {
+        let quoted = NaiveDate::from_ymd_opt(2024, 4, 19).unwrap();
+        let calls_only = NaiveDate::from_ymd_opt(2024, 7, 19).unwrap();
+        let mut options = Vec::new();
+        for strike in [90.0, 95.0, 100.0, 105.0, 110.0] {
+            options.push(quote(quoted, strike, OptionType::Call));
+            options.push(quote(quoted, strike, OptionType::Put));
+            options.push(quote(calls_only, strike, OptionType::Call));
+        }
+        let chain = OptionChain::new(options).unwrap();
+        let curve = ForwardCurve::from_chain(&chain, 100.0, RATE, 0.013);
+
+        assert_eq!(curve.estimates.len(), 1);
+        let estimate = curve.estimate(quoted).unwrap();
+        let t = estimate.time_to_expiry;
+        let forward = 100.0 * ((RATE - DIVIDEND_YIELD) * t).exp();
+        assert!((estimate.forward - forward).abs() < 1e-6);
+        assert!((estimate.implied_dividend_yield - DIVIDEND_YIELD).abs() < 1e-6);
+        assert!(estimate.dispersion < 1e-6);
+        assert_eq!(curve.dividend_yield(calls_only), 0.013);
+    }
+}
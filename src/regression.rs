@@ -0,0 +1,587 @@
+// OLS regressions for forecast evaluation: Mincer-Zarnowitz regressions of
+// realized vol on a single forecast, and encompassing regressions of realized
+// vol on IV and HV together.
+
+use crate::hac::{long_run_covariance, LagSelection};
+use crate::loss::ForecastPair;
+use crate::stats::{chi_squared_sf, mean, two_sided_normal_p_value};
+use crate::time_series::TimeSeries;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CovarianceEstimator {
+    // Homoskedastic, independent errors.
+    Classical,
+    // Newey-West HAC, for overlapping forecast windows.
+    NeweyWest(LagSelection),
+}
+
+#[derive(Debug, Clone)]
+pub struct OlsResult {
+    // Intercept first, then one coefficient per regressor.
+    pub coefficients: Vec<f64>,
+    pub std_errors: Vec<f64>,
+    pub covariance: Vec<Vec<f64>>,
+    pub r_squared: f64,
+    pub adjusted_r_squared: f64,
+    pub residuals: Vec<f64>,
+    pub n: usize,
+    // HAC lags used for the covariance, 0 for classical standard errors.
+    pub lags: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct WaldTest {
+    pub statistic: f64,
+    pub df: usize,
+    pub p_value: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MincerZarnowitz {
+    pub ols: OlsResult,
+    // H0: alpha = 0 and beta = 1 jointly (forecast is unbiased).
+    pub unbiasedness: WaldTest,
+    // H0: alpha = 0.
+    pub alpha_zero: WaldTest,
+    // H0: beta = 1.
+    pub beta_one: WaldTest,
+}
+
+#[derive(Debug, Clone)]
+pub struct CorrelationTest {
+    pub correlation: f64,
+    pub std_error: f64,
+    // H0: correlation = 0, normal reference distribution.
+    pub p_value: f64,
+    pub n: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct EncompassingTest {
+    // realized = alpha + beta_iv * IV + beta_hv * HV.
+    pub ols: OlsResult,
+    // H0: beta_hv = 0, i.e. HV adds nothing beyond IV.
+    pub hv_adds_nothing: WaldTest,
+    // H0: beta_iv = 0, i.e. IV adds nothing beyond HV.
+    pub iv_adds_nothing: WaldTest,
+}
+
+// Invert a small square matrix with Gauss-Jordan elimination and partial pivoting.
+pub fn invert_matrix(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    if n == 0 || matrix.iter().any(|row| row.len() != n) {
+        return None;
+    }
+
+    let mut a: Vec<Vec<f64>> = matrix.to_vec();
+    let mut inverse: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+
+    for col in 0..n {
+        let pivot_row = (col..n).max_by(|&i, &j| {
+            a[i][col]
+                .abs()
+                .partial_cmp(&a[j][col].abs())
+                .unwrap_or(std::cmp::Ordering::Less)
+        })?;
+        if a[pivot_row][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot_row);
+        inverse.swap(col, pivot_row);
+
+        let pivot = a[col][col];
+        for j in 0..n {
+            a[col][j] /= pivot;
+            inverse[col][j] /= pivot;
+        }
+        for row in 0..n {
+            if row != col {
+                let factor = a[row][col];
+                if factor != 0.0 {
+                    for j in 0..n {
+                        a[row][j] -= factor * a[col][j];
+                        inverse[row][j] -= factor * inverse[col][j];
+                    }
+                }
+            }
+        }
+    }
+
+    Some(inverse)
+}
+
+// Design matrix rows [1, x_1, ..., x_k] for observation i.
+fn design_row(regressors: &[Vec<f64>], i: usize) -> Vec<f64> {
+    let mut row = Vec::with_capacity(regressors.len() + 1);
+    row.push(1.0);
+    for regressor in regressors {
+        row.push(regressor[i]);
+    }
+    row
+}
+
+impl OlsResult {
+    // Durbin-Watson statistic of the residuals, near 2 when they are
+    // uncorrelated and well below 2 when overlapping windows make them
+    // positively autocorrelated.
+    pub fn durbin_watson(&self) -> f64 {
+        let ssr: f64 = self.residuals.iter().map(|e| e * e).sum();
+        let differences: f64 = self
+            .residuals
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).powi(2))
+            .sum();
+        differences / ssr
+    }
+}
+
+// OLS of y on an intercept and the given regressors with classical standard
+// errors. Main always picks the covariance estimator, so this is only a
+// shorthand for ad-hoc regressions.
+#[allow(dead_code)]
+pub fn ols(y: &[f64], regressors: &[Vec<f64>]) -> Option<OlsResult> {
+    ols_with_covariance(y, regressors, CovarianceEstimator::Classical)
+}
+
+pub fn ols_with_covariance(
+    y: &[f64],
+    regressors: &[Vec<f64>],
+    estimator: CovarianceEstimator,
+) -> Option<OlsResult> {
+    let n = y.len();
+    let k = regressors.len() + 1;
+    if n <= k || regressors.iter().any(|x| x.len() != n) {
+        return None;
+    }
+
+    let mut xtx = vec![vec![0.0; k]; k];
+    let mut xty = vec![0.0; k];
+    for (i, &y_i) in y.iter().enumerate() {
+        let row = design_row(regressors, i);
+        for a in 0..k {
+            xty[a] += row[a] * y_i;
+            for b in 0..k {
+                xtx[a][b] += row[a] * row[b];
+            }
+        }
+    }
+
+    let xtx_inverse = invert_matrix(&xtx)?;
+    let coefficients: Vec<f64> = (0..k)
+        .map(|a| (0..k).map(|b| xtx_inverse[a][b] * xty[b]).sum())
+        .collect();
+
+    let residuals: Vec<f64> = (0..n)
+        .map(|i| {
+            let row = design_row(regressors, i);
+            let fitted: f64 = row.iter().zip(&coefficients).map(|(x, b)| x * b).sum();
+            y[i] - fitted
+        })
+        .collect();
+
+    let ssr: f64 = residuals.iter().map(|e| e * e).sum();
+    let y_mean = mean(y)?;
+    let sst: f64 = y.iter().map(|v| (v - y_mean).powi(2)).sum();
+    let r_squared = if sst > 0.0 { 1.0 - ssr / sst } else { 0.0 };
+    let adjusted_r_squared = 1.0 - (1.0 - r_squared) * (n as f64 - 1.0) / (n - k) as f64;
+
+    let (covariance, lags) = match estimator {
+        CovarianceEstimator::Classical => {
+            let sigma_squared = ssr / (n - k) as f64;
+            let covariance: Vec<Vec<f64>> = xtx_inverse
+                .iter()
+                .map(|row| row.iter().map(|v| v * sigma_squared).collect())
+                .collect();
+            (covariance, 0)
+        }
+        CovarianceEstimator::NeweyWest(lag_selection) => {
+            // Sandwich n * (X'X)^-1 S (X'X)^-1 with S the long-run covariance of
+            // x_t * e_t, scaled by n / (n - k) for small samples.
+            let lags = lag_selection.resolve(n);
+            let scores: Vec<Vec<f64>> = (0..n)
+                .map(|i| {
+                    design_row(regressors, i)
+                        .into_iter()
+                        .map(|x| x * residuals[i])
+                        .collect()
+                })
+                .collect();
+            let s = long_run_covariance(&scores, lags)?;
+            let scale = n as f64 * n as f64 / (n - k) as f64;
+            let covariance: Vec<Vec<f64>> = (0..k)
+                .map(|a| {
+                    (0..k)
+                        .map(|b| {
+                            let mut sum = 0.0;
+                            for i in 0..k {
+                                for j in 0..k {
+                                    sum += xtx_inverse[a][i] * s[i][j] * xtx_inverse[j][b];
+                                }
+                            }
+                            sum * scale
+                        })
+                        .collect()
+                })
+                .collect();
+            (covariance, lags)
+        }
+    };
+    let std_errors = (0..k).map(|a| covariance[a][a].max(0.0).sqrt()).collect();
+
+    Some(OlsResult {
+        coefficients,
+        std_errors,
+        covariance,
+        r_squared,
+        adjusted_r_squared,
+        residuals,
+        n,
+        lags,
+    })
+}
+
+// Wald test of the linear restrictions R * b = r, where each restriction row
+// has one weight per coefficient (intercept first).
+pub fn wald_test(
+    coefficients: &[f64],
+    covariance: &[Vec<f64>],
+    restrictions: &[Vec<f64>],
+    targets: &[f64],
+) -> Option<WaldTest> {
+    let q = restrictions.len();
+    let k = coefficients.len();
+    if q == 0 || targets.len() != q || restrictions.iter().any(|row| row.len() != k) {
+        return None;
+    }
+
+    let discrepancy: Vec<f64> = restrictions
+        .iter()
+        .zip(targets)
+        .map(|(row, target)| {
+            row.iter()
+                .zip(coefficients)
+                .map(|(w, b)| w * b)
+                .sum::<f64>()
+                - target
+        })
+        .collect();
+
+    // R V R'
+    let middle: Vec<Vec<f64>> = restrictions
+        .iter()
+        .map(|row_a| {
+            restrictions
+                .iter()
+                .map(|row_b| {
+                    (0..k)
+                        .map(|i| {
+                            (0..k)
+                                .map(|j| row_a[i] * covariance[i][j] * row_b[j])
+                                .sum::<f64>()
+                        })
+                        .sum()
+                })
+                .collect()
+        })
+        .collect();
+    let middle_inverse = invert_matrix(&middle)?;
+
+    let statistic: f64 = (0..q)
+        .map(|a| {
+            (0..q)
+                .map(|b| discrepancy[a] * middle_inverse[a][b] * discrepancy[b])
+                .sum::<f64>()
+        })
+        .sum();
+
+    Some(WaldTest {
+        statistic,
+        df: q,
+        p_value: chi_squared_sf(statistic, q),
+    })
+}
+
+pub fn mincer_zarnowitz_from_ols(ols: OlsResult) -> Option<MincerZarnowitz> {
+    let unbiasedness = wald_test(
+        &ols.coefficients,
+        &ols.covariance,
+        &[vec![1.0, 0.0], vec![0.0, 1.0]],
+        &[0.0, 1.0],
+    )?;
+    let alpha_zero = wald_test(
+        &ols.coefficients,
+        &ols.covariance,
+        &[vec![1.0, 0.0]],
+        &[0.0],
+    )?;
+    let beta_one = wald_test(
+        &ols.coefficients,
+        &ols.covariance,
+        &[vec![0.0, 1.0]],
+        &[1.0],
+    )?;
+
+    Some(MincerZarnowitz {
+        ols,
+        unbiasedness,
+        alpha_zero,
+        beta_one,
+    })
+}
+
+// realized = alpha + beta * forecast + e.
+pub fn mincer_zarnowitz(
+    pairs: &[ForecastPair],
+    estimator: CovarianceEstimator,
+) -> Option<MincerZarnowitz> {
+    let y: Vec<f64> = pairs.iter().map(|p| p.realized).collect();
+    let x: Vec<f64> = pairs.iter().map(|p| p.forecast).collect();
+    mincer_zarnowitz_from_ols(ols_with_covariance(&y, &[x], estimator)?)
+}
+
+// Inner join of two forecast series on date into (realized, forecast_a, forecast_b).
+// The realized value is taken from the first series.
+pub fn join_forecast_pairs(
+    pairs_a: &[ForecastPair],
+    pairs_b: &[ForecastPair],
+) -> TimeSeries<(f64, f64, f64)> {
+    let series_a: TimeSeries<&ForecastPair> = pairs_a.iter().map(|p| (p.date, p)).collect();
+    let series_b: TimeSeries<f64> = pairs_b.iter().map(|p| (p.date, p.forecast)).collect();
+    series_a
+        .inner_join(&series_b)
+        .map(|(a, forecast_b)| (a.realized, a.forecast, *forecast_b))
+}
+
+pub fn encompassing_from_ols(ols: OlsResult) -> Option<EncompassingTest> {
+    let iv_adds_nothing = wald_test(
+        &ols.coefficients,
+        &ols.covariance,
+        &[vec![0.0, 1.0, 0.0]],
+        &[0.0],
+    )?;
+    let hv_adds_nothing = wald_test(
+        &ols.coefficients,
+        &ols.covariance,
+        &[vec![0.0, 0.0, 1.0]],
+        &[0.0],
+    )?;
+
+    Some(EncompassingTest {
+        ols,
+        hv_adds_nothing,
+        iv_adds_nothing,
+    })
+}
+
+// realized = alpha + beta_iv * IV + beta_hv * HV + e, on dates present in both series.
+pub fn encompassing_regression(
+    iv_pairs: &[ForecastPair],
+    hv_pairs: &[ForecastPair],
+    estimator: CovarianceEstimator,
+) -> Option<EncompassingTest> {
+    let joined = join_forecast_pairs(iv_pairs, hv_pairs);
+    let y: Vec<f64> = joined.values().map(|(realized, _, _)| *realized).collect();
+    let iv: Vec<f64> = joined.values().map(|(_, iv, _)| *iv).collect();
+    let hv: Vec<f64> = joined.values().map(|(_, _, hv)| *hv).collect();
+    encompassing_from_ols(ols_with_covariance(&y, &[iv, hv], estimator)?)
+}
+
+// Pearson correlation with a standard error from the regression of
+// standardized y on standardized x, whose slope equals the correlation. With
+// NeweyWest this gives autocorrelation-robust inference on the correlation.
+pub fn correlation_test(
+    x: &[f64],
+    y: &[f64],
+    estimator: CovarianceEstimator,
+) -> Option<CorrelationTest> {
+    let n = x.len();
+    if n != y.len() || n < 3 {
+        return None;
+    }
+    let standardize = |values: &[f64]| -> Option<Vec<f64>> {
+        let mean = mean(values)?;
+        let sd = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n as f64).sqrt();
+        if sd == 0.0 {
+            return None;
+        }
+        Some(values.iter().map(|v| (v - mean) / sd).collect())
+    };
+    let x_std = standardize(x)?;
+    let y_std = standardize(y)?;
+
+    let fit = ols_with_covariance(&y_std, &[x_std], estimator)?;
+    let correlation = fit.coefficients[1];
+    let std_error = fit.std_errors[1];
+    Some(CorrelationTest {
+        correlation,
+        std_error,
+        p_value: two_sided_normal_p_value(correlation / std_error),
+        n,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate};
+
+    // x = 1..5, y = [1, 3, 2, 5, 4]: xbar = ybar = 3, Sxx = 10, Sxy = 8, so
+    // b = 0.8, a = 0.6 and the residuals are [-0.4, 0.8, -1.0, 1.2, -0.6].
+    const X: [f64; 5] = [1.0, 2.0, 3.0, 4.0, 5.0];
+    const Y: [f64; 5] = [1.0, 3.0, 2.0, 5.0, 4.0];
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-10,
+            "{} vs {}",
+            actual,
+            expected
+        );
+    }
+
+    fn pairs() -> Vec<ForecastPair> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        X.iter()
+            .zip(Y)
+            .enumerate()
+            .map(|(i, (&forecast, realized))| ForecastPair {
+                date: start + Duration::days(i as i64),
+                forecast,
+                realized,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ols_matches_closed_form() {
+        let fit = ols(&Y, &[X.to_vec()]).unwrap();
+        assert_close(fit.coefficients[0], 0.6);
+        assert_close(fit.coefficients[1], 0.8);
+        for (residual, expected) in fit.residuals.iter().zip([-0.4, 0.8, -1.0, 1.2, -0.6]) {
+            assert_close(*residual, expected);
+        }
+        // s^2 = SSR / (n - 2) = 3.6 / 3, se(b) = sqrt(s^2 / Sxx) and
+        // se(a) = sqrt(s^2 * (1/n + xbar^2 / Sxx)).
+        assert_close(fit.std_errors[1], 0.12f64.sqrt());
+        assert_close(fit.std_errors[0], 1.32f64.sqrt());
+        assert_close(fit.covariance[0][1], -0.36);
+        assert_close(fit.r_squared, 0.64);
+        assert_close(fit.adjusted_r_squared, 0.52);
+        assert_eq!((fit.n, fit.lags), (5, 0));
+        // Squared residual differences sum to 12.76 against SSR 3.6.
+        assert_close(fit.durbin_watson(), 12.76 / 3.6);
+    }
+
+    #[test]
+    fn wald_statistics_match_hand_computation() {
+        let mz = mincer_zarnowitz(&pairs(), CovarianceEstimator::Classical).unwrap();
+        // (b - 1)^2 / var(b) = 0.04 / 0.12 and a^2 / var(a) = 0.36 / 1.32.
+        assert_close(mz.beta_one.statistic, 1.0 / 3.0);
+        assert_close(mz.alpha_zero.statistic, 0.36 / 1.32);
+        // d' V^-1 d with d = (0.6, -0.2) and det V = 0.0288.
+        assert_close(mz.unbiasedness.statistic, 0.0096 / 0.0288);
+        assert_eq!(mz.unbiasedness.df, 2);
+        assert_eq!(mz.beta_one.df, 1);
+        // Exponential survival function at two degrees of freedom.
+        assert_close(mz.unbiasedness.p_value, (-0.0096 / 0.0288 / 2.0f64).exp());
+    }
+
+    #[test]
+    fn newey_west_matches_hand_computed_sandwich() {
+        // The slope row of (X'X)^-1 X' e picks out u_t = (x_t - xbar) e_t / Sxx,
+        // so var(b) = n / (n - k) * (sum u^2 + 2 w_1 sum u_t u_{t-1}) / Sxx^2
+        // with u = [0.8, -0.8, 0, 1.2, -1.2] (before the 1 / Sxx).
+        let white = ols_with_covariance(
+            &Y,
+            &[X.to_vec()],
+            CovarianceEstimator::NeweyWest(LagSelection::Fixed(0)),
+        )
+        .unwrap();
+        assert_close(white.std_errors[1].powi(2), 5.0 / 3.0 * 4.16 / 100.0);
+
+        let newey_west = ols_with_covariance(
+            &Y,
+            &[X.to_vec()],
+            CovarianceEstimator::NeweyWest(LagSelection::Fixed(1)),
+        )
+        .unwrap();
+        assert_eq!(newey_west.lags, 1);
+        // Bartlett weight 1/2 on the lag-1 cross products, which sum to -2.08.
+        assert_close(
+            newey_west.std_errors[1].powi(2),
+            5.0 / 3.0 * (4.16 - 2.08) / 100.0,
+        );
+        // Point estimates do not depend on the covariance estimator.
+        assert_close(newey_west.coefficients[1], 0.8);
+    }
+
+    #[test]
+    fn two_regressors_recover_exact_linear_relation() {
+        let iv = vec![0.1, 0.25, 0.2, 0.3, 0.15, 0.22];
+        let hv = vec![0.2, 0.1, 0.25, 0.18, 0.3, 0.12];
+        let realized: Vec<f64> = iv
+            .iter()
+            .zip(&hv)
+            .map(|(iv, hv)| 0.01 + 0.5 * iv + 0.3 * hv)
+            .collect();
+        let fit = ols(&realized, &[iv, hv]).unwrap();
+        assert_close(fit.coefficients[0], 0.01);
+        assert_close(fit.coefficients[1], 0.5);
+        assert_close(fit.coefficients[2], 0.3);
+        assert_close(fit.r_squared, 1.0);
+    }
+
+    #[test]
+    fn encompassing_joins_on_date_and_tests_each_slope() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let iv = [0.1, 0.25, 0.2, 0.3, 0.15, 0.22];
+        let hv = [0.2, 0.1, 0.25, 0.18, 0.3, 0.12];
+        let realized = [0.13, 0.16, 0.19, 0.22, 0.18, 0.14];
+        let iv_pairs: Vec<ForecastPair> = (0..6)
+            .map(|i| ForecastPair {
+                date: start + Duration::days(i as i64),
+                forecast: iv[i],
+                realized: realized[i],
+            })
+            .collect();
+        // HV has one extra date that IV lacks, which the join must drop.
+        let mut hv_pairs: Vec<ForecastPair> = (0..6)
+            .map(|i| ForecastPair {
+                date: start + Duration::days(i as i64),
+                forecast: hv[i],
+                realized: realized[i],
+            })
+            .collect();
+        hv_pairs.push(ForecastPair {
+            date: start + Duration::days(10),
+            forecast: 0.5,
+            realized: 0.9,
+        });
+
+        let test =
+            encompassing_regression(&iv_pairs, &hv_pairs, CovarianceEstimator::Classical).unwrap();
+        let direct = ols(&realized, &[iv.to_vec(), hv.to_vec()]).unwrap();
+        assert_eq!(test.ols.n, 6);
+        for a in 0..3 {
+            assert_close(test.ols.coefficients[a], direct.coefficients[a]);
+        }
+        // A single restriction is the squared t-statistic.
+        assert_close(
+            test.hv_adds_nothing.statistic,
+            (direct.coefficients[2] / direct.std_errors[2]).powi(2),
+        );
+        assert_close(
+            test.iv_adds_nothing.statistic,
+            (direct.coefficients[1] / direct.std_errors[1]).powi(2),
+        );
+    }
+
+    #[test]
+    fn rejects_too_few_observations() {
+        assert!(ols(&[1.0, 2.0], &[vec![1.0, 2.0]]).is_none());
+        // Regressor length must match y.
+        assert!(ols(&[1.0, 2.0, 3.0], &[vec![1.0, 2.0]]).is_none());
+    }
+}
@@ -0,0 +1,443 @@
+// Policies for turning one day's chain into the single IV forecast that gets
+// scored. Each selector says why it rejected a date, so runs can be compared
+// and tuned instead of silently dropping dates.
+
+use crate::api::{OptionType, OptionsData};
+use crate::black_scholes::{BlackScholes, OptionPricer};
+use crate::chain::{ExpirySlice, OptionChain};
+use crate::constant_maturity::constant_maturity_atm_iv;
+use crate::parity::ForwardCurve;
+use chrono::{Duration, NaiveDate};
+use std::fmt;
+
+pub struct SelectionContext<'a> {
+    pub chain: &'a OptionChain,
+    pub forwards: &'a ForwardCurve,
+    pub symbol: &'a str,
+    pub date: NaiveDate,
+    // Calendar days to the target expiry.
+    pub target_days: usize,
+    // Last date with price data. Expiries after it cannot be scored.
+    pub last_price_date: NaiveDate,
+    // Date of the close the forecast is scored against, the forecast window
+    // in trading days after `date`. None when the price data ends first.
+    pub horizon_end: Option<NaiveDate>,
+}
+
+impl SelectionContext<'_> {
+    fn target_date(&self) -> Option<NaiveDate> {
+        self.date
+            .checked_add_signed(Duration::days(self.target_days as i64))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectionError {
+    EmptyChain,
+    NoExpiry {
+        target: NaiveDate,
+    },
+    ExpiryAfterPriceData {
+        expiration: NaiveDate,
+        last_price_date: NaiveDate,
+    },
+    NoStrike {
+        expiration: NaiveDate,
+        option_type: OptionType,
+    },
+    NoCallPutPair {
+        expiration: NaiveDate,
+    },
+    NoDeltaMatch {
+        target: f64,
+        closest: Option<f64>,
+    },
+    InterpolationFailed {
+        target_days: usize,
+    },
+    // The scoring window runs past the last price date.
+    HorizonAfterPriceData {
+        last_price_date: NaiveDate,
+    },
+    // The target tenor is before the first or after the last listed expiry,
+    // so its IV would be the nearest expiry's held flat.
+    TargetOutsideExpiries {
+        target_days: usize,
+        nearest: NaiveDate,
+    },
+}
+
+impl fmt::Display for SelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectionError::EmptyChain => write!(f, "empty option chain"),
+            SelectionError::NoExpiry { target } => {
+                write!(f, "no expiration near the target {}", target)
+            }
+            SelectionError::ExpiryAfterPriceData {
+                expiration,
+                last_price_date,
+            } => write!(
+                f,
+                "expiration {} is after the last price date {}",
+                expiration, last_price_date
+            ),
+            SelectionError::NoStrike {
+                expiration,
+                option_type,
+            } => write!(
+                f,
+                "no {:?} strikes with an IV at {}",
+                option_type, expiration
+            ),
+            SelectionError::NoCallPutPair { expiration } => write!(
+                f,
+                "no strike at {} with both a call and a put IV",
+                expiration
+            ),
+            SelectionError::NoDeltaMatch { target, closest } => match closest {
+                Some(closest) => write!(
+                    f,
+                    "closest delta {:.3} is too far from the target {:.3}",
+                    closest, target
+                ),
+                None => write!(f, "no contract with a delta near {:.3}", target),
+            },
+            SelectionError::InterpolationFailed { target_days } => {
+                write!(f, "could not interpolate a {}-day ATM IV", target_days)
+            }
+            SelectionError::HorizonAfterPriceData { last_price_date } => write!(
+                f,
+                "forecast window ends after the last price date {}",
+                last_price_date
+            ),
+            SelectionError::TargetOutsideExpiries {
+                target_days,
+                nearest,
+            } => write!(
+                f,
+                "no expiries on both sides of {} days, nearest is {}",
+                target_days, nearest
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SelectionError {}
+
+pub trait OptionSelector {
+    fn name(&self) -> &'static str;
+    // The contract (possibly synthetic) whose IV is the forecast for this date.
+    fn select(&self, context: &SelectionContext) -> Result<OptionsData, SelectionError>;
+}
+
+// The listed expiry closest to date + target days.
+fn nearest_expiry<'a>(context: &SelectionContext<'a>) -> Result<&'a ExpirySlice, SelectionError> {
+    if context.chain.is_empty() {
+        return Err(SelectionError::EmptyChain);
+    }
+    let target = context.target_date().ok_or(SelectionError::EmptyChain)?;
+    let slice = context
+        .chain
+        .nearest_expiry(target)
+        .ok_or(SelectionError::NoExpiry { target })?;
+    if slice.expiration > context.last_price_date {
+        return Err(SelectionError::ExpiryAfterPriceData {
+            expiration: slice.expiration,
+            last_price_date: context.last_price_date,
+        });
+    }
+    Ok(slice)
+}
+
+fn forward_for(context: &SelectionContext, slice: &ExpirySlice) -> f64 {
+    context
+        .forwards
+        .forward(slice.expiration, slice.time_to_expiry)
+}
+
+// Nearest expiry, strike nearest the forward, of one option type. This is the
+// original selection, with the call/put choice made explicit.
+#[derive(Debug, Clone, Copy)]
+pub struct NearestAtm {
+    pub option_type: OptionType,
+}
+
+impl OptionSelector for NearestAtm {
+    fn name(&self) -> &'static str {
+        "nearest ATM"
+    }
+
+    fn select(&self, context: &SelectionContext) -> Result<OptionsData, SelectionError> {
+        let slice = nearest_expiry(context)?;
+        slice
+            .nearest_contract(forward_for(context, slice), self.option_type, |opt| {
+                opt.implied_volatility > 0.0
+            })
+            .cloned()
+            .ok_or(SelectionError::NoStrike {
+                expiration: slice.expiration,
+                option_type: self.option_type,
+            })
+    }
+}
+
+// Nearest expiry, contract whose Black-Scholes delta at its own IV is closest
+// to `delta` (negative for puts). Rejected if the best match is more than
+// `tolerance` away.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetDelta {
+    pub option_type: OptionType,
+    pub delta: f64,
+    pub tolerance: f64,
+}
+
+impl OptionSelector for TargetDelta {
+    fn name(&self) -> &'static str {
+        "target delta"
+    }
+
+    fn select(&self, context: &SelectionContext) -> Result<OptionsData, SelectionError> {
+        let best = nearest_expiry(context)?
+            .contracts(self.option_type)
+            .filter(|opt| opt.implied_volatility > 0.0)
+            .map(|opt| {
+                let inputs = context.forwards.inputs_for(opt);
+                (opt, BlackScholes.greeks(&inputs).delta)
+            })
+            .min_by(|a, b| {
+                (a.1 - self.delta)
+                    .abs()
+                    .total_cmp(&(b.1 - self.delta).abs())
+            });
+        match best {
+            Some((option, delta)) if (delta - self.delta).abs() <= self.tolerance => {
+                Ok(option.clone())
+            }
+            best => Err(SelectionError::NoDeltaMatch {
+                target: self.delta,
+                closest: best.map(|(_, delta)| delta),
+            }),
+        }
+    }
+}
+
+// Nearest expiry, strike nearest the forward among those with both a call and
+// a put IV, forecasting the average of the two. Returns the call with its IV
+// replaced by the average.
+#[derive(Debug, Clone, Copy)]
+pub struct AtmCallPutAverage;
+
+impl OptionSelector for AtmCallPutAverage {
+    fn name(&self) -> &'static str {
+        "ATM call/put average"
+    }
+
+    fn select(&self, context: &SelectionContext) -> Result<OptionsData, SelectionError> {
+        let slice = nearest_expiry(context)?;
+        let with_iv = |option: &Option<OptionsData>| {
+            option
+                .as_ref()
+                .filter(|opt| opt.implied_volatility > 0.0)
+                .is_some()
+        };
+        let pair = slice
+            .nearest_strike_where(forward_for(context, slice), |pair| {
+                with_iv(&pair.call) && with_iv(&pair.put)
+            })
+            .ok_or(SelectionError::NoCallPutPair {
+                expiration: slice.expiration,
+            })?;
+        let (Some(call), Some(put)) = (&pair.call, &pair.put) else {
+            return Err(SelectionError::NoCallPutPair {
+                expiration: slice.expiration,
+            });
+        };
+
+        let mut record = call.clone();
+        record.contract = format!("{}+{}", call.contract, put.contract);
+        record.implied_volatility = 0.5 * (call.implied_volatility + put.implied_volatility);
+        Ok(record)
+    }
+}
+
+// ATM IV interpolated in total variance to exactly the end of the scoring
+// window, as a synthetic contract. The tenor is the calendar days to
+// `horizon_end`, so the forecast covers the same trading days it is scored
+// on. Dates whose listed expiries do not straddle it are rejected rather than
+// extrapolated.
+#[derive(Debug, Clone, Copy)]
+pub struct ConstantMaturityAtm;
+
+impl OptionSelector for ConstantMaturityAtm {
+    fn name(&self) -> &'static str {
+        "constant-maturity ATM"
+    }
+
+    fn select(&self, context: &SelectionContext) -> Result<OptionsData, SelectionError> {
+        if context.chain.is_empty() {
+            return Err(SelectionError::EmptyChain);
+        }
+        let horizon_end = context
+            .horizon_end
+            .ok_or(SelectionError::HorizonAfterPriceData {
+                last_price_date: context.last_price_date,
+            })?;
+        let target_days = (horizon_end - context.date).num_days().max(0) as usize;
+        let failed = SelectionError::InterpolationFailed { target_days };
+        let cm = constant_maturity_atm_iv(context.chain, context.forwards, target_days)
+            .ok_or(failed.clone())?;
+        if cm.extrapolated {
+            return Err(SelectionError::TargetOutsideExpiries {
+                target_days,
+                nearest: cm.lower_expiration,
+            });
+        }
+        cm.as_options_data(context.symbol, context.date)
+            .ok_or(failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(days: i64) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, 2).unwrap() + Duration::days(days)
+    }
+
+    fn chain(expiry_days: &[i64]) -> OptionChain {
+        let mut options = Vec::new();
+        for &days in expiry_days {
+            for strike in [95.0, 100.0, 105.0] {
+                for contract_type in [OptionType::Call, OptionType::Put] {
+                    options.push(OptionsData {
+                        symbol: "SPY".to_string(),
+                        contract: format!("SPY{}{:?}{}", days, contract_type, strike),
+                        contract_type,
+                        expiration: date(days),
+                        date: date(0),
+                        strike,
+                        last: 1.0,
+                        mark: 1.0,
+                        bid: 0.9,
+                        ask: 1.1,
+                        volume: 10.0,
+                        open_interest: 100.0,
+                        implied_volatility: 0.2,
+                        delta: 0.5,
+                        gamma: 0.01,
+                        theta: -0.01,
+                        vega: 0.1,
+                        rho: 0.01,
+                    });
+                }
+            }
+        }
+        OptionChain::new(options).unwrap()
+    }
+
+    fn select(chain: &OptionChain) -> Result<OptionsData, SelectionError> {
+        let forwards = ForwardCurve::flat(100.0, 0.0, 0.0);
+        ConstantMaturityAtm.select(&SelectionContext {
+            chain,
+            forwards: &forwards,
+            symbol: "SPY",
+            date: date(0),
+            target_days: 30,
+            last_price_date: date(365),
+            horizon_end: Some(date(30)),
+        })
+    }
+
+    #[test]
+    fn constant_maturity_interpolates_between_expiries() {
+        let selected = select(&chain(&[16, 44])).unwrap();
+        assert_eq!(selected.expiration, date(30));
+        assert!((selected.implied_volatility - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn constant_maturity_rejects_extrapolated_targets() {
+        assert_eq!(
+            select(&chain(&[9, 16])).unwrap_err(),
+            SelectionError::TargetOutsideExpiries {
+                target_days: 30,
+                nearest: date(16),
+            }
+        );
+        assert!(matches!(
+            select(&chain(&[44, 72])),
+            Err(SelectionError::TargetOutsideExpiries { .. })
+        ));
+    }
+
+    #[test]
+    fn constant_maturity_targets_the_scoring_horizon() {
+        let forwards = ForwardCurve::flat(100.0, 0.0, 0.0);
+        let chain = chain(&[16, 44]);
+        let context = |horizon_end| SelectionContext {
+            chain: &chain,
+            forwards: &forwards,
+            symbol: "SPY",
+            date: date(0),
+            target_days: 30,
+            last_price_date: date(60),
+            horizon_end,
+        };
+        // 30 trading days from a Tuesday end 42 calendar days later.
+        let selected = ConstantMaturityAtm
+            .select(&context(Some(date(42))))
+            .unwrap();
+        assert_eq!(selected.expiration, date(42));
+        assert_eq!(
+            ConstantMaturityAtm.select(&context(None)).unwrap_err(),
+            SelectionError::HorizonAfterPriceData {
+                last_price_date: date(60),
+            }
+        );
+    }
+
+    #[test]
+    fn nearest_selectors_use_the_expiry_nearest_the_target() {
+        let forwards = ForwardCurve::flat(101.0, 0.0, 0.0);
+        let chain = chain(&[20, 44]);
+        let context = SelectionContext {
+            chain: &chain,
+            forwards: &forwards,
+            symbol: "SPY",
+            date: date(0),
+            target_days: 30,
+            last_price_date: date(365),
+            horizon_end: Some(date(42)),
+        };
+        let call = NearestAtm {
+            option_type: OptionType::Call,
+        }
+        .select(&context)
+        .unwrap();
+        assert_eq!(call.expiration, date(20));
+        assert_eq!(call.strike, 100.0);
+        assert_eq!(call.contract_type, OptionType::Call);
+
+        let average = AtmCallPutAverage.select(&context).unwrap();
+        assert_eq!(average.contract, "SPY20Call100+SPY20Put100");
+        assert!((average.implied_volatility - 0.2).abs() < 1e-12);
+
+        let after_data = SelectionContext {
+            last_price_date: date(10),
+            ..context
+        };
+        assert_eq!(
+            NearestAtm {
+                option_type: OptionType::Put,
+            }
+            .select(&after_data)
+            .unwrap_err(),
+            SelectionError::ExpiryAfterPriceData {
+                expiration: date(20),
+                last_price_date: date(10),
+            }
+        );
+    }
+}
@@ -0,0 +1,599 @@
+// Synthetic markets with a known true volatility, for checking estimators
+// against ground truth. Prices follow GBM, GARCH(1,1) or Heston dynamics on
+// the NYSE calendar, with opens, highs and lows from an intraday path. Option
+// chains are priced with Black-Scholes at the model's expected average
+// variance to each expiry, plus a vol risk premium, a skew and per-contract IV
+// noise, and quoted with a spread. Output uses the API types so the rest of
+// the pipeline runs on it unchanged.
+
+use crate::api::{Ohlcv, OptionType, OptionsData};
+use crate::black_scholes::{BlackScholes, OptionPricer, PricingInputs};
+use crate::calendar::{is_trading_day, trading_days};
+use crate::rng::Rng;
+use crate::time_series::TimeSeries;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::fmt;
+
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+// Intraday steps per trading day, five minutes of a 6.5 hour session. They
+// set the high and low, and are the Euler steps for Heston variance.
+const STEPS_PER_DAY: usize = 78;
+
+// Variances are annualized unless noted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VolatilityModel {
+    Gbm {
+        volatility: f64,
+    },
+    // Daily-return GARCH(1,1): h' = omega + alpha * r^2 + beta * h, with h and
+    // omega daily variances. Starts at the long-run variance.
+    Garch {
+        omega: f64,
+        alpha: f64,
+        beta: f64,
+    },
+    // dv = kappa (theta - v) dt + xi sqrt(v) dW, corr(dW, dW_S) = rho.
+    Heston {
+        kappa: f64,
+        theta: f64,
+        xi: f64,
+        rho: f64,
+        v0: f64,
+    },
+}
+
+impl VolatilityModel {
+    // GARCH(1,1) with omega set so the long-run annualized vol is `volatility`.
+    pub fn garch(volatility: f64, alpha: f64, beta: f64) -> Self {
+        VolatilityModel::Garch {
+            omega: volatility * volatility / TRADING_DAYS_PER_YEAR * (1.0 - alpha - beta),
+            alpha,
+            beta,
+        }
+    }
+
+    fn validate(&self) -> Result<(), SimulationError> {
+        let valid = match *self {
+            VolatilityModel::Gbm { volatility } => volatility >= 0.0,
+            VolatilityModel::Garch { omega, alpha, beta } => {
+                omega > 0.0 && alpha >= 0.0 && beta >= 0.0 && alpha + beta < 1.0
+            }
+            VolatilityModel::Heston {
+                kappa,
+                theta,
+                xi,
+                rho,
+                v0,
+            } => kappa > 0.0 && theta > 0.0 && xi >= 0.0 && rho.abs() <= 1.0 && v0 >= 0.0,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(SimulationError::InvalidModel(*self))
+        }
+    }
+
+    // The variance the model mean-reverts to.
+    pub fn long_run_variance(&self) -> f64 {
+        match *self {
+            VolatilityModel::Gbm { volatility } => volatility * volatility,
+            VolatilityModel::Garch { omega, alpha, beta } => {
+                omega / (1.0 - alpha - beta) * TRADING_DAYS_PER_YEAR
+            }
+            VolatilityModel::Heston { theta, .. } => theta,
+        }
+    }
+
+    fn initial_variance(&self) -> f64 {
+        match *self {
+            VolatilityModel::Heston { v0, .. } => v0,
+            _ => self.long_run_variance(),
+        }
+    }
+
+    // Expected average variance over the next `years` given the current
+    // variance state (the next day's conditional variance for GARCH).
+    pub fn expected_average_variance(&self, variance: f64, years: f64) -> f64 {
+        match *self {
+            VolatilityModel::Gbm { volatility } => volatility * volatility,
+            VolatilityModel::Garch { alpha, beta, .. } => {
+                let persistence = alpha + beta;
+                let long_run = self.long_run_variance();
+                let days = (years * TRADING_DAYS_PER_YEAR).max(1.0);
+                long_run
+                    + (variance - long_run) * (1.0 - persistence.powf(days))
+                        / (days * (1.0 - persistence))
+            }
+            VolatilityModel::Heston { kappa, theta, .. } => {
+                if years <= 0.0 {
+                    return variance;
+                }
+                theta + (variance - theta) * (1.0 - (-kappa * years).exp()) / (kappa * years)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimulationError {
+    InvalidModel(VolatilityModel),
+    InvalidSetting(&'static str),
+    // Fewer than two trading days between start and end.
+    TooFewTradingDays,
+    // A forecaster returned a different number of forecasts than bars.
+    ForecastLength { expected: usize, actual: usize },
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::InvalidModel(model) => {
+                write!(f, "invalid volatility model parameters: {:?}", model)
+            }
+            SimulationError::InvalidSetting(setting) => {
+                write!(f, "invalid simulator setting: {}", setting)
+            }
+            SimulationError::TooFewTradingDays => {
+                write!(f, "need at least two trading days to simulate")
+            }
+            SimulationError::ForecastLength { expected, actual } => write!(
+                f,
+                "forecaster returned {} forecasts for {} bars",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+#[derive(Debug, Clone)]
+pub struct MarketSimulator {
+    pub model: VolatilityModel,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub initial_spot: f64,
+    // Real-world drift of the log price, before the -variance/2 correction.
+    pub drift: f64,
+    // Share of each day's variance realized overnight, between the previous
+    // close and the open. Zero makes every open equal the previous close.
+    pub overnight_fraction: f64,
+    // Continuously compounded, used to price the chains.
+    pub rate: f64,
+    pub dividend_yield: f64,
+    // Added to every implied vol on top of the model's expected vol.
+    pub vol_risk_premium: f64,
+    // IV change per unit of ln(K/F) / sqrt(T); negative for an equity skew.
+    pub skew: f64,
+    // Standard deviation of independent per-contract IV noise.
+    pub iv_noise: f64,
+    // Full bid-ask spread as a fraction of mid, at least one cent.
+    pub relative_spread: f64,
+    // Expiries are the Friday on or after each of these calendar-day offsets
+    // from the quote date (the Thursday when that Friday is a holiday).
+    pub expiry_days: Vec<i64>,
+    // Strikes cover spot * (1 +/- strike_width) in steps of strike_step.
+    pub strike_step: f64,
+    pub strike_width: f64,
+    pub seed: u64,
+}
+
+impl Default for MarketSimulator {
+    fn default() -> Self {
+        MarketSimulator {
+            model: VolatilityModel::Heston {
+                kappa: 3.0,
+                theta: 0.04,
+                xi: 0.5,
+                rho: -0.7,
+                v0: 0.04,
+            },
+            start: NaiveDate::from_ymd_opt(2023, 1, 3).unwrap(),
+            end: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            initial_spot: 100.0,
+            drift: 0.07,
+            overnight_fraction: 0.0,
+            rate: 0.045,
+            dividend_yield: 0.013,
+            vol_risk_premium: 0.02,
+            skew: -0.15,
+            iv_noise: 0.005,
+            relative_spread: 0.04,
+            expiry_days: vec![7, 14, 30, 60, 90, 180],
+            strike_step: 1.0,
+            strike_width: 0.25,
+            seed: 42,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulatedMarket {
+    pub symbol: String,
+    pub ohlcv: Vec<Ohlcv>,
+    // Variance of each day's log return, dated on the day it ends; absent on
+    // the first day.
+    pub daily_variance: TimeSeries<f64>,
+    // Variance state at each close: the next day's conditional variance for
+    // GBM and GARCH, the instantaneous variance for Heston.
+    pub spot_variance: TimeSeries<f64>,
+    simulator: MarketSimulator,
+}
+
+impl MarketSimulator {
+    pub fn simulate(&self, symbol: &str) -> Result<SimulatedMarket, SimulationError> {
+        self.model.validate()?;
+        if self.initial_spot.is_nan() || self.initial_spot <= 0.0 {
+            return Err(SimulationError::InvalidSetting(
+                "initial_spot must be positive",
+            ));
+        }
+        if ![self.strike_step, self.strike_width]
+            .iter()
+            .all(|setting| *setting > 0.0)
+        {
+            return Err(SimulationError::InvalidSetting(
+                "strike_step and strike_width must be positive",
+            ));
+        }
+        if !(0.0..1.0).contains(&self.overnight_fraction) {
+            return Err(SimulationError::InvalidSetting(
+                "overnight_fraction must be in [0, 1)",
+            ));
+        }
+        if self.iv_noise < 0.0 || self.relative_spread < 0.0 {
+            return Err(SimulationError::InvalidSetting(
+                "iv_noise and relative_spread must not be negative",
+            ));
+        }
+        let dates = trading_days(self.start, self.end);
+        if dates.len() < 2 {
+            return Err(SimulationError::TooFewTradingDays);
+        }
+
+        let mut rng = Rng::new(self.seed);
+        let dt = 1.0 / TRADING_DAYS_PER_YEAR;
+        let mut log_spot = self.initial_spot.ln();
+        let mut variance = self.model.initial_variance();
+
+        let mut ohlcv = Vec::with_capacity(dates.len());
+        let mut daily_variance = TimeSeries::new();
+        let mut spot_variance = TimeSeries::new();
+        ohlcv.push(Ohlcv {
+            open: self.initial_spot,
+            high: self.initial_spot,
+            low: self.initial_spot,
+            close: self.initial_spot,
+            date: dates[0],
+        });
+        spot_variance.insert(dates[0], variance);
+
+        // Each day is one overnight step to the open, then STEPS_PER_DAY
+        // intraday steps that also set the high and low.
+        let overnight_step = self.overnight_fraction * dt;
+        let intraday_step = (1.0 - self.overnight_fraction) * dt / STEPS_PER_DAY as f64;
+        for &date in &dates[1..] {
+            let mut shock = 0.0;
+            let mut integrated = 0.0;
+            let (mut open, mut high, mut low) = (log_spot, log_spot, log_spot);
+            for step in 0..=STEPS_PER_DAY {
+                let h = if step == 0 {
+                    overnight_step
+                } else {
+                    intraday_step
+                };
+                if h > 0.0 {
+                    // Full truncation for Heston: negative variance is floored
+                    // at zero wherever it feeds a drift or diffusion term.
+                    let v = variance.max(0.0);
+                    let z_spot = rng.normal();
+                    let increment = (v * h).sqrt() * z_spot;
+                    log_spot += (self.drift - 0.5 * v) * h + increment;
+                    shock += increment;
+                    integrated += v * h;
+                    if let VolatilityModel::Heston {
+                        kappa,
+                        theta,
+                        xi,
+                        rho,
+                        ..
+                    } = self.model
+                    {
+                        let z_var = rho * z_spot + (1.0 - rho * rho).sqrt() * rng.normal();
+                        variance += kappa * (theta - v) * h + xi * (v * h).sqrt() * z_var;
+                    }
+                }
+                if step == 0 {
+                    (open, high, low) = (log_spot, log_spot, log_spot);
+                } else {
+                    high = high.max(log_spot);
+                    low = low.min(log_spot);
+                }
+            }
+            if let VolatilityModel::Garch { omega, alpha, beta } = self.model {
+                variance =
+                    (omega + alpha * shock * shock + beta * variance * dt) * TRADING_DAYS_PER_YEAR;
+            }
+            ohlcv.push(Ohlcv {
+                open: open.exp(),
+                high: high.exp(),
+                low: low.exp(),
+                close: log_spot.exp(),
+                date,
+            });
+            daily_variance.insert(date, integrated / dt);
+            spot_variance.insert(date, variance.max(0.0));
+        }
+
+        Ok(SimulatedMarket {
+            symbol: symbol.to_string(),
+            ohlcv,
+            daily_variance,
+            spot_variance,
+            simulator: self.clone(),
+        })
+    }
+}
+
+// The Friday on or after `date`, or the Thursday before a Friday holiday.
+fn listed_expiry(date: NaiveDate) -> NaiveDate {
+    let days_to_friday = (Weekday::Fri.num_days_from_monday() as i64
+        - date.weekday().num_days_from_monday() as i64)
+        .rem_euclid(7);
+    let friday = date + Duration::days(days_to_friday);
+    if is_trading_day(friday) {
+        friday
+    } else {
+        friday - Duration::days(1)
+    }
+}
+
+impl SimulatedMarket {
+    pub fn close_on(&self, date: NaiveDate) -> Option<f64> {
+        self.ohlcv
+            .binary_search_by_key(&date, |ohlcv| ohlcv.date)
+            .ok()
+            .map(|index| self.ohlcv[index].close)
+    }
+
+    // The model's volatility at the close of `date`.
+    pub fn spot_volatility(&self, date: NaiveDate) -> Option<f64> {
+        self.spot_variance.get(date).map(|variance| variance.sqrt())
+    }
+
+    // Realized volatility of the true variance over the `days` trading days
+    // after `date`: the quantity a `days`-day forecast made on `date` targets.
+    // None if the simulation ends first.
+    pub fn forward_volatility(&self, date: NaiveDate, days: usize) -> Option<f64> {
+        if days == 0 {
+            return None;
+        }
+        let start = self.daily_variance.partition_point(|(d, _)| *d <= date);
+        let window = self.daily_variance[start..].get(..days)?;
+        let mean = window.iter().map(|(_, variance)| variance).sum::<f64>() / days as f64;
+        Some(mean.sqrt())
+    }
+
+    // The option chain quoted at the close of `date`, empty on dates outside
+    // the simulation. Noise is seeded per date, so repeated calls agree.
+    pub fn options_on(&self, date: NaiveDate) -> Vec<OptionsData> {
+        let (Some(spot), Some(variance)) = (self.close_on(date), self.spot_variance.get(date))
+        else {
+            return Vec::new();
+        };
+        let sim = &self.simulator;
+        let mut rng = Rng::new(sim.seed ^ (date.num_days_from_ce() as u64).wrapping_mul(0x9E37));
+
+        let low = ((spot * (1.0 - sim.strike_width)) / sim.strike_step).ceil() as i64;
+        let high = ((spot * (1.0 + sim.strike_width)) / sim.strike_step).floor() as i64;
+        let strikes: Vec<f64> = (low.max(1)..=high)
+            .map(|step| step as f64 * sim.strike_step)
+            .collect();
+
+        let mut expirations: Vec<NaiveDate> = sim
+            .expiry_days
+            .iter()
+            .filter(|days| **days > 0)
+            .map(|days| listed_expiry(date + Duration::days(*days)))
+            .filter(|expiration| *expiration > date)
+            .collect();
+        expirations.sort();
+        expirations.dedup();
+
+        let mut options = Vec::new();
+        for expiration in expirations {
+            let time_to_expiry = (expiration - date).num_days() as f64 / 365.0;
+            let atm_vol = sim
+                .model
+                .expected_average_variance(*variance, time_to_expiry)
+                .sqrt()
+                + sim.vol_risk_premium;
+            let forward = spot * ((sim.rate - sim.dividend_yield) * time_to_expiry).exp();
+            for &strike in &strikes {
+                let moneyness = (strike / forward).ln();
+                let smile_vol = atm_vol + sim.skew * moneyness / time_to_expiry.sqrt();
+                for option_type in [OptionType::Call, OptionType::Put] {
+                    let iv = (smile_vol + sim.iv_noise * rng.normal()).max(0.01);
+                    let inputs = PricingInputs {
+                        option_type,
+                        spot,
+                        strike,
+                        time_to_expiry,
+                        rate: sim.rate,
+                        dividend_yield: sim.dividend_yield,
+                        volatility: iv,
+                    };
+                    let mid = BlackScholes.price(&inputs);
+                    let half_spread = (0.5 * sim.relative_spread * mid).max(0.005);
+                    let bid = ((mid - half_spread) * 100.0).floor().max(0.0) / 100.0;
+                    let ask = ((mid + half_spread) * 100.0).ceil() / 100.0;
+                    if ask <= 0.0 {
+                        continue;
+                    }
+                    // Interest falls off away from the money.
+                    let open_interest = (5000.0 * (-0.5 * (moneyness / 0.1).powi(2)).exp()).round();
+                    let greeks = BlackScholes.greeks(&inputs);
+                    let type_code = match option_type {
+                        OptionType::Call => 'C',
+                        OptionType::Put => 'P',
+                    };
+                    // Vendor conventions: theta per calendar day, vega and rho
+                    // per vol or rate point.
+                    options.push(OptionsData {
+                        symbol: self.symbol.clone(),
+                        contract: format!(
+                            "{}{}{}{:08}",
+                            self.symbol,
+                            expiration.format("%y%m%d"),
+                            type_code,
+                            (strike * 1000.0).round() as i64
+                        ),
+                        contract_type: option_type,
+                        expiration,
+                        date,
+                        strike,
+                        last: mid,
+                        mark: mid,
+                        bid,
+                        ask,
+                        volume: (open_interest / 10.0).round(),
+                        open_interest,
+                        implied_volatility: iv,
+                        delta: greeks.delta,
+                        gamma: greeks.gamma,
+                        theta: greeks.theta / 365.0,
+                        vega: greeks.vega / 100.0,
+                        rho: greeks.rho / 100.0,
+                    });
+                }
+            }
+        }
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn gbm(volatility: f64) -> MarketSimulator {
+        MarketSimulator {
+            model: VolatilityModel::Gbm { volatility },
+            start: date(2020, 1, 2),
+            end: date(2023, 12, 29),
+            drift: 0.0,
+            vol_risk_premium: 0.0,
+            skew: 0.0,
+            iv_noise: 0.0,
+            ..MarketSimulator::default()
+        }
+    }
+
+    #[test]
+    fn gbm_log_return_variance_is_sigma_squared_dt() {
+        let market = gbm(0.2).simulate("SIM").unwrap();
+        let returns: Vec<f64> = market
+            .ohlcv
+            .windows(2)
+            .map(|pair| (pair[1].close / pair[0].close).ln())
+            .collect();
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        let expected = 0.04 / TRADING_DAYS_PER_YEAR;
+        // About 1000 returns put the sample variance within ~5% (one sd).
+        assert!(
+            (variance / expected - 1.0).abs() < 0.15,
+            "{} vs {}",
+            variance,
+            expected
+        );
+
+        // The true variance is constant and known.
+        assert!(market
+            .daily_variance
+            .values()
+            .all(|v| (v - 0.04).abs() < 1e-12));
+        let last = market.ohlcv.last().unwrap().date;
+        assert!((market.spot_volatility(last).unwrap() - 0.2).abs() < 1e-12);
+        assert!((market.forward_volatility(market.ohlcv[0].date, 30).unwrap() - 0.2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn simulation_is_reproducible_per_seed() {
+        let a = gbm(0.2).simulate("SIM").unwrap();
+        let b = gbm(0.2).simulate("SIM").unwrap();
+        let c = MarketSimulator {
+            seed: 7,
+            ..gbm(0.2)
+        }
+        .simulate("SIM")
+        .unwrap();
+        let closes = |market: &SimulatedMarket| -> Vec<f64> {
+            market.ohlcv.iter().map(|bar| bar.close).collect()
+        };
+        assert_eq!(closes(&a), closes(&b));
+        assert_ne!(closes(&a), closes(&c));
+    }
+
+    #[test]
+    fn chain_prices_are_black_scholes_at_the_simulated_vol() {
+        let market = gbm(0.25).simulate("SIM").unwrap();
+        let quote_date = date(2021, 6, 1);
+        let spot = market.close_on(quote_date).unwrap();
+        let options = market.options_on(quote_date);
+        assert!(!options.is_empty());
+        for option in &options {
+            assert!((option.implied_volatility - 0.25).abs() < 1e-12);
+            let price = BlackScholes.price(&PricingInputs {
+                option_type: option.contract_type,
+                spot,
+                strike: option.strike,
+                time_to_expiry: (option.expiration - quote_date).num_days() as f64 / 365.0,
+                rate: 0.045,
+                dividend_yield: 0.013,
+                volatility: 0.25,
+            });
+            assert!((option.mark - price).abs() < 1e-12);
+            assert!(option.bid <= option.mark && option.mark <= option.ask);
+            assert_eq!(option.expiration.weekday(), Weekday::Fri);
+        }
+        assert!(market.options_on(date(2021, 6, 5)).is_empty());
+    }
+
+    #[test]
+    fn heston_chain_iv_is_the_expected_average_vol() {
+        let simulator = MarketSimulator {
+            vol_risk_premium: 0.0,
+            skew: 0.0,
+            iv_noise: 0.0,
+            ..MarketSimulator::default()
+        };
+        let market = simulator.simulate("SIM").unwrap();
+        let quote_date = date(2023, 6, 1);
+        let variance = *market.spot_variance.get(quote_date).unwrap();
+        for option in market.options_on(quote_date) {
+            let t = (option.expiration - quote_date).num_days() as f64 / 365.0;
+            let expected = simulator
+                .model
+                .expected_average_variance(variance, t)
+                .sqrt();
+            assert!((option.implied_volatility - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn garch_constructor_targets_the_long_run_vol() {
+        let model = VolatilityModel::garch(0.2, 0.08, 0.9);
+        assert!((model.long_run_variance() - 0.04).abs() < 1e-15);
+        assert!((model.expected_average_variance(0.04, 0.5) - 0.04).abs() < 1e-15);
+        // Mean reversion from above: the average lies between the two.
+        let average = model.expected_average_variance(0.09, 30.0 / 252.0);
+        assert!(average < 0.09 && average > 0.04);
+        assert!(VolatilityModel::garch(0.2, 0.5, 0.6).validate().is_err());
+    }
+}
@@ -0,0 +1,272 @@
+// Skew analytics from the fitted SVI smiles: risk reversals and butterflies
+// at 25 and 10 delta, and the slope of IV against log-moneyness at the money.
+// Deltas are forward (undiscounted) Black-Scholes deltas on the smile vol, so
+// the 25-delta put is the strike where N(d1) = 0.75. Risk reversals are call
+// minus put IV, negative for the usual equity put skew.
+
+use crate::api::Ohlcv;
+use crate::data::downside_realized_volatility;
+use crate::regression::{ols_with_covariance, wald_test, CovarianceEstimator, OlsResult, WaldTest};
+use crate::stats::normal_cdf;
+use crate::svi::SmileFit;
+use chrono::NaiveDate;
+
+#[derive(Debug, Clone)]
+pub struct ExpirySkew {
+    pub expiration: NaiveDate,
+    pub time_to_expiry: f64,
+    pub atm_iv: f64,
+    pub risk_reversal_25: f64,
+    pub butterfly_25: f64,
+    pub risk_reversal_10: f64,
+    pub butterfly_10: f64,
+    // d(IV)/d(ln(K/F)) at the money.
+    pub slope: f64,
+}
+
+// Skew at a constant tenor on one date, interpolated linearly in time between
+// the expiries around it.
+#[derive(Debug, Clone)]
+pub struct SkewMetrics {
+    pub date: NaiveDate,
+    pub tenor_days: usize,
+    // The expiries blended. Equal when the tenor falls on an expiry or outside
+    // the fitted range.
+    pub lower_expiration: NaiveDate,
+    pub upper_expiration: NaiveDate,
+    pub atm_iv: f64,
+    pub risk_reversal_25: f64,
+    pub butterfly_25: f64,
+    pub risk_reversal_10: f64,
+    pub butterfly_10: f64,
+    pub slope: f64,
+}
+
+// Log-moneyness where the smile's forward call delta N(d1) equals
+// `call_delta`, by bisection. N(d1) falls as k rises for any smile without
+// calendar or butterfly arbitrage.
+fn strike_for_delta(fit: &SmileFit, call_delta: f64) -> Option<f64> {
+    let delta_at = |k: f64| -> Option<f64> {
+        let w = fit.raw.total_variance(k);
+        if w <= 0.0 {
+            return None;
+        }
+        let sqrt_w = w.sqrt();
+        Some(normal_cdf(-k / sqrt_w + 0.5 * sqrt_w))
+    };
+    let mut low = -3.0;
+    let mut high = 3.0;
+    if delta_at(low)? < call_delta || delta_at(high)? > call_delta {
+        return None;
+    }
+    for _ in 0..100 {
+        let mid = 0.5 * (low + high);
+        if delta_at(mid)? > call_delta {
+            low = mid;
+        } else {
+            high = mid;
+        }
+        if high - low < 1e-10 {
+            break;
+        }
+    }
+    Some(0.5 * (low + high))
+}
+
+fn iv_at_delta(fit: &SmileFit, call_delta: f64) -> Option<f64> {
+    fit.iv_at(strike_for_delta(fit, call_delta)?)
+}
+
+pub fn expiry_skew(fit: &SmileFit) -> Option<ExpirySkew> {
+    let atm_iv = fit.atm_iv()?;
+    let call_25 = iv_at_delta(fit, 0.25)?;
+    let put_25 = iv_at_delta(fit, 0.75)?;
+    let call_10 = iv_at_delta(fit, 0.10)?;
+    let put_10 = iv_at_delta(fit, 0.90)?;
+    Some(ExpirySkew {
+        expiration: fit.expiration,
+        time_to_expiry: fit.time_to_expiry,
+        atm_iv,
+        risk_reversal_25: call_25 - put_25,
+        butterfly_25: 0.5 * (call_25 + put_25) - atm_iv,
+        risk_reversal_10: call_10 - put_10,
+        butterfly_10: 0.5 * (call_10 + put_10) - atm_iv,
+        slope: fit.iv_slope_at(0.0)?,
+    })
+}
+
+// Skew at `tenor_days` calendar days from one date's smiles, in any order.
+// Outside the fitted expiries the nearest one is used as is.
+pub fn skew_at_tenor(fits: &[SmileFit], date: NaiveDate, tenor_days: usize) -> Option<SkewMetrics> {
+    let mut skews: Vec<ExpirySkew> = fits.iter().filter_map(expiry_skew).collect();
+    skews.sort_by(|a, b| a.time_to_expiry.total_cmp(&b.time_to_expiry));
+    let target = tenor_days as f64 / 365.0;
+    let upper = skews
+        .iter()
+        .position(|skew| skew.time_to_expiry >= target)
+        .unwrap_or(skews.len().checked_sub(1)?);
+    let high = &skews[upper];
+    let (low, weight) = if upper == 0 || high.time_to_expiry < target {
+        (high, 0.0)
+    } else {
+        let low = &skews[upper - 1];
+        (
+            low,
+            (target - low.time_to_expiry) / (high.time_to_expiry - low.time_to_expiry),
+        )
+    };
+    let blend = |f: fn(&ExpirySkew) -> f64| (1.0 - weight) * f(low) + weight * f(high);
+
+    Some(SkewMetrics {
+        date,
+        tenor_days,
+        lower_expiration: low.expiration,
+        upper_expiration: high.expiration,
+        atm_iv: blend(|s| s.atm_iv),
+        risk_reversal_25: blend(|s| s.risk_reversal_25),
+        butterfly_25: blend(|s| s.butterfly_25),
+        risk_reversal_10: blend(|s| s.risk_reversal_10),
+        butterfly_10: blend(|s| s.butterfly_10),
+        slope: blend(|s| s.slope),
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct SkewPredictionTest {
+    // downside = alpha + beta_atm * ATM IV + beta_rr * RR25.
+    pub ols: OlsResult,
+    // H0: beta_rr = 0, i.e. skew adds nothing beyond ATM IV.
+    pub skew_adds_nothing: WaldTest,
+}
+
+// Does the 25-delta risk reversal predict downside realized volatility over
+// the next `window` trading days beyond what ATM IV does?
+pub fn skew_downside_regression(
+    history: &[SkewMetrics],
+    ohlcv_data: &[Ohlcv],
+    window: usize,
+    estimator: CovarianceEstimator,
+) -> Option<SkewPredictionTest> {
+    let mut downside = Vec::new();
+    let mut atm = Vec::new();
+    let mut risk_reversal = Vec::new();
+    for metrics in history {
+        let Some(start_idx) = ohlcv_data.iter().position(|d| d.date == metrics.date) else {
+            continue;
+        };
+        if let Some(realized) = downside_realized_volatility(ohlcv_data, start_idx, window) {
+            downside.push(realized);
+            atm.push(metrics.atm_iv);
+            risk_reversal.push(metrics.risk_reversal_25);
+        }
+    }
+
+    let ols = ols_with_covariance(&downside, &[atm, risk_reversal], estimator)?;
+    let skew_adds_nothing = wald_test(
+        &ols.coefficients,
+        &ols.covariance,
+        &[vec![0.0, 0.0, 1.0]],
+        &[0.0],
+    )?;
+    Some(SkewPredictionTest {
+        ols,
+        skew_adds_nothing,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svi::{SviFitQuality, SviRaw};
+    use chrono::Duration;
+
+    fn date(days: i64) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, 2).unwrap() + Duration::days(days)
+    }
+
+    fn fit(days: i64, raw: SviRaw) -> SmileFit {
+        SmileFit {
+            expiration: date(days),
+            time_to_expiry: days as f64 / 365.0,
+            forward: 100.0,
+            raw,
+            quality: SviFitQuality {
+                n: 10,
+                rmse: 0.0,
+                weighted_rmse: 0.0,
+                max_abs_error: 0.0,
+                r_squared: 1.0,
+            },
+            converged: true,
+            iterations: 0,
+        }
+    }
+
+    // b = 0 leaves total variance a at every strike: a flat smile at IV v.
+    fn flat(days: i64, iv: f64) -> SmileFit {
+        let raw = SviRaw {
+            a: iv * iv * days as f64 / 365.0,
+            b: 0.0,
+            rho: 0.0,
+            m: 0.0,
+            sigma: 0.1,
+        };
+        fit(days, raw)
+    }
+
+    #[test]
+    fn flat_smile_has_no_skew() {
+        let skew = expiry_skew(&flat(30, 0.2)).unwrap();
+        assert!((skew.atm_iv - 0.2).abs() < 1e-12);
+        for value in [
+            skew.risk_reversal_25,
+            skew.butterfly_25,
+            skew.risk_reversal_10,
+            skew.butterfly_10,
+            skew.slope,
+        ] {
+            assert!(value.abs() < 1e-9, "{}", value);
+        }
+    }
+
+    #[test]
+    fn put_skew_gives_negative_risk_reversals_and_slope() {
+        let t = 30.0 / 365.0;
+        let skewed = fit(
+            30,
+            SviRaw {
+                a: 0.03 * t,
+                b: 0.1 * t,
+                rho: -0.7,
+                m: 0.0,
+                sigma: 0.1,
+            },
+        );
+        let skew = expiry_skew(&skewed).unwrap();
+        assert!(skew.risk_reversal_25 < 0.0);
+        assert!(skew.risk_reversal_10 < skew.risk_reversal_25);
+        assert!(skew.butterfly_25 > 0.0);
+        assert!(skew.butterfly_10 > skew.butterfly_25);
+        assert!(skew.slope < 0.0);
+
+        // The 25-delta put strike is where the forward call delta is 0.75.
+        let k = strike_for_delta(&skewed, 0.75).unwrap();
+        let sqrt_w = skewed.raw.total_variance(k).sqrt();
+        assert!((normal_cdf(-k / sqrt_w + 0.5 * sqrt_w) - 0.75).abs() < 1e-8);
+        assert!(k < 0.0);
+    }
+
+    #[test]
+    fn tenor_skew_interpolates_in_time_whatever_the_fit_order() {
+        let fits = [flat(44, 0.3), flat(16, 0.2)];
+        let skew = skew_at_tenor(&fits, date(0), 30).unwrap();
+        assert_eq!(skew.lower_expiration, date(16));
+        assert_eq!(skew.upper_expiration, date(44));
+        assert!((skew.atm_iv - 0.25).abs() < 1e-12);
+        assert!(skew.risk_reversal_25.abs() < 1e-9);
+
+        let beyond = skew_at_tenor(&fits, date(0), 60).unwrap();
+        assert_eq!(beyond.lower_expiration, date(44));
+        assert!((beyond.atm_iv - 0.3).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,360 @@
+// Distribution helpers shared by the statistical tests. Kept dependency free.
+
+// Standard normal density.
+pub fn normal_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+// Standard normal CDF via the complementary error function.
+pub fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+// Complementary error function (Numerical Recipes erfcc, |error| < 1.2e-7).
+pub fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+// Two-sided p-value for a statistic that is standard normal under the null.
+pub fn two_sided_normal_p_value(statistic: f64) -> f64 {
+    (2.0 * (1.0 - normal_cdf(statistic.abs()))).clamp(0.0, 1.0)
+}
+
+// Natural log of the gamma function (Lanczos approximation).
+pub fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let mut y = x;
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000000000190015;
+    for c in COEFFICIENTS {
+        y += 1.0;
+        series += c / y;
+    }
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+// Regularized lower incomplete gamma P(a, x).
+pub fn gamma_p(a: f64, x: f64) -> f64 {
+    if x <= 0.0 || a <= 0.0 {
+        return 0.0;
+    }
+    if x < a + 1.0 {
+        // Series representation.
+        let mut ap = a;
+        let mut sum = 1.0 / a;
+        let mut del = sum;
+        for _ in 0..500 {
+            ap += 1.0;
+            del *= x / ap;
+            sum += del;
+            if del.abs() < sum.abs() * 1e-14 {
+                break;
+            }
+        }
+        (sum * (-x + a * x.ln() - ln_gamma(a)).exp()).clamp(0.0, 1.0)
+    } else {
+        1.0 - gamma_q_continued_fraction(a, x)
+    }
+}
+
+// Regularized upper incomplete gamma Q(a, x) = 1 - P(a, x).
+pub fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 || a <= 0.0 {
+        return 1.0;
+    }
+    if x < a + 1.0 {
+        1.0 - gamma_p(a, x)
+    } else {
+        gamma_q_continued_fraction(a, x)
+    }
+}
+
+fn gamma_q_continued_fraction(a: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / TINY;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..500 {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = b + an / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let del = d * c;
+        h *= del;
+        if (del - 1.0).abs() < 1e-14 {
+            break;
+        }
+    }
+    ((-x + a * x.ln() - ln_gamma(a)).exp() * h).clamp(0.0, 1.0)
+}
+
+// Survival function of the chi-squared distribution with `df` degrees of freedom.
+pub fn chi_squared_sf(statistic: f64, df: usize) -> f64 {
+    if df == 0 {
+        return f64::NAN;
+    }
+    gamma_q(df as f64 / 2.0, statistic.max(0.0) / 2.0)
+}
+
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+// Regularized incomplete beta function I_x(a, b).
+pub fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let qab = a + b;
+    let qap = a + 1.0;
+    let qam = a - 1.0;
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..500 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        h *= d * c;
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let del = d * c;
+        h *= del;
+        if (del - 1.0).abs() < 1e-14 {
+            break;
+        }
+    }
+    h
+}
+
+// Student t CDF with `df` degrees of freedom.
+pub fn student_t_cdf(t: f64, df: f64) -> f64 {
+    let x = df / (df + t * t);
+    let tail = 0.5 * incomplete_beta(df / 2.0, 0.5, x);
+    if t >= 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+// Two-sided p-value for a statistic that is Student t under the null.
+pub fn two_sided_t_p_value(statistic: f64, df: f64) -> f64 {
+    (2.0 * (1.0 - student_t_cdf(statistic.abs(), df))).clamp(0.0, 1.0)
+}
+
+// Inverse of the standard normal CDF (Acklam's rational approximation,
+// relative error < 1.2e-9).
+pub fn normal_quantile(p: f64) -> f64 {
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1.0 {
+        return f64::INFINITY;
+    }
+
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    if p < P_LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        let q = (-2.0 * (1.0 - p).ln()).sqrt();
+        -(((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfidenceInterval {
+    pub estimate: f64,
+    pub lower: f64,
+    pub upper: f64,
+    // Confidence level, e.g. 0.95.
+    pub level: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "{} vs {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn normal_cdf_matches_tables() {
+        assert_close(normal_cdf(0.0), 0.5, 1e-7);
+        assert_close(normal_cdf(1.0), 0.8413447461, 1e-7);
+        assert_close(normal_cdf(1.96), 0.9750021049, 1e-7);
+        assert_close(normal_cdf(-2.5), 0.0062096653, 1e-7);
+        assert_close(two_sided_normal_p_value(1.96), 0.0499957902, 1e-6);
+    }
+
+    #[test]
+    fn normal_quantile_matches_tables_and_inverts_cdf() {
+        assert_close(normal_quantile(0.5), 0.0, 1e-9);
+        assert_close(normal_quantile(0.975), 1.959963985, 1e-8);
+        assert_close(normal_quantile(0.01), -2.326347874, 1e-8);
+        assert_close(normal_quantile(0.999), 3.090232306, 1e-8);
+        for p in [0.001, 0.02, 0.3, 0.7, 0.98] {
+            assert_close(normal_cdf(normal_quantile(p)), p, 2e-7);
+        }
+        assert_eq!(normal_quantile(0.0), f64::NEG_INFINITY);
+        assert_eq!(normal_quantile(1.0), f64::INFINITY);
+    }
+
+    #[test]
+    fn ln_gamma_matches_factorials() {
+        assert_close(ln_gamma(1.0), 0.0, 1e-10);
+        assert_close(ln_gamma(5.0), 24f64.ln(), 1e-10);
+        assert_close(ln_gamma(0.5), std::f64::consts::PI.sqrt().ln(), 1e-10);
+    }
+
+    #[test]
+    fn chi_squared_sf_matches_critical_values() {
+        assert_close(chi_squared_sf(3.841459, 1), 0.05, 1e-6);
+        assert_close(chi_squared_sf(9.487729, 4), 0.05, 1e-6);
+        assert_close(chi_squared_sf(18.307038, 10), 0.05, 1e-6);
+        assert_close(chi_squared_sf(6.634897, 1), 0.01, 1e-6);
+        // With two degrees of freedom the survival function is exp(-x / 2).
+        assert_close(chi_squared_sf(3.0, 2), (-1.5f64).exp(), 1e-12);
+        assert_close(chi_squared_sf(0.0, 3), 1.0, 1e-12);
+    }
+
+    #[test]
+    fn incomplete_beta_matches_closed_forms() {
+        // I_x(a, 1) = x^a and I_x(1, b) = 1 - (1 - x)^b.
+        assert_close(incomplete_beta(2.5, 1.0, 0.3), 0.3f64.powf(2.5), 1e-12);
+        assert_close(incomplete_beta(1.0, 3.0, 0.6), 1.0 - 0.4f64.powi(3), 1e-12);
+        // Symmetry I_x(a, b) = 1 - I_{1-x}(b, a).
+        assert_close(
+            incomplete_beta(2.0, 5.0, 0.2),
+            1.0 - incomplete_beta(5.0, 2.0, 0.8),
+            1e-12,
+        );
+    }
+
+    #[test]
+    fn student_t_cdf_matches_critical_values() {
+        // Cauchy at one degree of freedom.
+        assert_close(student_t_cdf(1.0, 1.0), 0.75, 1e-10);
+        assert_close(student_t_cdf(0.0, 7.0), 0.5, 1e-12);
+        assert_close(student_t_cdf(2.015048, 5.0), 0.95, 1e-6);
+        assert_close(student_t_cdf(2.228139, 10.0), 0.975, 1e-6);
+        assert_close(student_t_cdf(-2.749996, 30.0), 0.005, 1e-6);
+        assert_close(two_sided_t_p_value(2.228139, 10.0), 0.05, 1e-6);
+        // Approaches the normal as the degrees of freedom grow.
+        assert_close(student_t_cdf(1.96, 1e6), normal_cdf(1.96), 1e-5);
+    }
+}
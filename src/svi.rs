@@ -0,0 +1,406 @@
+// Per-expiry SVI fits of the implied volatility smile (Gatheral, 2004), so
+// ATM, skew and wing metrics come from a smooth curve instead of single noisy
+// quotes. The fit is in total implied variance w = sigma^2 * T against
+// log-moneyness k = ln(K / F):
+//
+//   raw:     w(k) = a + b (rho (k - m) + sqrt((k - m)^2 + sigma^2))
+//   natural: w(k) = delta + omega/2 (1 + zeta rho (k - mu)
+//                   + sqrt((zeta (k - mu) + rho)^2 + 1 - rho^2))
+//
+// Calibration is the quasi-explicit method of Zeller and Ruf: for fixed
+// (m, sigma) the raw form is linear in (a, b rho sigma, b sigma), so only
+// (m, sigma) are searched by Nelder-Mead.
+
+use crate::black_scholes::{BlackScholes, OptionPricer};
+use crate::chain::{ExpirySlice, OptionChain};
+use crate::optimize::{Minimum, NelderMead};
+use crate::parity::ForwardCurve;
+use crate::regression::invert_matrix;
+use chrono::NaiveDate;
+
+const MIN_POINTS: usize = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SviRaw {
+    pub a: f64,
+    pub b: f64,
+    pub rho: f64,
+    pub m: f64,
+    pub sigma: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SviNatural {
+    pub delta: f64,
+    pub omega: f64,
+    pub mu: f64,
+    pub zeta: f64,
+    pub rho: f64,
+}
+
+impl SviRaw {
+    pub fn total_variance(&self, log_moneyness: f64) -> f64 {
+        let x = log_moneyness - self.m;
+        self.a + self.b * (self.rho * x + (x * x + self.sigma * self.sigma).sqrt())
+    }
+
+    // dw/dk.
+    pub fn total_variance_slope(&self, log_moneyness: f64) -> f64 {
+        let x = log_moneyness - self.m;
+        self.b * (self.rho + x / (x * x + self.sigma * self.sigma).sqrt())
+    }
+
+    // None where the fitted total variance is not positive.
+    pub fn implied_volatility(&self, log_moneyness: f64, time_to_expiry: f64) -> Option<f64> {
+        let w = self.total_variance(log_moneyness);
+        (w > 0.0 && time_to_expiry > 0.0).then(|| (w / time_to_expiry).sqrt())
+    }
+
+    // Gatheral and Jacquier (2014), Lemma 3.3.
+    pub fn to_natural(self) -> SviNatural {
+        let root = (1.0 - self.rho * self.rho).sqrt();
+        let zeta = root / self.sigma;
+        let omega = 2.0 * self.b * self.sigma / root;
+        SviNatural {
+            delta: self.a - 0.5 * omega * (1.0 - self.rho * self.rho),
+            omega,
+            mu: self.m + self.rho * self.sigma / root,
+            zeta,
+            rho: self.rho,
+        }
+    }
+}
+
+// Main only reports the natural parameters; these are for working with them
+// directly and are checked against the raw form in the tests.
+#[allow(dead_code)]
+impl SviNatural {
+    pub fn total_variance(&self, log_moneyness: f64) -> f64 {
+        let x = self.zeta * (log_moneyness - self.mu);
+        self.delta
+            + 0.5
+                * self.omega
+                * (1.0 + self.rho * x + ((x + self.rho).powi(2) + 1.0 - self.rho * self.rho).sqrt())
+    }
+
+    pub fn to_raw(self) -> SviRaw {
+        SviRaw {
+            a: self.delta + 0.5 * self.omega * (1.0 - self.rho * self.rho),
+            b: 0.5 * self.omega * self.zeta,
+            rho: self.rho,
+            m: self.mu - self.rho / self.zeta,
+            sigma: (1.0 - self.rho * self.rho).sqrt() / self.zeta,
+        }
+    }
+}
+
+// Chosen through the smile_weighting config in main, which uses Vega.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmileWeighting {
+    Uniform,
+    // Black-Scholes vega at the quoted IV, so near-the-money strikes dominate.
+    Vega,
+    // (vega / bid-ask spread)^2, roughly one over the squared IV spread, so
+    // tight quotes dominate. Strikes without a two-sided quote are dropped.
+    Spread,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SmilePoint {
+    pub log_moneyness: f64,
+    pub iv: f64,
+    pub weight: f64,
+}
+
+// Errors in IV units, comparing the fitted smile to the quotes.
+#[derive(Debug, Clone, Copy)]
+pub struct SviFitQuality {
+    pub n: usize,
+    pub rmse: f64,
+    pub weighted_rmse: f64,
+    pub max_abs_error: f64,
+    pub r_squared: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SmileFit {
+    pub expiration: NaiveDate,
+    pub time_to_expiry: f64,
+    pub forward: f64,
+    pub raw: SviRaw,
+    pub quality: SviFitQuality,
+    pub converged: bool,
+    // Nelder-Mead iterations of the best starting point.
+    pub iterations: usize,
+}
+
+impl SmileFit {
+    pub fn natural(&self) -> SviNatural {
+        self.raw.to_natural()
+    }
+
+    pub fn iv_at(&self, log_moneyness: f64) -> Option<f64> {
+        self.raw
+            .implied_volatility(log_moneyness, self.time_to_expiry)
+    }
+
+    pub fn iv_at_strike(&self, strike: f64) -> Option<f64> {
+        if strike <= 0.0 {
+            return None;
+        }
+        self.iv_at((strike / self.forward).ln())
+    }
+
+    pub fn atm_iv(&self) -> Option<f64> {
+        self.iv_at(0.0)
+    }
+
+    // d(IV)/dk of the fitted smile, from w = IV^2 T.
+    pub fn iv_slope_at(&self, log_moneyness: f64) -> Option<f64> {
+        let iv = self.iv_at(log_moneyness)?;
+        Some(self.raw.total_variance_slope(log_moneyness) / (2.0 * iv * self.time_to_expiry))
+    }
+}
+
+// One point per strike from the out-of-the-money side (puts below the forward,
+// calls above), falling back to the other type. Weights are scaled to mean one.
+pub fn smile_points(
+    slice: &ExpirySlice,
+    forwards: &ForwardCurve,
+    forward: f64,
+    weighting: SmileWeighting,
+) -> Vec<SmilePoint> {
+    let mut points: Vec<SmilePoint> = slice
+        .strikes()
+        .filter_map(|pair| {
+            let strike = pair.strike;
+            let option = pair.out_of_the_money(forward, |o| o.implied_volatility > 0.0)?;
+            let weight = match weighting {
+                SmileWeighting::Uniform => 1.0,
+                SmileWeighting::Vega => BlackScholes.vega(&forwards.inputs_for(option)),
+                SmileWeighting::Spread => {
+                    let spread = option.ask - option.bid;
+                    if option.bid <= 0.0 || spread <= 0.0 {
+                        return None;
+                    }
+                    (BlackScholes.vega(&forwards.inputs_for(option)) / spread).powi(2)
+                }
+            };
+            (weight > 0.0 && weight.is_finite()).then_some(SmilePoint {
+                log_moneyness: (strike / forward).ln(),
+                iv: option.implied_volatility,
+                weight,
+            })
+        })
+        .collect();
+
+    let total_weight: f64 = points.iter().map(|p| p.weight).sum();
+    let scale = points.len() as f64 / total_weight;
+    for point in &mut points {
+        point.weight *= scale;
+    }
+    points
+}
+
+// Best raw SVI for fixed (m, sigma) by weighted least squares on
+// (a, d, c) = (a, b rho sigma, b sigma), projected onto b >= 0, |rho| < 1 and
+// non-negative minimum variance.
+fn inner_fit(points: &[SmilePoint], time_to_expiry: f64, m: f64, sigma: f64) -> Option<SviRaw> {
+    let mut normal = vec![vec![0.0; 3]; 3];
+    let mut rhs = [0.0; 3];
+    for point in points {
+        let y = (point.log_moneyness - m) / sigma;
+        let x = [1.0, y, (y * y + 1.0).sqrt()];
+        let w = point.iv.powi(2) * time_to_expiry;
+        for i in 0..3 {
+            rhs[i] += point.weight * x[i] * w;
+            for j in 0..3 {
+                normal[i][j] += point.weight * x[i] * x[j];
+            }
+        }
+    }
+    let inverse = invert_matrix(&normal)?;
+    let solve = |i: usize| (0..3).map(|j| inverse[i][j] * rhs[j]).sum::<f64>();
+    let mut a = solve(0);
+    let c = solve(2).max(0.0);
+    let d = solve(1).clamp(-0.999 * c, 0.999 * c);
+    a = a.max(-(c * c - d * d).sqrt());
+
+    Some(SviRaw {
+        a,
+        b: c / sigma,
+        rho: if c > 0.0 { d / c } else { 0.0 },
+        m,
+        sigma,
+    })
+}
+
+fn weighted_sse(points: &[SmilePoint], time_to_expiry: f64, raw: &SviRaw) -> f64 {
+    points
+        .iter()
+        .map(|p| {
+            p.weight * (raw.total_variance(p.log_moneyness) - p.iv.powi(2) * time_to_expiry).powi(2)
+        })
+        .sum()
+}
+
+fn fit_quality(points: &[SmilePoint], time_to_expiry: f64, raw: &SviRaw) -> SviFitQuality {
+    let n = points.len();
+    let mean_iv = points.iter().map(|p| p.iv).sum::<f64>() / n as f64;
+    let mut sse = 0.0;
+    let mut weighted_sse = 0.0;
+    let mut sst = 0.0;
+    let mut max_abs_error: f64 = 0.0;
+    for point in points {
+        let fitted = raw
+            .implied_volatility(point.log_moneyness, time_to_expiry)
+            .unwrap_or(0.0);
+        let error = fitted - point.iv;
+        sse += error * error;
+        weighted_sse += point.weight * error * error;
+        sst += (point.iv - mean_iv).powi(2);
+        max_abs_error = max_abs_error.max(error.abs());
+    }
+    SviFitQuality {
+        n,
+        rmse: (sse / n as f64).sqrt(),
+        weighted_rmse: (weighted_sse / n as f64).sqrt(),
+        max_abs_error,
+        r_squared: if sst > 0.0 { 1.0 - sse / sst } else { 0.0 },
+    }
+}
+
+// Raw SVI fit to one expiry's smile. Returns the parameters, fit quality and
+// the optimiser result of the best starting point. None with fewer than five
+// points.
+pub fn fit_svi(
+    points: &[SmilePoint],
+    time_to_expiry: f64,
+) -> Option<(SviRaw, SviFitQuality, Minimum)> {
+    if points.len() < MIN_POINTS || time_to_expiry <= 0.0 {
+        return None;
+    }
+
+    // sigma is searched on a log scale to keep it positive.
+    let objective = |params: &[f64]| {
+        let sigma = params[1].exp();
+        match inner_fit(points, time_to_expiry, params[0], sigma) {
+            Some(raw) => weighted_sse(points, time_to_expiry, &raw),
+            None => f64::INFINITY,
+        }
+    };
+    let m_start = points
+        .iter()
+        .min_by(|a, b| a.iv.total_cmp(&b.iv))
+        .map_or(0.0, |p| p.log_moneyness);
+    let optimizer = NelderMead {
+        initial_step: 0.5,
+        ..NelderMead::default()
+    };
+
+    let best = [0.05_f64, 0.2, 0.5]
+        .iter()
+        .map(|sigma| optimizer.minimize(objective, &[m_start, sigma.ln()]))
+        .min_by(|a, b| a.value.total_cmp(&b.value))?;
+    let raw = inner_fit(points, time_to_expiry, best.point[0], best.point[1].exp())?;
+    Some((raw, fit_quality(points, time_to_expiry, &raw), best))
+}
+
+// SVI fit for one expiry of a chain, in moneyness against that expiry's
+// forward.
+pub fn fit_expiry_smile(
+    slice: &ExpirySlice,
+    forwards: &ForwardCurve,
+    weighting: SmileWeighting,
+) -> Option<SmileFit> {
+    let t = slice.time_to_expiry;
+    let expiration = slice.expiration;
+    let forward = forwards.forward(expiration, t);
+    let points = smile_points(slice, forwards, forward, weighting);
+    let (raw, quality, minimum) = fit_svi(&points, t)?;
+    Some(SmileFit {
+        expiration,
+        time_to_expiry: t,
+        forward,
+        raw,
+        quality,
+        converged: minimum.converged,
+        iterations: minimum.iterations,
+    })
+}
+
+// SVI fits for every expiry with enough strikes, sorted by time to expiry.
+pub fn fit_chain_smiles(
+    chain: &OptionChain,
+    forwards: &ForwardCurve,
+    weighting: SmileWeighting,
+) -> Vec<SmileFit> {
+    chain
+        .expiries()
+        .filter_map(|slice| fit_expiry_smile(slice, forwards, weighting))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW: SviRaw = SviRaw {
+        a: 0.02,
+        b: 0.12,
+        rho: -0.55,
+        m: 0.03,
+        sigma: 0.15,
+    };
+
+    #[test]
+    fn raw_and_natural_parameterisations_round_trip() {
+        let natural = RAW.to_natural();
+        let back = natural.to_raw();
+        for (original, round_trip) in [
+            (RAW.a, back.a),
+            (RAW.b, back.b),
+            (RAW.rho, back.rho),
+            (RAW.m, back.m),
+            (RAW.sigma, back.sigma),
+        ] {
+            assert!((original - round_trip).abs() < 1e-12);
+        }
+        for k in [-0.6, -0.2, 0.0, 0.1, 0.5] {
+            assert!((RAW.total_variance(k) - natural.total_variance(k)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn total_variance_slope_matches_finite_difference() {
+        let h = 1e-6;
+        for k in [-0.4, 0.0, 0.03, 0.3] {
+            let numeric = (RAW.total_variance(k + h) - RAW.total_variance(k - h)) / (2.0 * h);
+            assert!((RAW.total_variance_slope(k) - numeric).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn fit_recovers_an_exact_smile() {
+        let t = 0.4;
+        let points: Vec<SmilePoint> = (-8..=6)
+            .map(|i| {
+                let k = 0.05 * i as f64;
+                SmilePoint {
+                    log_moneyness: k,
+                    iv: RAW.implied_volatility(k, t).unwrap(),
+                    weight: 1.0,
+                }
+            })
+            .collect();
+        let (raw, quality, _) = fit_svi(&points, t).unwrap();
+        assert_eq!(quality.n, points.len());
+        assert!(quality.rmse < 1e-5, "{}", quality.rmse);
+        for point in &points {
+            let iv = raw.implied_volatility(point.log_moneyness, t).unwrap();
+            assert!((iv - point.iv).abs() < 1e-4);
+        }
+        assert!(fit_svi(&points[..4], t).is_none());
+    }
+}
@@ -0,0 +1,263 @@
+// ATM implied volatility term structure for one day's chain: ATM IV per
+// listed expiry, interpolated IV at standard tenors, and slope/curvature
+// metrics. Kept per fetch date so the shape can be compared with the IV
+// forecast errors.
+
+use crate::chain::OptionChain;
+use crate::constant_maturity::{atm_term_points, interpolate_atm_iv, AtmPoint};
+use crate::data::{
+    paired_accuracy_series, paired_correlation, CorrelationError, CorrelationMethod,
+};
+use crate::parity::ForwardCurve;
+use crate::time_series::TimeSeries;
+use chrono::NaiveDate;
+
+// 7d, 30d, 60d, 90d, 180d and 1y, in calendar days.
+pub const STANDARD_TENORS_DAYS: [usize; 6] = [7, 30, 60, 90, 180, 365];
+
+// Slopes within this many vol points per year of zero count as flat.
+const FLAT_SLOPE: f64 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TermStructureShape {
+    // Longer-dated IV above shorter-dated, the usual calm-market state.
+    Contango,
+    // Short-dated IV above longer-dated, typical of stressed markets.
+    Backwardation,
+    Flat,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TenorIv {
+    pub days: usize,
+    pub iv: f64,
+    // True when the tenor is outside the listed expiries and the nearest ATM
+    // vol was held flat.
+    pub extrapolated: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TermStructureMetrics {
+    // (IV_180d - IV_30d) per year of maturity.
+    pub slope: f64,
+    // IV_90d - IV_30d, in vol points.
+    pub front_spread: f64,
+    // IV_30d - 2 IV_90d + IV_180d. Positive when the curve is convex.
+    pub curvature: f64,
+    pub shape: TermStructureShape,
+}
+
+#[derive(Debug, Clone)]
+pub struct TermStructure {
+    pub date: NaiveDate,
+    pub points: Vec<AtmPoint>,
+    pub tenors: Vec<TenorIv>,
+}
+
+impl TermStructure {
+    // Interpolated ATM IV at any maturity in calendar days.
+    pub fn iv_at(&self, days: usize) -> Option<f64> {
+        interpolate_atm_iv(&self.points, days as f64 / 365.0).map(|cm| cm.iv)
+    }
+
+    pub fn tenor_iv(&self, days: usize) -> Option<f64> {
+        self.tenors
+            .iter()
+            .find(|tenor| tenor.days == days)
+            .map(|tenor| tenor.iv)
+            .or_else(|| self.iv_at(days))
+    }
+
+    // Change in ATM IV per year of maturity between two tenors.
+    pub fn slope(&self, short_days: usize, long_days: usize) -> Option<f64> {
+        if long_days <= short_days {
+            return None;
+        }
+        let short = self.tenor_iv(short_days)?;
+        let long = self.tenor_iv(long_days)?;
+        Some((long - short) / ((long_days - short_days) as f64 / 365.0))
+    }
+
+    // IV at a tenor inside the listed expiries; None where it would be the
+    // nearest ATM vol held flat.
+    fn interpolated_tenor_iv(&self, days: usize) -> Option<f64> {
+        match self.tenors.iter().find(|tenor| tenor.days == days) {
+            Some(tenor) => (!tenor.extrapolated).then_some(tenor.iv),
+            None => interpolate_atm_iv(&self.points, days as f64 / 365.0)
+                .filter(|cm| !cm.extrapolated)
+                .map(|cm| cm.iv),
+        }
+    }
+
+    // Shape metrics from the 30, 90 and 180 day tenors. None unless all three
+    // are inside the listed expiries, since a flat-extrapolated tenor would
+    // read as a flat curve.
+    pub fn metrics(&self) -> Option<TermStructureMetrics> {
+        let iv_30 = self.interpolated_tenor_iv(30)?;
+        let iv_90 = self.interpolated_tenor_iv(90)?;
+        let iv_180 = self.interpolated_tenor_iv(180)?;
+        let slope = (iv_180 - iv_30) / (150.0 / 365.0);
+        let shape = if slope > FLAT_SLOPE {
+            TermStructureShape::Contango
+        } else if slope < -FLAT_SLOPE {
+            TermStructureShape::Backwardation
+        } else {
+            TermStructureShape::Flat
+        };
+        Some(TermStructureMetrics {
+            slope,
+            front_spread: iv_90 - iv_30,
+            curvature: iv_30 - 2.0 * iv_90 + iv_180,
+            shape,
+        })
+    }
+}
+
+// Term structure from one day's chain. None when no expiry has an ATM IV.
+pub fn build_term_structure(chain: &OptionChain, forwards: &ForwardCurve) -> Option<TermStructure> {
+    let date = chain.date;
+    let points = atm_term_points(chain, forwards);
+    let tenors: Vec<TenorIv> = STANDARD_TENORS_DAYS
+        .iter()
+        .filter_map(|&days| {
+            let cm = interpolate_atm_iv(&points, days as f64 / 365.0)?;
+            Some(TenorIv {
+                days,
+                iv: cm.iv,
+                extrapolated: cm.extrapolated,
+            })
+        })
+        .collect();
+    if tenors.is_empty() {
+        return None;
+    }
+    Some(TermStructure {
+        date,
+        points,
+        tenors,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct ShapeErrorSummary {
+    pub shape: TermStructureShape,
+    pub n: usize,
+    pub mean_iv_error: f64,
+    pub mean_hv_error: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TermStructureErrorStudy {
+    pub by_shape: Vec<ShapeErrorSummary>,
+    // Spearman correlation between the 30d-180d slope and IV error minus HV
+    // error on the same date.
+    pub slope_vs_excess_error: Result<f64, CorrelationError>,
+}
+
+// Does term-structure shape on the forecast date predict how IV does against
+// HV? Uses dates with both accuracy scores and term-structure metrics, so
+// days whose chain does not reach the 180 day tenor are left out.
+pub fn term_structure_error_study(
+    structures: &[TermStructure],
+    iv_accuracy_data: &TimeSeries<f64>,
+    hv_accuracy_data: &TimeSeries<f64>,
+) -> TermStructureErrorStudy {
+    let paired = paired_accuracy_series(iv_accuracy_data, hv_accuracy_data);
+    let joined: Vec<(TermStructureMetrics, f64, f64)> = paired
+        .iter()
+        .filter_map(|(date, (iv_error, hv_error))| {
+            let structure = structures.iter().find(|s| s.date == *date)?;
+            Some((structure.metrics()?, *iv_error, *hv_error))
+        })
+        .collect();
+
+    let by_shape = [
+        TermStructureShape::Contango,
+        TermStructureShape::Flat,
+        TermStructureShape::Backwardation,
+    ]
+    .into_iter()
+    .filter_map(|shape| {
+        let errors: Vec<(f64, f64)> = joined
+            .iter()
+            .filter(|(metrics, _, _)| metrics.shape == shape)
+            .map(|(_, iv_error, hv_error)| (*iv_error, *hv_error))
+            .collect();
+        if errors.is_empty() {
+            return None;
+        }
+        let n = errors.len();
+        Some(ShapeErrorSummary {
+            shape,
+            n,
+            mean_iv_error: errors.iter().map(|e| e.0).sum::<f64>() / n as f64,
+            mean_hv_error: errors.iter().map(|e| e.1).sum::<f64>() / n as f64,
+        })
+    })
+    .collect();
+
+    let slope_pairs: Vec<(f64, f64)> = joined
+        .iter()
+        .map(|(metrics, iv_error, hv_error)| (metrics.slope, iv_error - hv_error))
+        .collect();
+
+    TermStructureErrorStudy {
+        by_shape,
+        slope_vs_excess_error: paired_correlation(&slope_pairs, CorrelationMethod::Spearman),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    // Term structure from ATM vols at the given calendar-day expiries, built
+    // the way `build_term_structure` does from a chain.
+    fn structure(expiries: &[(i64, f64)]) -> TermStructure {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let points: Vec<AtmPoint> = expiries
+            .iter()
+            .map(|&(days, atm_iv)| AtmPoint {
+                expiration: date + Duration::days(days),
+                time_to_expiry: days as f64 / 365.0,
+                forward: 100.0,
+                atm_iv,
+            })
+            .collect();
+        let tenors = STANDARD_TENORS_DAYS
+            .iter()
+            .filter_map(|&days| {
+                let cm = interpolate_atm_iv(&points, days as f64 / 365.0)?;
+                Some(TenorIv {
+                    days,
+                    iv: cm.iv,
+                    extrapolated: cm.extrapolated,
+                })
+            })
+            .collect();
+        TermStructure {
+            date,
+            points,
+            tenors,
+        }
+    }
+
+    #[test]
+    fn classifies_an_upward_curve_as_contango() {
+        let metrics = structure(&[(20, 0.15), (60, 0.17), (120, 0.19), (240, 0.21)])
+            .metrics()
+            .unwrap();
+        assert_eq!(metrics.shape, TermStructureShape::Contango);
+        assert!(metrics.slope > 0.0 && metrics.front_spread > 0.0);
+    }
+
+    #[test]
+    fn no_metrics_when_a_tenor_is_extrapolated() {
+        // Inverted front end, but nothing listed past 60 days: the 90 and 180
+        // day tenors would be the 60 day vol held flat.
+        let short = structure(&[(10, 0.30), (30, 0.25), (60, 0.20)]);
+        assert!(short.tenors.iter().any(|tenor| tenor.extrapolated));
+        assert!(short.metrics().is_none());
+    }
+}
@@ -0,0 +1,358 @@
+// Values indexed by trading date. Points are kept sorted by date with at most
+// one value per date, so lookups are binary searches and joins are a single
+// merge pass instead of hashing date strings.
+
+use chrono::{Datelike, NaiveDate};
+use std::ops::Deref;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeries<T> {
+    points: Vec<(NaiveDate, T)>,
+}
+
+// Calendar buckets for `resample`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    // ISO weeks, Monday to Sunday.
+    Weekly,
+    Monthly,
+}
+
+impl Frequency {
+    fn bucket(self, date: NaiveDate) -> (i32, u32) {
+        match self {
+            Frequency::Weekly => {
+                let week = date.iso_week();
+                (week.year(), week.week())
+            }
+            Frequency::Monthly => (date.year(), date.month()),
+        }
+    }
+}
+
+impl<T> Default for TimeSeries<T> {
+    fn default() -> Self {
+        TimeSeries { points: Vec::new() }
+    }
+}
+
+impl<T> Deref for TimeSeries<T> {
+    type Target = [(NaiveDate, T)];
+
+    fn deref(&self) -> &Self::Target {
+        &self.points
+    }
+}
+
+impl<T> FromIterator<(NaiveDate, T)> for TimeSeries<T> {
+    fn from_iter<I: IntoIterator<Item = (NaiveDate, T)>>(iter: I) -> Self {
+        TimeSeries::from_points(iter.into_iter().collect())
+    }
+}
+
+impl<T> IntoIterator for TimeSeries<T> {
+    type Item = (NaiveDate, T);
+    type IntoIter = std::vec::IntoIter<(NaiveDate, T)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.points.into_iter()
+    }
+}
+
+impl<T> TimeSeries<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Sorts by date. When a date appears more than once the last value wins.
+    pub fn from_points(mut points: Vec<(NaiveDate, T)>) -> Self {
+        points.reverse();
+        points.sort_by_key(|(date, _)| *date);
+        points.dedup_by_key(|(date, _)| *date);
+        TimeSeries { points }
+    }
+
+    // Insert or replace the value at `date`. Appending in date order is O(1).
+    pub fn insert(&mut self, date: NaiveDate, value: T) {
+        match self.points.last() {
+            Some((last, _)) if *last < date => self.points.push((date, value)),
+            None => self.points.push((date, value)),
+            _ => match self.search(date) {
+                Ok(index) => self.points[index].1 = value,
+                Err(index) => self.points.insert(index, (date, value)),
+            },
+        }
+    }
+
+    fn search(&self, date: NaiveDate) -> Result<usize, usize> {
+        self.points.binary_search_by_key(&date, |(d, _)| *d)
+    }
+
+    pub fn get(&self, date: NaiveDate) -> Option<&T> {
+        self.search(date).ok().map(|index| &self.points[index].1)
+    }
+
+    pub fn contains_date(&self, date: NaiveDate) -> bool {
+        self.search(date).is_ok()
+    }
+
+    // Latest value on or before `date`.
+    pub fn as_of(&self, date: NaiveDate) -> Option<&(NaiveDate, T)> {
+        let end = match self.search(date) {
+            Ok(index) => index + 1,
+            Err(index) => index,
+        };
+        self.points[..end].last()
+    }
+
+    pub fn dates(&self) -> impl DoubleEndedIterator<Item = NaiveDate> + '_ {
+        self.points.iter().map(|(date, _)| *date)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &T> {
+        self.points.iter().map(|(_, value)| value)
+    }
+
+    pub fn first_date(&self) -> Option<NaiveDate> {
+        self.points.first().map(|(date, _)| *date)
+    }
+
+    pub fn last_date(&self) -> Option<NaiveDate> {
+        self.points.last().map(|(date, _)| *date)
+    }
+
+    // Points dated in [from, to].
+    pub fn between(&self, from: NaiveDate, to: NaiveDate) -> &[(NaiveDate, T)] {
+        let start = self.points.partition_point(|(date, _)| *date < from);
+        let end = self.points.partition_point(|(date, _)| *date <= to);
+        &self.points[start..end.max(start)]
+    }
+
+    pub fn into_points(self) -> Vec<(NaiveDate, T)> {
+        self.points
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> TimeSeries<U> {
+        TimeSeries {
+            points: self.points.iter().map(|(d, v)| (*d, f(v))).collect(),
+        }
+    }
+
+    pub fn filter(&self, mut keep: impl FnMut(NaiveDate, &T) -> bool) -> Self
+    where
+        T: Clone,
+    {
+        TimeSeries {
+            points: self
+                .points
+                .iter()
+                .filter(|(date, value)| keep(*date, value))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    // Both values on the dates the two series share.
+    pub fn inner_join<U: Clone>(&self, other: &TimeSeries<U>) -> TimeSeries<(T, U)>
+    where
+        T: Clone,
+    {
+        let mut joined = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.points.len() && j < other.points.len() {
+            let (a_date, a) = &self.points[i];
+            let (b_date, b) = &other.points[j];
+            match a_date.cmp(b_date) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    joined.push((*a_date, (a.clone(), b.clone())));
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        TimeSeries { points: joined }
+    }
+
+    // This series restricted to the dates present in `other`.
+    pub fn restrict_to<U>(&self, other: &TimeSeries<U>) -> Self
+    where
+        T: Clone,
+    {
+        self.filter(|date, _| other.contains_date(date))
+    }
+
+    // This series on `other`'s dates, None where it has no value on the day.
+    pub fn align_to<U>(&self, other: &TimeSeries<U>) -> TimeSeries<Option<T>>
+    where
+        T: Clone,
+    {
+        TimeSeries {
+            points: other
+                .dates()
+                .map(|date| (date, self.get(date).cloned()))
+                .collect(),
+        }
+    }
+
+    // This series on `other`'s dates, carrying the last value forward. Dates
+    // before this series starts are dropped; `max_age_days` limits how stale a
+    // carried value may be.
+    pub fn forward_fill_to<U>(&self, other: &TimeSeries<U>, max_age_days: Option<i64>) -> Self
+    where
+        T: Clone,
+    {
+        TimeSeries {
+            points: other
+                .dates()
+                .filter_map(|date| {
+                    let (observed, value) = self.as_of(date)?;
+                    let fresh =
+                        max_age_days.is_none_or(|days| (date - *observed).num_days() <= days);
+                    fresh.then(|| (date, value.clone()))
+                })
+                .collect(),
+        }
+    }
+
+    // Last observation in each week or month, dated on the day it was observed.
+    pub fn resample(&self, frequency: Frequency) -> Self
+    where
+        T: Clone,
+    {
+        let mut points: Vec<(NaiveDate, T)> = Vec::new();
+        for (date, value) in &self.points {
+            match points.last_mut() {
+                Some(last) if frequency.bucket(last.0) == frequency.bucket(*date) => {
+                    *last = (*date, value.clone());
+                }
+                _ => points.push((*date, value.clone())),
+            }
+        }
+        TimeSeries { points }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn series(points: &[(NaiveDate, f64)]) -> TimeSeries<f64> {
+        points.iter().copied().collect()
+    }
+
+    #[test]
+    fn from_points_sorts_and_keeps_the_last_duplicate() {
+        let ts = series(&[
+            (date(2024, 3, 5), 2.0),
+            (date(2024, 3, 1), 1.0),
+            (date(2024, 3, 5), 3.0),
+        ]);
+        assert_eq!(ts.first_date(), Some(date(2024, 3, 1)));
+        assert_eq!(ts.last_date(), Some(date(2024, 3, 5)));
+        assert_eq!(ts.get(date(2024, 3, 5)), Some(&3.0));
+        assert_eq!(ts.len(), 2);
+    }
+
+    #[test]
+    fn as_of_and_between_respect_calendar_bounds() {
+        let ts = series(&[
+            (date(2024, 3, 1), 1.0),
+            (date(2024, 3, 4), 2.0),
+            (date(2024, 3, 8), 3.0),
+        ]);
+        assert_eq!(ts.as_of(date(2024, 2, 29)), None);
+        assert_eq!(ts.as_of(date(2024, 3, 4)), Some(&(date(2024, 3, 4), 2.0)));
+        // Saturday takes Friday's value.
+        assert_eq!(ts.as_of(date(2024, 3, 2)), Some(&(date(2024, 3, 1), 1.0)));
+        assert_eq!(ts.as_of(date(2024, 12, 31)), Some(&(date(2024, 3, 8), 3.0)));
+
+        let inside = ts.between(date(2024, 3, 2), date(2024, 3, 8));
+        assert_eq!(inside, &[(date(2024, 3, 4), 2.0), (date(2024, 3, 8), 3.0)]);
+        assert!(ts.between(date(2024, 3, 8), date(2024, 3, 1)).is_empty());
+    }
+
+    #[test]
+    fn align_to_marks_missing_dates() {
+        let iv = series(&[(date(2024, 3, 1), 0.2), (date(2024, 3, 15), 0.25)]);
+        let calendar = series(&[
+            (date(2024, 3, 1), 0.0),
+            (date(2024, 3, 8), 0.0),
+            (date(2024, 3, 15), 0.0),
+        ]);
+        let aligned = iv.align_to(&calendar);
+        assert_eq!(
+            aligned.into_points(),
+            vec![
+                (date(2024, 3, 1), Some(0.2)),
+                (date(2024, 3, 8), None),
+                (date(2024, 3, 15), Some(0.25)),
+            ]
+        );
+    }
+
+    #[test]
+    fn forward_fill_carries_values_across_gaps() {
+        // Observed on a Friday and the following Thursday.
+        let sparse = series(&[(date(2024, 3, 1), 1.0), (date(2024, 3, 7), 2.0)]);
+        let trading_days = series(&[
+            (date(2024, 2, 29), 0.0),
+            (date(2024, 3, 1), 0.0),
+            (date(2024, 3, 4), 0.0),
+            (date(2024, 3, 8), 0.0),
+            (date(2024, 3, 18), 0.0),
+        ]);
+
+        // Nothing before the first observation; the Monday gets Friday's value
+        // across the weekend.
+        let filled = sparse.forward_fill_to(&trading_days, None);
+        assert_eq!(
+            filled.into_points(),
+            vec![
+                (date(2024, 3, 1), 1.0),
+                (date(2024, 3, 4), 1.0),
+                (date(2024, 3, 8), 2.0),
+                (date(2024, 3, 18), 2.0),
+            ]
+        );
+
+        // With a week's limit the value 11 days stale is dropped.
+        let limited = sparse.forward_fill_to(&trading_days, Some(7));
+        assert_eq!(limited.last_date(), Some(date(2024, 3, 8)));
+        assert_eq!(limited.len(), 3);
+    }
+
+    #[test]
+    fn resample_keeps_the_last_point_per_week_or_month() {
+        let ts = series(&[
+            (date(2024, 12, 23), 1.0),
+            (date(2024, 12, 27), 2.0),
+            // Monday 30 December 2024 starts ISO week 1 of 2025.
+            (date(2024, 12, 30), 3.0),
+            (date(2025, 1, 3), 4.0),
+            (date(2025, 1, 6), 5.0),
+            (date(2025, 1, 31), 6.0),
+        ]);
+        assert_eq!(
+            ts.resample(Frequency::Weekly).into_points(),
+            vec![
+                (date(2024, 12, 27), 2.0),
+                (date(2025, 1, 3), 4.0),
+                (date(2025, 1, 6), 5.0),
+                (date(2025, 1, 31), 6.0),
+            ]
+        );
+        assert_eq!(
+            ts.resample(Frequency::Monthly).into_points(),
+            vec![(date(2024, 12, 30), 3.0), (date(2025, 1, 31), 6.0)]
+        );
+        assert!(TimeSeries::<f64>::new()
+            .resample(Frequency::Monthly)
+            .is_empty());
+    }
+}
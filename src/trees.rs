@@ -0,0 +1,628 @@
+// Lattice pricers for American (and European) options with discrete cash
+// dividends: a Cox-Ross-Rubinstein binomial tree and a log-space trinomial
+// tree. Both implement `OptionPricer`, so they plug into the IV solver the same
+// way Black-Scholes does. Greeks are by finite differences on a control
+// variate (closed-form European price plus lattice early-exercise premium).
+//
+// Discrete dividends use the escrowed model: the tree is built on spot minus
+// the present value of dividends paid before expiry, and the PV of the
+// dividends still to come is added back at each node for exercise decisions.
+
+use crate::api::OptionType;
+use crate::black_scholes::{BlackScholes, Greeks, OptionPricer, PricingInputs};
+use crate::implied_vol::{implied_volatility, ImpliedVolError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExerciseStyle {
+    European,
+    American,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Dividend {
+    // Ex-dividend time in years from the valuation date.
+    pub time: f64,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BinomialTree {
+    pub steps: usize,
+    pub exercise: ExerciseStyle,
+    pub dividends: Vec<Dividend>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrinomialTree {
+    pub steps: usize,
+    pub exercise: ExerciseStyle,
+    pub dividends: Vec<Dividend>,
+}
+
+impl BinomialTree {
+    pub fn american(steps: usize) -> Self {
+        BinomialTree {
+            steps,
+            exercise: ExerciseStyle::American,
+            dividends: Vec::new(),
+        }
+    }
+
+    // Main has no dividend calendar and uses a continuous yield instead.
+    #[allow(dead_code)]
+    pub fn with_dividends(mut self, dividends: Vec<Dividend>) -> Self {
+        self.dividends = dividends;
+        self
+    }
+
+    pub fn implied_volatility(
+        &self,
+        inputs: &PricingInputs,
+        price: f64,
+    ) -> Result<f64, ImpliedVolError> {
+        implied_volatility(self, inputs, price)
+    }
+
+    // The lattice price, or None when the step count is too coarse for the
+    // inputs. `OptionPricer::price` reports that case as NaN.
+    pub fn checked_price(&self, inputs: &PricingInputs) -> Option<f64> {
+        binomial_price(self, inputs, 0.0)
+    }
+}
+
+impl TrinomialTree {
+    pub fn american(steps: usize) -> Self {
+        TrinomialTree {
+            steps,
+            exercise: ExerciseStyle::American,
+            dividends: Vec::new(),
+        }
+    }
+
+    // Main has no dividend calendar and uses a continuous yield instead.
+    #[allow(dead_code)]
+    pub fn with_dividends(mut self, dividends: Vec<Dividend>) -> Self {
+        self.dividends = dividends;
+        self
+    }
+
+    pub fn implied_volatility(
+        &self,
+        inputs: &PricingInputs,
+        price: f64,
+    ) -> Result<f64, ImpliedVolError> {
+        implied_volatility(self, inputs, price)
+    }
+
+    // The lattice price, or None when the step count is too coarse for the
+    // inputs. `OptionPricer::price` reports that case as NaN.
+    pub fn checked_price(&self, inputs: &PricingInputs) -> Option<f64> {
+        trinomial_price(self, inputs, 0.0)
+    }
+}
+
+fn payoff(option_type: OptionType, spot: f64, strike: f64) -> f64 {
+    match option_type {
+        OptionType::Call => (spot - strike).max(0.0),
+        OptionType::Put => (strike - spot).max(0.0),
+    }
+}
+
+// PV at time `t` of the dividends paid in (t, expiry], shifted by `elapsed`.
+fn dividend_pv(dividends: &[Dividend], elapsed: f64, t: f64, expiry: f64, rate: f64) -> f64 {
+    dividends
+        .iter()
+        .map(|d| (d.time - elapsed, d.amount))
+        .filter(|&(time, _)| time > t && time <= expiry)
+        .map(|(time, amount)| amount * (-rate * (time - t)).exp())
+        .sum()
+}
+
+// Zero-vol limit of the lattices: the escrowed spot grows at r - q, so the
+// value is the discounted intrinsic on the forward path, at expiry for a
+// European option and at the best step for an American one.
+fn deterministic_price(
+    exercise: ExerciseStyle,
+    dividends: &[Dividend],
+    inputs: &PricingInputs,
+    elapsed: f64,
+    steps: usize,
+) -> f64 {
+    let expiry = inputs.time_to_expiry;
+    let escrowed_spot = inputs.spot - dividend_pv(dividends, elapsed, 0.0, expiry, inputs.rate);
+    let value_at = |t: f64| {
+        let node = escrowed_spot * ((inputs.rate - inputs.dividend_yield) * t).exp()
+            + dividend_pv(dividends, elapsed, t, expiry, inputs.rate);
+        (-inputs.rate * t).exp() * payoff(inputs.option_type, node, inputs.strike)
+    };
+    match exercise {
+        ExerciseStyle::European => value_at(expiry),
+        ExerciseStyle::American => {
+            let n = steps.max(1);
+            (0..=n)
+                .map(|step| value_at(expiry * step as f64 / n as f64))
+                .fold(0.0, f64::max)
+        }
+    }
+}
+
+// None when the step is too coarse for the carry: the risk-neutral up
+// probability then falls outside [0, 1] and the lattice admits arbitrage.
+fn binomial_price(tree: &BinomialTree, inputs: &PricingInputs, elapsed: f64) -> Option<f64> {
+    let expiry = inputs.time_to_expiry;
+    if expiry <= 0.0 {
+        return Some(payoff(inputs.option_type, inputs.spot, inputs.strike));
+    }
+    if inputs.volatility <= 0.0 {
+        return Some(deterministic_price(
+            tree.exercise,
+            &tree.dividends,
+            inputs,
+            elapsed,
+            tree.steps,
+        ));
+    }
+
+    let n = tree.steps.max(1);
+    let dt = expiry / n as f64;
+    let u = (inputs.volatility * dt.sqrt()).exp();
+    let d = 1.0 / u;
+    let growth = ((inputs.rate - inputs.dividend_yield) * dt).exp();
+    let p = (growth - d) / (u - d);
+    if !(0.0..=1.0).contains(&p) {
+        return None;
+    }
+    let discount = (-inputs.rate * dt).exp();
+    let escrowed_spot =
+        inputs.spot - dividend_pv(&tree.dividends, elapsed, 0.0, expiry, inputs.rate);
+
+    let mut values: Vec<f64> = (0..=n)
+        .map(|i| {
+            let node = escrowed_spot * u.powi(i as i32) * d.powi((n - i) as i32);
+            payoff(inputs.option_type, node, inputs.strike)
+        })
+        .collect();
+
+    for step in (0..n).rev() {
+        let t = step as f64 * dt;
+        let pv = dividend_pv(&tree.dividends, elapsed, t, expiry, inputs.rate);
+        for i in 0..=step {
+            let continuation = discount * (p * values[i + 1] + (1.0 - p) * values[i]);
+            values[i] = match tree.exercise {
+                ExerciseStyle::European => continuation,
+                ExerciseStyle::American => {
+                    let node = escrowed_spot * u.powi(i as i32) * d.powi((step - i) as i32) + pv;
+                    continuation.max(payoff(inputs.option_type, node, inputs.strike))
+                }
+            };
+        }
+    }
+    Some(values[0])
+}
+
+// None when any branch probability is negative, as for the binomial tree.
+fn trinomial_price(tree: &TrinomialTree, inputs: &PricingInputs, elapsed: f64) -> Option<f64> {
+    let expiry = inputs.time_to_expiry;
+    if expiry <= 0.0 {
+        return Some(payoff(inputs.option_type, inputs.spot, inputs.strike));
+    }
+    if inputs.volatility <= 0.0 {
+        return Some(deterministic_price(
+            tree.exercise,
+            &tree.dividends,
+            inputs,
+            elapsed,
+            tree.steps,
+        ));
+    }
+
+    let n = tree.steps.max(1);
+    let dt = expiry / n as f64;
+    let sigma = inputs.volatility;
+    let dx = sigma * (3.0 * dt).sqrt();
+    let nu = inputs.rate - inputs.dividend_yield - 0.5 * sigma * sigma;
+    let a = (sigma * sigma * dt + nu * nu * dt * dt) / (dx * dx);
+    let b = nu * dt / dx;
+    let pu = 0.5 * (a + b);
+    let pd = 0.5 * (a - b);
+    let pm = 1.0 - a;
+    if pu < 0.0 || pd < 0.0 || pm < 0.0 {
+        return None;
+    }
+    let discount = (-inputs.rate * dt).exp();
+    let escrowed_spot =
+        inputs.spot - dividend_pv(&tree.dividends, elapsed, 0.0, expiry, inputs.rate);
+
+    // Node j at a step with offset k = j - step sits at log price ln(S*) + k * dx.
+    let node_spot =
+        |step: usize, j: usize| -> f64 { escrowed_spot * ((j as f64 - step as f64) * dx).exp() };
+
+    let mut values: Vec<f64> = (0..=2 * n)
+        .map(|j| payoff(inputs.option_type, node_spot(n, j), inputs.strike))
+        .collect();
+
+    for step in (0..n).rev() {
+        let t = step as f64 * dt;
+        let pv = dividend_pv(&tree.dividends, elapsed, t, expiry, inputs.rate);
+        let next: Vec<f64> = (0..=2 * step)
+            .map(|j| {
+                let continuation =
+                    discount * (pu * values[j + 2] + pm * values[j + 1] + pd * values[j]);
+                match tree.exercise {
+                    ExerciseStyle::European => continuation,
+                    ExerciseStyle::American => continuation.max(payoff(
+                        inputs.option_type,
+                        node_spot(step, j) + pv,
+                        inputs.strike,
+                    )),
+                }
+            })
+            .collect();
+        values = next;
+    }
+    Some(values[0])
+}
+
+// Finite-difference greeks. `price(inputs, elapsed)` prices with the valuation
+// date moved forward by `elapsed` years, so theta, charm and veta also move
+// the dividend dates closer.
+fn finite_difference_greeks<F>(inputs: &PricingInputs, price: F) -> Greeks
+where
+    F: Fn(&PricingInputs, f64) -> f64,
+{
+    // Second differences amplify lattice noise by 1/h^2, so they use wider bumps
+    // than the first-order greeks.
+    let ds = 0.01 * inputs.spot;
+    let ds2 = 0.03 * inputs.spot;
+    let dv = 0.01;
+    let dv2 = 0.05_f64.min(inputs.volatility / 2.0);
+    let dr = 1e-4;
+    let dt = (1.0 / 365.0_f64).min(inputs.time_to_expiry / 2.0);
+
+    let bump_spot = |i: &PricingInputs, h: f64| PricingInputs {
+        spot: i.spot + h,
+        ..*i
+    };
+    let bump_vol = |i: &PricingInputs, h: f64| i.with_volatility(i.volatility + h);
+    let advance = |i: &PricingInputs| PricingInputs {
+        time_to_expiry: i.time_to_expiry - dt,
+        ..*i
+    };
+
+    let delta_at = |i: &PricingInputs, elapsed: f64| {
+        (price(&bump_spot(i, ds), elapsed) - price(&bump_spot(i, -ds), elapsed)) / (2.0 * ds)
+    };
+    let vega_at = |i: &PricingInputs, elapsed: f64| {
+        (price(&bump_vol(i, dv), elapsed) - price(&bump_vol(i, -dv), elapsed)) / (2.0 * dv)
+    };
+
+    let base = price(inputs, 0.0);
+    let delta = delta_at(inputs, 0.0);
+    let vega = vega_at(inputs, 0.0);
+    let gamma = (price(&bump_spot(inputs, ds2), 0.0) - 2.0 * base
+        + price(&bump_spot(inputs, -ds2), 0.0))
+        / (ds2 * ds2);
+    let rho = (price(
+        &PricingInputs {
+            rate: inputs.rate + dr,
+            ..*inputs
+        },
+        0.0,
+    ) - price(
+        &PricingInputs {
+            rate: inputs.rate - dr,
+            ..*inputs
+        },
+        0.0,
+    )) / (2.0 * dr);
+    let dividend_rho = (price(
+        &PricingInputs {
+            dividend_yield: inputs.dividend_yield + dr,
+            ..*inputs
+        },
+        0.0,
+    ) - price(
+        &PricingInputs {
+            dividend_yield: inputs.dividend_yield - dr,
+            ..*inputs
+        },
+        0.0,
+    )) / (2.0 * dr);
+    let vanna = (delta_at(&bump_vol(inputs, dv2), 0.0) - delta_at(&bump_vol(inputs, -dv2), 0.0))
+        / (2.0 * dv2);
+    let volga = (price(&bump_vol(inputs, dv2), 0.0) - 2.0 * base
+        + price(&bump_vol(inputs, -dv2), 0.0))
+        / (dv2 * dv2);
+
+    let (theta, charm, veta) = if dt > 0.0 {
+        let later = advance(inputs);
+        (
+            (price(&later, dt) - base) / dt,
+            (delta_at(&later, dt) - delta) / dt,
+            (vega_at(&later, dt) - vega) / dt,
+        )
+    } else {
+        (0.0, 0.0, 0.0)
+    };
+
+    Greeks {
+        delta,
+        vega,
+        theta,
+        rho,
+        dividend_rho,
+        gamma,
+        vanna,
+        volga,
+        charm,
+        veta,
+    }
+}
+
+fn finite_difference_vega<F>(inputs: &PricingInputs, price: F) -> f64
+where
+    F: Fn(&PricingInputs) -> f64,
+{
+    let dv = 0.01_f64.min(inputs.volatility / 2.0).max(1e-4);
+    (price(&inputs.with_volatility(inputs.volatility + dv))
+        - price(&inputs.with_volatility(inputs.volatility - dv)))
+        / (2.0 * dv)
+}
+
+// Black-Scholes on the escrowed spot, the European analogue of the lattices.
+fn escrowed_black_scholes(dividends: &[Dividend], inputs: &PricingInputs, elapsed: f64) -> f64 {
+    let pv = dividend_pv(dividends, elapsed, 0.0, inputs.time_to_expiry, inputs.rate);
+    BlackScholes.price(&PricingInputs {
+        spot: inputs.spot - pv,
+        ..*inputs
+    })
+}
+
+// Control-variate price for finite-difference greeks: the closed-form European
+// price plus the lattice early-exercise premium. The lattice discretisation
+// error largely cancels in the premium, so bumped greeks stay smooth.
+fn control_variate_price<F>(
+    exercise: ExerciseStyle,
+    dividends: &[Dividend],
+    inputs: &PricingInputs,
+    elapsed: f64,
+    lattice: F,
+) -> f64
+where
+    F: Fn(ExerciseStyle, &PricingInputs, f64) -> f64,
+{
+    let european = escrowed_black_scholes(dividends, inputs, elapsed);
+    match exercise {
+        ExerciseStyle::European => european,
+        ExerciseStyle::American => {
+            european + lattice(ExerciseStyle::American, inputs, elapsed)
+                - lattice(ExerciseStyle::European, inputs, elapsed)
+        }
+    }
+}
+
+impl OptionPricer for BinomialTree {
+    fn price(&self, inputs: &PricingInputs) -> f64 {
+        self.checked_price(inputs).unwrap_or(f64::NAN)
+    }
+
+    fn greeks(&self, inputs: &PricingInputs) -> Greeks {
+        finite_difference_greeks(inputs, |i, elapsed| {
+            control_variate_price(
+                self.exercise,
+                &self.dividends,
+                i,
+                elapsed,
+                |exercise, i, elapsed| {
+                    binomial_price(
+                        &BinomialTree {
+                            exercise,
+                            ..self.clone()
+                        },
+                        i,
+                        elapsed,
+                    )
+                    .unwrap_or(f64::NAN)
+                },
+            )
+        })
+    }
+
+    fn vega(&self, inputs: &PricingInputs) -> f64 {
+        finite_difference_vega(inputs, |i| self.price(i))
+    }
+}
+
+impl OptionPricer for TrinomialTree {
+    fn price(&self, inputs: &PricingInputs) -> f64 {
+        self.checked_price(inputs).unwrap_or(f64::NAN)
+    }
+
+    fn greeks(&self, inputs: &PricingInputs) -> Greeks {
+        finite_difference_greeks(inputs, |i, elapsed| {
+            control_variate_price(
+                self.exercise,
+                &self.dividends,
+                i,
+                elapsed,
+                |exercise, i, elapsed| {
+                    trinomial_price(
+                        &TrinomialTree {
+                            exercise,
+                            ..self.clone()
+                        },
+                        i,
+                        elapsed,
+                    )
+                    .unwrap_or(f64::NAN)
+                },
+            )
+        })
+    }
+
+    fn vega(&self, inputs: &PricingInputs) -> f64 {
+        finite_difference_vega(inputs, |i| self.price(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(option_type: OptionType, strike: f64) -> PricingInputs {
+        PricingInputs {
+            option_type,
+            spot: 100.0,
+            strike,
+            time_to_expiry: 0.75,
+            rate: 0.05,
+            dividend_yield: 0.02,
+            volatility: 0.25,
+        }
+    }
+
+    fn european_binomial(steps: usize) -> BinomialTree {
+        BinomialTree {
+            exercise: ExerciseStyle::European,
+            ..BinomialTree::american(steps)
+        }
+    }
+
+    fn european_trinomial(steps: usize) -> TrinomialTree {
+        TrinomialTree {
+            exercise: ExerciseStyle::European,
+            ..TrinomialTree::american(steps)
+        }
+    }
+
+    #[test]
+    fn european_lattices_converge_to_black_scholes() {
+        for option_type in [OptionType::Call, OptionType::Put] {
+            for strike in [85.0, 100.0, 115.0] {
+                let contract = inputs(option_type, strike);
+                let exact = BlackScholes.price(&contract);
+                let coarse = (european_binomial(50).price(&contract) - exact).abs();
+                let fine = (european_binomial(800).price(&contract) - exact).abs();
+                assert!(
+                    fine < 0.01,
+                    "binomial {:?} {}: {}",
+                    option_type,
+                    strike,
+                    fine
+                );
+                assert!(fine < coarse);
+                let trinomial = (european_trinomial(400).price(&contract) - exact).abs();
+                assert!(trinomial < 0.01, "trinomial {:?} {}", option_type, strike);
+            }
+        }
+    }
+
+    #[test]
+    fn american_prices_bound_european_ones() {
+        let put = inputs(OptionType::Put, 110.0);
+        let european = BlackScholes.price(&put);
+        let american = BinomialTree::american(500).price(&put);
+        assert!(american > european + 0.05);
+        assert!(american >= 10.0);
+        let trinomial = TrinomialTree::american(300).price(&put);
+        assert!((trinomial - american).abs() < 0.02);
+
+        // Without dividends early exercise of a call is never optimal.
+        let call = PricingInputs {
+            dividend_yield: 0.0,
+            ..inputs(OptionType::Call, 100.0)
+        };
+        let american = BinomialTree::american(800).price(&call);
+        assert!((american - BlackScholes.price(&call)).abs() < 0.01);
+    }
+
+    #[test]
+    fn escrowed_dividends_match_black_scholes_on_adjusted_spot() {
+        let dividends = vec![Dividend {
+            time: 0.3,
+            amount: 1.5,
+        }];
+        let call = PricingInputs {
+            dividend_yield: 0.0,
+            ..inputs(OptionType::Call, 100.0)
+        };
+        let tree = european_binomial(800).with_dividends(dividends.clone());
+        let exact = escrowed_black_scholes(&dividends, &call, 0.0);
+        assert!((tree.price(&call) - exact).abs() < 0.01);
+    }
+
+    #[test]
+    fn implied_volatility_round_trips_american_prices() {
+        let tree = BinomialTree::american(200);
+        let put = inputs(OptionType::Put, 105.0);
+        let price = tree.price(&put);
+        let solved = tree.implied_volatility(&put, price).unwrap();
+        assert!((solved - put.volatility).abs() < 1e-6);
+    }
+
+    #[test]
+    fn zero_volatility_prices_are_discounted_forward_intrinsic() {
+        let call = PricingInputs {
+            volatility: 0.0,
+            ..inputs(OptionType::Call, 95.0)
+        };
+        let forward_intrinsic = BlackScholes.price(&call);
+        let expected = 100.0 * (-0.02f64 * 0.75).exp() - 95.0 * (-0.05f64 * 0.75).exp();
+        assert!((forward_intrinsic - expected).abs() < 1e-12);
+        assert!((european_binomial(100).price(&call) - expected).abs() < 1e-12);
+        assert!((european_trinomial(100).price(&call) - expected).abs() < 1e-12);
+        // With r > q the discounted intrinsic grows along the path, so an
+        // American call is worth the same.
+        let american = BinomialTree::american(100).price(&call);
+        assert!((american - expected).abs() < 1e-12);
+
+        let dividends = vec![Dividend {
+            time: 0.3,
+            amount: 2.0,
+        }];
+        let pv = 2.0 * (-0.05f64 * 0.3).exp();
+        let with_dividend = european_binomial(100)
+            .with_dividends(dividends.clone())
+            .price(&call);
+        let expected = (100.0 - pv) * (-0.02f64 * 0.75).exp() - 95.0 * (-0.05f64 * 0.75).exp();
+        assert!((with_dividend - expected).abs() < 1e-12);
+        // Exercise before the dividend is allowed, so never worth less.
+        let american = TrinomialTree::american(100)
+            .with_dividends(dividends)
+            .price(&call);
+        assert!(american >= expected);
+        assert!(american >= 5.0);
+
+        let expired = PricingInputs {
+            time_to_expiry: 0.0,
+            ..call
+        };
+        assert_eq!(european_binomial(100).price(&expired), 5.0);
+    }
+
+    #[test]
+    fn coarse_lattices_with_invalid_probabilities_have_no_price() {
+        // One yearly step at 1% vol cannot carry a 20% rate: the binomial up
+        // probability exceeds 1 and the trinomial middle one goes negative.
+        let call = PricingInputs {
+            time_to_expiry: 1.0,
+            rate: 0.2,
+            dividend_yield: 0.0,
+            volatility: 0.01,
+            ..inputs(OptionType::Call, 100.0)
+        };
+        assert_eq!(BinomialTree::american(1).checked_price(&call), None);
+        assert_eq!(TrinomialTree::american(1).checked_price(&call), None);
+        assert!(BinomialTree::american(1).price(&call).is_nan());
+        assert!(european_trinomial(1).price(&call).is_nan());
+        assert!(BinomialTree::american(500).checked_price(&call).is_some());
+
+        // Any vol below 20% is unpriceable on that step, so solving for a price
+        // just above the zero-vol bound reports the failure.
+        let price = BlackScholes.price(&call.with_volatility(0.05));
+        assert!(matches!(
+            BinomialTree::american(1).implied_volatility(&call, price),
+            Err(ImpliedVolError::PricingFailed { .. })
+        ));
+    }
+}